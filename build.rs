@@ -9,4 +9,22 @@ fn main() {
         .file("LFMF/src/WiRoot.cpp")
         .file("LFMF/src/wofz.cpp")
         .compile("lfmf");
+
+    #[cfg(feature = "capi")]
+    generate_header();
+}
+
+#[cfg(feature = "capi")]
+fn generate_header() {
+    println!("cargo:rerun-if-changed=src/capi.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    // Only the C API's own module is parsed, so that the bindings to LFMF aren't exported.
+    let config =
+        cbindgen::Config::from_file("cbindgen.toml").expect("Could not read cbindgen.toml.");
+    cbindgen::Builder::new()
+        .with_config(config)
+        .with_src("src/capi.rs")
+        .generate()
+        .expect("Could not generate the C header.")
+        .write_to_file("include/wave_propagation.h");
 }
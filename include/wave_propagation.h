@@ -0,0 +1,130 @@
+#ifndef WAVE_PROPAGATION_H
+#define WAVE_PROPAGATION_H
+
+/* Generated by cbindgen from src/capi.rs with the `capi` feature, don't edit it by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define WP_SUCCESS 0
+
+#define WP_ERROR__TX_TERMINAL_HEIGHT 1000
+
+#define WP_ERROR__RX_TERMINAL_HEIGHT 1001
+
+#define WP_ERROR__FREQUENCY 1002
+
+#define WP_ERROR__TX_POWER 1003
+
+#define WP_ERROR__SURFACE_REFRACTIVITY 1004
+
+#define WP_ERROR__PATH_DISTANCE 1005
+
+#define WP_ERROR__EPSILON 1006
+
+#define WP_ERROR__SIGMA 1007
+
+#define WP_ERROR__POLARIZATION 1008
+
+// A pointer argument was null.
+#define WP_ERROR__NULL_POINTER 2000
+
+// A segment length isn't positive.
+#define WP_ERROR__SEGMENT_LENGTH 2001
+
+// The path has no segments.
+#define WP_ERROR__EMPTY_PATH 2002
+
+// The distance isn't within the path.
+#define WP_ERROR__DISTANCE 2003
+
+// The field strength doesn't fall to the minimum within the path.
+#define WP_ERROR__NOT_FOUND 2004
+
+// The terrain isn't one of the `WP_TERRAIN__` values.
+#define WP_ERROR__TERRAIN 2005
+
+#define WP_TERRAIN__LAND 0
+
+#define WP_TERRAIN__SEA 1
+
+// A path from the transmitter made of segments of homogeneous ground.
+typedef struct WP_Path WP_Path;
+
+// The Millington field strength at a distance, with the results derived from it the same way LFMF does.
+typedef struct WP_Result {
+  double A_btl__db;
+  double E_dBuVm;
+  double P_rx__dbm;
+  // The sum going from the transmitter to the receiver in dB(uV)/m.
+  double E_forward__dBuVm;
+  // The sum going from the receiver to the transmitter in dB(uV)/m.
+  double E_reverse__dBuVm;
+} WP_Result;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Makes an empty path at `angle` degrees with the parameters of our project.
+// It must be freed with `wp_path_free`.
+struct WP_Path *wp_path_new(double angle);
+
+// # Safety
+// `path` must be null or come from `wp_path_new`, and must not be used afterwards.
+void wp_path_free(struct WP_Path *path);
+
+// Sets the parameters that all the segments of the path share, including those already added.
+//
+// # Safety
+// `path` must be null or come from `wp_path_new`.
+int wp_path_set_parameters(struct WP_Path *path,
+                           double h_tx__meter,
+                           double h_rx__meter,
+                           double f__mhz,
+                           double P_tx__watt,
+                           double N_s,
+                           int pol);
+
+// Adds a segment of `length__km` with its own ground constants after the last one.
+//
+// # Safety
+// `path` must be null or come from `wp_path_new`.
+int wp_path_add_segment(struct WP_Path *path, double length__km, double epsilon, double sigma);
+
+// Adds a segment of `length__km` of one of the `WP_TERRAIN__` terrains after the last one.
+//
+// # Safety
+// `path` must be null or come from `wp_path_new`.
+int wp_path_add_terrain(struct WP_Path *path, double length__km, int terrain);
+
+// The length of the path in km, or 0 if `path` is null.
+//
+// # Safety
+// `path` must be null or come from `wp_path_new`.
+double wp_path_length(const struct WP_Path *path);
+
+// Calculates the Millington field strength at `d__km` from the transmitter into `result`.
+//
+// # Safety
+// `path` must be null or come from `wp_path_new`, and `result` must be null or valid for writes.
+int wp_field_strength(const struct WP_Path *path, double d__km, struct WP_Result *result);
+
+// Finds the furthest distance in km at which the field strength is still at least `E_min__dBuVm` into `d__km`.
+//
+// # Safety
+// `path` must be null or come from `wp_path_new`, and `d__km` must be null or valid for writes.
+int wp_max_distance(const struct WP_Path *path,
+                    double E_min__dBuVm,
+                    double *d__km);
+
+// A static description of an error code, for messages.
+const char *wp_error_message(int status);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* WAVE_PROPAGATION_H */
@@ -0,0 +1,361 @@
+//! The C API, built with the `capi` feature, which also generates `include/wave_propagation.h`.
+//!
+//! A path is an opaque `WP_Path` made with `wp_path_new`, given its segments in order from the transmitter
+//! and freed with `wp_path_free`. Every other function returns `WP_SUCCESS` or an error code,
+//! and the codes of LFMF keep their values from `LFMF.h`.
+
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+
+use crate::find_max_distance::{
+    calc_millington_field_strength_for_line_at_km, find_max_distance_for_line,
+};
+use crate::lfmf::{self, LFMF_Error, LFMF_Parameters};
+use crate::propagation_model::Lfmf;
+use crate::terrain::defaults::Terrain;
+use crate::terrain::{Line, LineSegment, Medium, Polarization, Receiver, Transmitter};
+use std::ffi::{c_char, c_double, c_int};
+
+pub const WP_SUCCESS: c_int = 0;
+
+pub const WP_ERROR__TX_TERMINAL_HEIGHT: c_int = 1000;
+pub const WP_ERROR__RX_TERMINAL_HEIGHT: c_int = 1001;
+pub const WP_ERROR__FREQUENCY: c_int = 1002;
+pub const WP_ERROR__TX_POWER: c_int = 1003;
+pub const WP_ERROR__SURFACE_REFRACTIVITY: c_int = 1004;
+pub const WP_ERROR__PATH_DISTANCE: c_int = 1005;
+pub const WP_ERROR__EPSILON: c_int = 1006;
+pub const WP_ERROR__SIGMA: c_int = 1007;
+pub const WP_ERROR__POLARIZATION: c_int = 1008;
+
+/// A pointer argument was null.
+pub const WP_ERROR__NULL_POINTER: c_int = 2000;
+/// A segment length isn't positive.
+pub const WP_ERROR__SEGMENT_LENGTH: c_int = 2001;
+/// The path has no segments.
+pub const WP_ERROR__EMPTY_PATH: c_int = 2002;
+/// The distance isn't within the path.
+pub const WP_ERROR__DISTANCE: c_int = 2003;
+/// The field strength doesn't fall to the minimum within the path.
+pub const WP_ERROR__NOT_FOUND: c_int = 2004;
+/// The terrain isn't one of the `WP_TERRAIN__` values.
+pub const WP_ERROR__TERRAIN: c_int = 2005;
+
+pub const WP_TERRAIN__LAND: c_int = 0;
+pub const WP_TERRAIN__SEA: c_int = 1;
+
+/// A path from the transmitter made of segments of homogeneous ground.
+pub struct WP_Path {
+    line: Line,
+}
+
+/// The Millington field strength at a distance, with the results derived from it the same way LFMF does.
+#[repr(C)]
+pub struct WP_Result {
+    pub A_btl__db: c_double,
+    pub E_dBuVm: c_double,
+    pub P_rx__dbm: c_double,
+    /// The sum going from the transmitter to the receiver in dB(uV)/m.
+    pub E_forward__dBuVm: c_double,
+    /// The sum going from the receiver to the transmitter in dB(uV)/m.
+    pub E_reverse__dBuVm: c_double,
+}
+
+fn lfmf_status(error: &anyhow::Error) -> Option<c_int> {
+    error
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<LFMF_Error>())
+        .map(|error| error.status)
+}
+
+/// Makes an empty path at `angle` degrees with the parameters of our project.
+/// It must be freed with `wp_path_free`.
+#[no_mangle]
+pub extern "C" fn wp_path_new(angle: c_double) -> *mut WP_Path {
+    Box::into_raw(Box::new(WP_Path {
+        line: Line::new(angle),
+    }))
+}
+
+/// # Safety
+/// `path` must be null or come from `wp_path_new`, and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn wp_path_free(path: *mut WP_Path) {
+    if !path.is_null() {
+        drop(Box::from_raw(path));
+    }
+}
+
+/// Sets the parameters that all the segments of the path share, including those already added.
+///
+/// # Safety
+/// `path` must be null or come from `wp_path_new`.
+#[no_mangle]
+pub unsafe extern "C" fn wp_path_set_parameters(
+    path: *mut WP_Path,
+    h_tx__meter: c_double,
+    h_rx__meter: c_double,
+    f__mhz: c_double,
+    P_tx__watt: c_double,
+    N_s: c_double,
+    pol: c_int,
+) -> c_int {
+    let Some(path) = path.as_mut() else {
+        return WP_ERROR__NULL_POINTER;
+    };
+    let parameters = LFMF_Parameters::builder()
+        .h_tx__meter(h_tx__meter)
+        .h_rx__meter(h_rx__meter)
+        .f__mhz(f__mhz)
+        .P_tx__watt(P_tx__watt)
+        .N_s(N_s)
+        .pol(pol)
+        .build();
+    if let Err(error) = parameters {
+        return error.status;
+    }
+    let polarization = Polarization::from_lfmf_code(pol).expect("The builder checked it.");
+    *path.line.transmitter_mut() = Transmitter {
+        height_m: h_tx__meter,
+        power_w: P_tx__watt,
+        frequency_mhz: f__mhz,
+        polarization,
+    };
+    *path.line.receiver_mut() = Receiver {
+        height_m: h_rx__meter,
+    };
+    *path.line.surface_refractivity_mut() = N_s;
+    WP_SUCCESS
+}
+
+/// Adds a segment of `length__km` with its own ground constants after the last one.
+///
+/// # Safety
+/// `path` must be null or come from `wp_path_new`.
+#[no_mangle]
+pub unsafe extern "C" fn wp_path_add_segment(
+    path: *mut WP_Path,
+    length__km: c_double,
+    epsilon: c_double,
+    sigma: c_double,
+) -> c_int {
+    let Some(path) = path.as_mut() else {
+        return WP_ERROR__NULL_POINTER;
+    };
+    if !(length__km.is_finite() && length__km > 0.0) {
+        return WP_ERROR__SEGMENT_LENGTH;
+    }
+    let medium = Medium { epsilon, sigma };
+    let parameters = path
+        .line
+        .lfmf_parameters(medium, path.line.max_distance() + length__km);
+    if let Err(error) = parameters.validate() {
+        return error.status;
+    }
+    path.line.add_segment(LineSegment::new(medium, length__km));
+    WP_SUCCESS
+}
+
+/// Adds a segment of `length__km` of one of the `WP_TERRAIN__` terrains after the last one.
+///
+/// # Safety
+/// `path` must be null or come from `wp_path_new`.
+#[no_mangle]
+pub unsafe extern "C" fn wp_path_add_terrain(
+    path: *mut WP_Path,
+    length__km: c_double,
+    terrain: c_int,
+) -> c_int {
+    let terrain = match terrain {
+        WP_TERRAIN__LAND => Terrain::Ground,
+        WP_TERRAIN__SEA => Terrain::Sea,
+        _ => return WP_ERROR__TERRAIN,
+    };
+    let medium = terrain.medium();
+    wp_path_add_segment(path, length__km, medium.epsilon, medium.sigma)
+}
+
+/// The length of the path in km, or 0 if `path` is null.
+///
+/// # Safety
+/// `path` must be null or come from `wp_path_new`.
+#[no_mangle]
+pub unsafe extern "C" fn wp_path_length(path: *const WP_Path) -> c_double {
+    path.as_ref().map_or(0.0, |path| path.line.max_distance())
+}
+
+/// Calculates the Millington field strength at `d__km` from the transmitter into `result`.
+///
+/// # Safety
+/// `path` must be null or come from `wp_path_new`, and `result` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn wp_field_strength(
+    path: *const WP_Path,
+    d__km: c_double,
+    result: *mut WP_Result,
+) -> c_int {
+    let (Some(path), Some(result)) = (path.as_ref(), result.as_mut()) else {
+        return WP_ERROR__NULL_POINTER;
+    };
+    if path.line.is_empty() {
+        return WP_ERROR__EMPTY_PATH;
+    }
+    if !(d__km > 0.0 && d__km <= path.line.max_distance()) {
+        return WP_ERROR__DISTANCE;
+    }
+    match calc_millington_field_strength_for_line_at_km(&Lfmf, &path.line, d__km) {
+        Ok(millington) => {
+            let transmitter = path.line.transmitter();
+            let E_dBuVm = millington.field_strength;
+            *result = WP_Result {
+                A_btl__db: lfmf::basic_transmission_loss_db(
+                    E_dBuVm,
+                    transmitter.frequency_mhz,
+                    transmitter.power_w,
+                ),
+                E_dBuVm,
+                P_rx__dbm: lfmf::received_power_dbm(E_dBuVm, transmitter.frequency_mhz),
+                E_forward__dBuVm: millington.forward,
+                E_reverse__dBuVm: millington.reverse,
+            };
+            WP_SUCCESS
+        }
+        Err(error) => lfmf_status(&error).unwrap_or(WP_ERROR__DISTANCE),
+    }
+}
+
+/// Finds the furthest distance in km at which the field strength is still at least `E_min__dBuVm` into `d__km`.
+///
+/// # Safety
+/// `path` must be null or come from `wp_path_new`, and `d__km` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn wp_max_distance(
+    path: *const WP_Path,
+    E_min__dBuVm: c_double,
+    d__km: *mut c_double,
+) -> c_int {
+    let (Some(path), Some(d__km)) = (path.as_ref(), d__km.as_mut()) else {
+        return WP_ERROR__NULL_POINTER;
+    };
+    if path.line.is_empty() {
+        return WP_ERROR__EMPTY_PATH;
+    }
+    match find_max_distance_for_line(&Lfmf, E_min__dBuVm, &path.line) {
+        Ok(distance) => {
+            *d__km = distance;
+            WP_SUCCESS
+        }
+        Err(error) => lfmf_status(&error).unwrap_or(WP_ERROR__NOT_FOUND),
+    }
+}
+
+/// A static description of an error code, for messages.
+#[no_mangle]
+pub extern "C" fn wp_error_message(status: c_int) -> *const c_char {
+    let message: &'static [u8] = match status {
+        WP_SUCCESS => b"Success.\0",
+        WP_ERROR__TX_TERMINAL_HEIGHT => {
+            b"TX terminal height is out of the range 0 <= h_tx__meter <= 50.\0"
+        }
+        WP_ERROR__RX_TERMINAL_HEIGHT => {
+            b"RX terminal height is out of the range 0 <= h_rx__meter <= 50.\0"
+        }
+        WP_ERROR__FREQUENCY => b"Frequency is out of the range 0.01 <= f__mhz <= 30.\0",
+        WP_ERROR__TX_POWER => b"Transmit power is out of the range 0 < P_tx__watt.\0",
+        WP_ERROR__SURFACE_REFRACTIVITY => {
+            b"Surface refractivity is out of the range 250 <= N_s <= 400.\0"
+        }
+        WP_ERROR__PATH_DISTANCE => b"Path distance is out of the range 0.001 <= d__km <= 10000.\0",
+        WP_ERROR__EPSILON => b"Epsilon (relative permittivity) is out of the range 1 <= epsilon.\0",
+        WP_ERROR__SIGMA => b"Sigma (conductivity) is out of the range 0 < sigma.\0",
+        WP_ERROR__POLARIZATION => b"Polarization is invalid as it must be either 0 or 1.\0",
+        WP_ERROR__NULL_POINTER => b"A pointer argument was null.\0",
+        WP_ERROR__SEGMENT_LENGTH => b"Segment length must be positive.\0",
+        WP_ERROR__EMPTY_PATH => b"The path has no segments.\0",
+        WP_ERROR__DISTANCE => b"The distance is outside of the path.\0",
+        WP_ERROR__NOT_FOUND => b"The field strength doesn't fall to the minimum within the path.\0",
+        WP_ERROR__TERRAIN => b"Unknown terrain.\0",
+        _ => b"Unknown error code.\0",
+    };
+    message.as_ptr().cast()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn path_matches_library() {
+        let line = Line::builder(0.0)
+            .segment(Terrain::Ground, 38.0)
+            .segment(Terrain::Sea, 61.0)
+            .segment(Terrain::Ground, 191.0)
+            .build()
+            .unwrap();
+        unsafe {
+            let path = wp_path_new(0.0);
+            assert_eq!(
+                wp_path_add_terrain(path, 38.0, WP_TERRAIN__LAND),
+                WP_SUCCESS
+            );
+            assert_eq!(wp_path_add_terrain(path, 61.0, WP_TERRAIN__SEA), WP_SUCCESS);
+            assert_eq!(
+                wp_path_add_terrain(path, 191.0, WP_TERRAIN__LAND),
+                WP_SUCCESS
+            );
+            assert_eq!(wp_path_length(path), 290.0);
+
+            let mut result = std::mem::MaybeUninit::<WP_Result>::uninit();
+            assert_eq!(
+                wp_field_strength(path, 87.0, result.as_mut_ptr()),
+                WP_SUCCESS
+            );
+            let expected =
+                calc_millington_field_strength_for_line_at_km(&Lfmf, &line, 87.0).unwrap();
+            assert_eq!(result.assume_init().E_dBuVm, expected.field_strength);
+
+            let mut distance = 0.0;
+            assert_eq!(wp_max_distance(path, 43.2, &mut distance), WP_SUCCESS);
+            assert_eq!(
+                distance,
+                find_max_distance_for_line(&Lfmf, 43.2, &line).unwrap()
+            );
+            wp_path_free(path);
+        }
+    }
+
+    #[test]
+    fn errors_have_codes() {
+        unsafe {
+            let path = wp_path_new(0.0);
+            let mut distance = 0.0;
+            assert_eq!(
+                wp_max_distance(path, 43.2, &mut distance),
+                WP_ERROR__EMPTY_PATH
+            );
+            assert_eq!(
+                wp_path_add_terrain(path, -1.0, WP_TERRAIN__LAND),
+                WP_ERROR__SEGMENT_LENGTH
+            );
+            assert_eq!(wp_path_add_terrain(path, 1.0, 7), WP_ERROR__TERRAIN);
+            assert_eq!(wp_path_add_segment(path, 1.0, 0.5, 1.0), WP_ERROR__EPSILON);
+            assert_eq!(
+                wp_path_set_parameters(path, 10.0, 10.0, 100.0, 10000.0, 300.0, 1),
+                WP_ERROR__FREQUENCY
+            );
+            assert_eq!(
+                wp_path_add_terrain(path, 10.0, WP_TERRAIN__LAND),
+                WP_SUCCESS
+            );
+            assert_eq!(
+                wp_max_distance(path, 10.0, &mut distance),
+                WP_ERROR__NOT_FOUND
+            );
+            assert_eq!(
+                wp_field_strength(path, 20.0, std::ptr::null_mut()),
+                WP_ERROR__NULL_POINTER
+            );
+            wp_path_free(path);
+        }
+    }
+}
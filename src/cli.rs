@@ -1,37 +1,719 @@
+use std::ffi::OsString;
 use std::path::PathBuf;
+use wave_propagation::file::{Columns, ReadOptions};
+use wave_propagation::mixed_path::MixedPathMethod;
+use wave_propagation::monte_carlo::Distribution;
+use wave_propagation::radials::mask::{Legend, MaskOptions};
+use wave_propagation::radials::vector::RadialOptions;
+use wave_propagation::scenario::{Parameter, Scenario};
+use wave_propagation::skywave::{NightTime, Region, Skywave};
+use wave_propagation::terrain::defaults::Terrain;
+use wave_propagation::terrain::{notation, Line};
 
-use clap::Parser;
+use anyhow::Context;
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
 
+/// Parses the command line arguments.
+/// Arguments that don't start with a subcommand, like `43.2 data.csv`, are parsed as the arguments of `coverage`.
 pub fn parse() -> Cli {
-    Cli::parse()
+    let mut args = std::env::args_os().collect::<Vec<_>>();
+    if let Some(first) = args.get(1).and_then(|arg| arg.to_str()) {
+        let command = Cli::command();
+        let is_subcommand = command
+            .get_subcommands()
+            .any(|subcommand| subcommand.get_name() == first)
+            || first == "help";
+        let is_top_level_flag = matches!(first, "-h" | "--help" | "-V" | "--version");
+        if !is_subcommand && !is_top_level_flag {
+            args.insert(1, OsString::from("coverage"));
+        }
+    }
+    Cli::parse_from(args)
 }
 
 #[derive(Parser)]
 #[clap(author, version, about)]
 pub struct Cli {
-    /// Minimum usable field strength in dB(uV)/m.
-    pub min_field_strength: f64,
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Finds the maximum distance of every line for one or more minimum field strengths.
+    /// This is the default, so `43.2 data.csv` is the same as `coverage 43.2 data.csv`.
+    Coverage(CoverageArgs),
+    /// Calculates the field strength at a distance along a line with Millington's method.
+    Point(PointArgs),
+    /// Lists every term of the two sums of Millington's method at a distance along a line.
+    Explain(ExplainArgs),
+    /// Exports the field strength along lines with Millington's method as csv.
+    Profile(ProfileArgs),
+    /// Finds the maximum distance of every line for a range of values of a parameter.
+    Sweep(SweepArgs),
+    /// Checks that every line of the input can be calculated.
+    Validate(ValidateArgs),
+    /// Shows the lines as they were parsed from the input.
+    Inspect(InspectArgs),
+    /// Ranks how much the maximum distance of every line depends on each of its inputs.
+    Sensitivity(SensitivityArgs),
+    /// Finds the percentiles of the maximum distance of every line under uncertain ground constants and boundaries.
+    MonteCarlo(MonteCarloArgs),
+    /// Runs a local HTTP server that answers JSON queries about paths.
+    Serve(ServeArgs),
+    /// Explores the field strength along the lines interactively in the terminal.
+    Tui(TuiArgs),
+    /// Calculates a run saved by `coverage --save` again and reports any maximum distance that changed.
+    #[cfg(feature = "serde")]
+    Replay(ReplayArgs),
+    /// Compares the maximum distances of two runs saved by `coverage --save`.
+    #[cfg(feature = "serde")]
+    Compare(CompareArgs),
+}
+
+#[derive(Args)]
+pub struct InputArgs {
+    /// Input csv file, `.xlsx`/`.ods` workbook, `.png` mask, GeoJSON/Shapefile of land polygons or `.osm.pbf` extract, or a path like `land:100 sea:50 land:200` or `G100,S50,G200`.
+    /// Defaults to 200 km of land.
+    #[arg(value_parser = parse_input)]
+    pub input: Option<Input>,
+
+    /// Sheet of the input workbook, defaults to the first one.
+    #[arg(long)]
+    pub sheet: Option<String>,
+
+    /// Header of the column of the angles in the input, where `*` matches any text and `|` separates alternatives.
+    #[arg(long, default_value = "degrees")]
+    pub angle_column: String,
+
+    /// Header of the column of the distances in px to the border in the input.
+    #[arg(long, default_value = "till border px")]
+    pub border_column: String,
+
+    /// Headers of the columns of the boundaries in px in the input, in order from the transmitter.
+    #[arg(long, default_value = "land* px|sea* px")]
+    pub boundary_columns: String,
+
+    /// Delimiter of the input csv file, e.g. `;`.
+    #[arg(long, default_value_t = ',', value_parser = parse_delimiter)]
+    pub delimiter: char,
+
+    /// Reads the numbers of the input with a decimal comma, like `12,5`.
+    #[arg(long)]
+    pub decimal_comma: bool,
+
+    #[command(flatten)]
+    pub radials: RadialArgs,
+}
+
+/// How lines are traced along radials over a `.png` mask, land polygons or an OpenStreetMap extract.
+#[derive(Args)]
+#[command(next_help_heading = "Radials")]
+pub struct RadialArgs {
+    /// Position of the transmitter in pixels from the top left corner of a mask, like `512,384`.
+    #[arg(long, value_parser = parse_pair)]
+    pub transmitter_px: Option<(f64, f64)>,
+
+    /// Latitude and longitude of the transmitter in degrees for land polygons or an OpenStreetMap extract, like `37.98,23.73`.
+    #[arg(long, value_parser = parse_pair)]
+    pub transmitter_lat_lon: Option<(f64, f64)>,
+
+    /// GeoJSON or Shapefile of the national boundary, where the lines over land polygons end.
+    #[arg(long)]
+    pub border: Option<PathBuf>,
+
+    /// Length of the lines over land polygons in km, if they end before the border or there is none.
+    #[arg(long)]
+    pub length_km: Option<f64>,
+
+    /// GeoTIFF or ASCII grid of the ground conductivity in mS/m, like a digitised ITU-R P.832 atlas,
+    /// that gives the land of lines over land polygons or an OpenStreetMap extract its media.
+    #[arg(long)]
+    pub conductivity: Option<PathBuf>,
 
-    /// Input csv file.
-    #[arg(value_parser = parse_input_path)]
-    pub input_file: Option<PathBuf>,
+    /// GeoTIFF or ASCII grid of the relative permittivity of the ground, along with `--conductivity`.
+    #[arg(long, requires = "conductivity")]
+    pub permittivity: Option<PathBuf>,
 
-    // #[arg(value_parser = parse_output_path)]
-    // pub output_file: Option<PathBuf>,
+    /// Takes the lakes and other water bodies of an OpenStreetMap extract as sea.
+    #[arg(long)]
+    pub water: bool,
+
+    /// Scale of the mask in km per pixel. Defaults to the one of its world file, like `map.pgw`, in metres.
+    #[arg(long)]
+    pub km_per_px: Option<f64>,
+
+    /// Step of the bearings of the lines in degrees, clockwise from north.
+    #[arg(long, default_value_t = 5.0)]
+    pub step: f64,
+
+    /// Colour of land in the mask.
+    #[arg(long, default_value = "#000000", value_parser = parse_colour)]
+    pub land_colour: [u8; 3],
+
+    /// Colour of sea in the mask.
+    #[arg(long, default_value = "#ffffff", value_parser = parse_colour)]
+    pub sea_colour: [u8; 3],
+
+    /// Colour of the outside of the area in the mask, where the lines end.
+    #[arg(long, default_value = "#ff0000", value_parser = parse_colour)]
+    pub outside_colour: [u8; 3],
+}
+
+impl InputArgs {
+    pub fn read_options(&self) -> ReadOptions {
+        ReadOptions {
+            sheet: self.sheet.clone(),
+            columns: Columns {
+                angle: self.angle_column.clone(),
+                border: self.border_column.clone(),
+                boundaries: self.boundary_columns.clone(),
+            },
+            delimiter: self.delimiter as u8,
+            decimal_comma: self.decimal_comma,
+        }
+    }
+
+    pub fn mask_options(&self) -> anyhow::Result<MaskOptions> {
+        let radials = &self.radials;
+        Ok(MaskOptions {
+            legend: Legend {
+                land: radials.land_colour,
+                sea: radials.sea_colour,
+                outside: radials.outside_colour,
+            },
+            transmitter_px: radials
+                .transmitter_px
+                .context("A mask needs the position of the transmitter with `--transmitter-px`.")?,
+            km_per_px: radials.km_per_px,
+            step_degrees: radials.step,
+        })
+    }
+
+    pub fn radial_options(&self) -> anyhow::Result<RadialOptions> {
+        let radials = &self.radials;
+        let (latitude, longitude) = radials.transmitter_lat_lon.context(
+            "Land polygons and OpenStreetMap extracts need the position of the transmitter with `--transmitter-lat-lon`.",
+        )?;
+        Ok(RadialOptions {
+            latitude,
+            longitude,
+            step_degrees: radials.step,
+            length_km: radials.length_km,
+        })
+    }
+}
+
+/// Values that replace the defaults of our project's parameters.
+#[derive(Args)]
+#[command(next_help_heading = "Scenario")]
+pub struct ScenarioArgs {
+    /// Transmitter height in m.
+    #[arg(long)]
+    pub tx_height: Option<f64>,
+
+    /// Receiver height in m.
+    #[arg(long)]
+    pub rx_height: Option<f64>,
+
+    /// Frequency in MHz.
+    #[arg(long)]
+    pub frequency: Option<f64>,
+
+    /// Transmitter power in W.
+    #[arg(long)]
+    pub tx_power: Option<f64>,
+
+    /// Surface refractivity in N-units.
+    #[arg(long)]
+    pub surface_refractivity: Option<f64>,
+
+    /// Relative permittivity of land.
+    #[arg(long)]
+    pub land_epsilon: Option<f64>,
+
+    /// Conductivity of land in S/m.
+    #[arg(long)]
+    pub land_sigma: Option<f64>,
+
+    /// Relative permittivity of sea.
+    #[arg(long)]
+    pub sea_epsilon: Option<f64>,
+
+    /// Conductivity of sea in S/m.
+    #[arg(long)]
+    pub sea_sigma: Option<f64>,
+}
+
+impl ScenarioArgs {
+    pub fn scenario(&self) -> Scenario {
+        let values = [
+            (Parameter::TxHeight, self.tx_height),
+            (Parameter::RxHeight, self.rx_height),
+            (Parameter::Frequency, self.frequency),
+            (Parameter::TxPower, self.tx_power),
+            (Parameter::SurfaceRefractivity, self.surface_refractivity),
+            (Parameter::Permittivity(Terrain::Ground), self.land_epsilon),
+            (Parameter::Conductivity(Terrain::Ground), self.land_sigma),
+            (Parameter::Permittivity(Terrain::Sea), self.sea_epsilon),
+            (Parameter::Conductivity(Terrain::Sea), self.sea_sigma),
+        ];
+        let mut scenario = Scenario::default();
+        for (parameter, value) in values {
+            if let Some(value) = value {
+                scenario.set(parameter, value);
+            }
+        }
+        scenario
+    }
+}
+
+#[derive(Args)]
+pub struct CoverageArgs {
+    /// Minimum usable field strength in dB(uV)/m, or a comma separated list of them, e.g. `43.2,50,60,70`.
+    #[arg(value_parser = parse_field_strengths)]
+    pub min_field_strength: FieldStrengths,
+
+    #[command(flatten)]
+    pub input: InputArgs,
 
     /// Disables plotting of every result.
     #[arg(long)]
     pub no_plot: bool,
+
+    /// How the field strength along the lines is calculated.
+    #[arg(long, value_enum, default_value_t = Method::Millington)]
+    pub method: Method,
+
+    /// Tabulated curves to evaluate the segments with instead of LFMF, like digitised ITU-R P.368 ones, in a csv file
+    /// with the columns `frequency MHz`, `epsilon`, `sigma S/m`, `distance km` and `field strength dB(uV)/m` for 1 kW.
+    #[arg(long, conflicts_with = "dem")]
+    pub curves: Option<PathBuf>,
+
+    /// SRTM `.hgt` or GeoTIFF elevation tiles, or directories of them, for `--method integral-equation`.
+    /// The lines are cast from `--transmitter-lat-lon` at their angles.
+    #[arg(long, num_args = 1.., required_if_eq("method", "integral-equation"))]
+    pub dem: Vec<PathBuf>,
+
+    #[command(flatten)]
+    pub night: NightArgs,
+
+    /// Saves the run with its lines and scenario to a `.json` or `.bin` file.
+    #[cfg(feature = "serde")]
+    #[arg(long, value_parser = parse_output_path)]
+    pub save: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub scenario: ScenarioArgs,
+}
+
+/// How the field strength along a line is calculated.
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum Method {
+    /// LFMF over a smooth earth for every segment, combined with Millington's method.
+    Millington,
+    /// Kirke's method, continuing the curve of every segment from the field strength at its start.
+    Kirke,
+    /// Suda's method, attenuating the inverse distance field by every segment as if it started at the transmitter.
+    Suda,
+    /// The equivalent distance method of Eckersley and Kirke,
+    /// continuing the curve of every segment from the distance with the field strength at its start.
+    EquivalentDistance,
+    /// The integral equation over the elevation profile of `--dem` with the ground constants of the segments.
+    IntegralEquation,
 }
 
-fn parse_input_path(input: &str) -> Result<PathBuf, String> {
+#[derive(Args)]
+pub struct NightArgs {
+    /// Night mode, which also finds where the groundwave falls to this ratio in dB over the skywave of ITU-R P.1147
+    /// of the same transmitter at `--transmitter-lat-lon`, and limits the coverage to it.
+    #[arg(long)]
+    pub night_ratio: Option<f64>,
+
+    /// Hours after sunset of the skywave, 6 being the annual median of P.1147.
+    #[arg(long, default_value_t = 6.0, requires = "night_ratio")]
+    pub hours_after_sunset: f64,
+
+    /// Hours before sunrise of the skywave, instead of `--hours-after-sunset`.
+    #[arg(long, conflicts_with = "hours_after_sunset", requires = "night_ratio")]
+    pub hours_before_sunrise: Option<f64>,
+
+    /// Percentage of the nights the skywave is exceeded for.
+    #[arg(long, default_value_t = 50.0, requires = "night_ratio")]
+    pub time_percentage: f64,
+
+    /// The ITU region of the transmitter.
+    #[arg(long, value_enum, default_value_t = ItuRegion::One, requires = "night_ratio")]
+    pub region: ItuRegion,
+}
+
+impl NightArgs {
+    /// The skywave of night mode, `None` without it.
+    pub fn skywave(&self) -> Option<Skywave> {
+        self.night_ratio?;
+        let time = match self.hours_before_sunrise {
+            Some(hours) => NightTime::BeforeSunrise(hours),
+            None => NightTime::AfterSunset(self.hours_after_sunset),
+        };
+        Some(Skywave {
+            region: match self.region {
+                ItuRegion::One => Region::One,
+                ItuRegion::Two => Region::Two,
+                ItuRegion::Three => Region::Three,
+            },
+            time,
+            time_percentage: self.time_percentage,
+        })
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum ItuRegion {
+    /// Europe and Africa.
+    #[value(name = "1")]
+    One,
+    /// The Americas.
+    #[value(name = "2")]
+    Two,
+    /// Asia and Oceania.
+    #[value(name = "3")]
+    Three,
+}
+
+impl Method {
+    /// How the segments are combined, `None` for the integral equation that doesn't split the line into them.
+    pub fn mixed_path(self) -> Option<MixedPathMethod> {
+        match self {
+            Self::Millington => Some(MixedPathMethod::Millington),
+            Self::Kirke => Some(MixedPathMethod::Kirke),
+            Self::Suda => Some(MixedPathMethod::Suda),
+            Self::EquivalentDistance => Some(MixedPathMethod::EquivalentDistance),
+            Self::IntegralEquation => None,
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct PointArgs {
+    /// Distance from the transmitter in km.
+    pub distance: f64,
+
+    #[command(flatten)]
+    pub input: InputArgs,
+
+    /// Angle of the line in the input file.
+    #[arg(long)]
+    pub angle: Option<f64>,
+
+    /// Tabulated curves to evaluate the segments with instead of LFMF, in the csv format of `coverage --curves`.
+    #[arg(long)]
+    pub curves: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub scenario: ScenarioArgs,
+}
+
+#[derive(Args)]
+pub struct ExplainArgs {
+    /// Distance from the transmitter in km.
+    pub distance: f64,
+
+    #[command(flatten)]
+    pub input: InputArgs,
+
+    /// Angle of the line in the input file.
+    #[arg(long)]
+    pub angle: Option<f64>,
+
+    /// Prints the terms as JSON instead of a table.
+    #[arg(long)]
+    pub json: bool,
+
+    /// Tabulated curves to evaluate the segments with instead of LFMF, in the csv format of `coverage --curves`.
+    #[arg(long)]
+    pub curves: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub scenario: ScenarioArgs,
+}
+
+#[derive(Args)]
+pub struct ProfileArgs {
+    #[command(flatten)]
+    pub input: InputArgs,
+
+    /// Angle of the line in the input file. Defaults to every line.
+    #[arg(long)]
+    pub angle: Option<f64>,
+
+    /// Distance in km between the samples.
+    #[arg(long, default_value_t = 0.5)]
+    pub step: f64,
+
+    /// Output csv file, defaults to the standard output.
+    #[arg(long, value_parser = parse_output_path)]
+    pub output_file: Option<PathBuf>,
+
+    /// Also plots every profile.
+    #[arg(long)]
+    pub plot: bool,
+
+    /// Tabulated curves to evaluate the segments with instead of LFMF, in the csv format of `coverage --curves`.
+    #[arg(long)]
+    pub curves: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub scenario: ScenarioArgs,
+}
+
+#[derive(Args)]
+pub struct SweepArgs {
+    /// Minimum usable field strength in dB(uV)/m.
+    pub min_field_strength: f64,
+
+    #[command(flatten)]
+    pub input: InputArgs,
+
+    /// The parameter to sweep, e.g. `tx-power` or `land-sigma`.
+    #[arg(long)]
+    pub parameter: Parameter,
+
+    /// First value of the parameter.
+    #[arg(long)]
+    pub from: f64,
+
+    /// Last value of the parameter.
+    #[arg(long)]
+    pub to: f64,
+
+    /// Number of values from `from` to `to`, inclusive.
+    #[arg(long, default_value_t = 5)]
+    pub steps: usize,
+
+    /// Spaces the values evenly in their logarithm instead, which suits conductivities and powers.
+    #[arg(long)]
+    pub log: bool,
+
+    #[command(flatten)]
+    pub scenario: ScenarioArgs,
+}
+
+#[derive(Args)]
+pub struct ValidateArgs {
+    #[command(flatten)]
+    pub input: InputArgs,
+
+    #[command(flatten)]
+    pub scenario: ScenarioArgs,
+}
+
+#[derive(Args)]
+pub struct InspectArgs {
+    #[command(flatten)]
+    pub input: InputArgs,
+
+    #[command(flatten)]
+    pub scenario: ScenarioArgs,
+}
+
+#[derive(Args)]
+pub struct SensitivityArgs {
+    /// Minimum usable field strength in dB(uV)/m.
+    pub min_field_strength: f64,
+
+    #[command(flatten)]
+    pub input: InputArgs,
+
+    /// Relative step by which every LFMF parameter is perturbed up and down.
+    #[arg(long, default_value_t = 0.1)]
+    pub relative_step: f64,
+
+    /// Step in km by which every segment boundary is moved forwards and backwards.
+    /// Defaults to one pixel of our map.
+    #[arg(long)]
+    pub boundary_step: Option<f64>,
+
+    #[command(flatten)]
+    pub scenario: ScenarioArgs,
+}
+
+#[derive(Args)]
+pub struct MonteCarloArgs {
+    /// Minimum usable field strength in dB(uV)/m.
+    pub min_field_strength: f64,
+
+    #[command(flatten)]
+    pub input: InputArgs,
+
+    /// Number of random samples for every line.
+    #[arg(long, default_value_t = 1000)]
+    pub samples: usize,
+
+    /// Seed of the random generator, the same seed gives the same results.
+    #[arg(long, default_value_t = 0)]
+    pub seed: u64,
+
+    /// Distribution of the land permittivity, e.g. `uniform:15:30`.
+    #[arg(long)]
+    pub land_epsilon_distribution: Option<Distribution>,
+
+    /// Distribution of the land conductivity in S/m, e.g. `lognormal:0.003:3`.
+    #[arg(long)]
+    pub land_sigma_distribution: Option<Distribution>,
+
+    /// Distribution of the sea permittivity.
+    #[arg(long)]
+    pub sea_epsilon_distribution: Option<Distribution>,
+
+    /// Distribution of the sea conductivity in S/m.
+    #[arg(long)]
+    pub sea_sigma_distribution: Option<Distribution>,
+
+    /// Distribution of the offset in km of every segment boundary, e.g. `normal:0:0.79` for 1 px of error.
+    #[arg(long)]
+    pub boundary_offset: Option<Distribution>,
+
+    /// Output csv file for the percentile contours.
+    #[arg(long, value_parser = parse_output_path)]
+    pub output_file: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub scenario: ScenarioArgs,
+}
+
+#[derive(Args)]
+pub struct TuiArgs {
+    #[command(flatten)]
+    pub input: InputArgs,
+
+    /// Starting minimum usable field strength in dB(uV)/m.
+    #[arg(long, default_value_t = 43.2)]
+    pub threshold: f64,
+
+    #[command(flatten)]
+    pub scenario: ScenarioArgs,
+}
+
+#[derive(Args)]
+pub struct ServeArgs {
+    /// Address to listen on.
+    #[arg(long, default_value = "127.0.0.1")]
+    pub address: String,
+
+    /// Port to listen on, 0 picks a free one.
+    #[arg(long, default_value_t = 8080)]
+    pub port: u16,
+
+    /// Threads that calculate the requests, defaults to one per CPU.
+    #[arg(long, default_value_t = 0)]
+    pub threads: usize,
+
+    /// Requests calculated at the same time, beyond which requests are turned away.
+    #[arg(long, default_value_t = 4)]
+    pub max_concurrent_requests: usize,
+
+    /// Samples of a single profile.
+    #[arg(long, default_value_t = 100_000)]
+    pub max_samples: usize,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Args)]
+pub struct ReplayArgs {
+    /// The saved run, a `.json` or `.bin` file.
+    pub run: PathBuf,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Args)]
+pub struct CompareArgs {
+    /// The saved run to compare from.
+    pub before: PathBuf,
+
+    /// The saved run to compare to.
+    pub after: PathBuf,
+}
+
+/// A list of field strengths in dB(uV)/m.
+#[derive(Clone)]
+pub struct FieldStrengths(pub Vec<f64>);
+
+fn parse_field_strengths(input: &str) -> Result<FieldStrengths, String> {
+    input
+        .split(',')
+        .map(|value| {
+            value
+                .trim()
+                .parse()
+                .map_err(|_| format!("`{value}` isn't a valid field strength."))
+        })
+        .collect::<Result<_, _>>()
+        .map(FieldStrengths)
+}
+
+/// Where the lines come from.
+#[derive(Clone)]
+pub enum Input {
+    File(PathBuf),
+    /// A `.png` mask of land, sea and outside to trace the lines over.
+    Mask(PathBuf),
+    /// A GeoJSON or Shapefile of land polygons to cast the lines over.
+    Polygons(PathBuf),
+    /// An OpenStreetMap `.osm.pbf` extract whose coastlines the lines are cast over.
+    Osm(PathBuf),
+    /// A single line written in the path notation.
+    Path(Line),
+}
+
+fn parse_input(input: &str) -> Result<Input, String> {
     let path: PathBuf = input
         .parse()
         .map_err(|_| format!("`{input}` isn't a valid path."))?;
     if path.is_file() {
-        Ok(path)
-    } else {
-        Err(format!("`{}` is not a file.", path.display()))
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_lowercase);
+        return Ok(match extension.as_deref() {
+            Some("png") => Input::Mask(path),
+            Some("geojson" | "json" | "shp") => Input::Polygons(path),
+            Some("pbf") => Input::Osm(path),
+            _ => Input::File(path),
+        });
+    }
+    notation::parse(input).map(Input::Path).map_err(|error| {
+        format!("`{input}` is neither a file nor a path like `land:100 sea:50` or `G100,S50`. {error:#}")
+    })
+}
+
+fn parse_pair(input: &str) -> Result<(f64, f64), String> {
+    let coordinates = input
+        .split(',')
+        .map(|value| value.trim().parse::<f64>())
+        .collect::<Result<Vec<_>, _>>();
+    match coordinates.as_deref() {
+        Ok(&[x, y]) => Ok((x, y)),
+        _ => Err(format!("`{input}` isn't a pair of numbers like `512,384`.")),
+    }
+}
+
+fn parse_colour(input: &str) -> Result<[u8; 3], String> {
+    let error = || format!("`{input}` isn't a colour like `#00ff00`.");
+    let hex = input.strip_prefix('#').unwrap_or(input);
+    if hex.len() != 6 || !hex.is_ascii() {
+        return Err(error());
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| error());
+    Ok([channel(0)?, channel(2)?, channel(4)?])
+}
+
+fn parse_delimiter(input: &str) -> Result<char, String> {
+    let input = if input == "\\t" { "\t" } else { input };
+    match input.chars().collect::<Vec<_>>()[..] {
+        [delimiter] if delimiter.is_ascii() => Ok(delimiter),
+        _ => Err(format!(
+            "`{input}` isn't a single ASCII character, or `\\t` for a tab."
+        )),
     }
 }
 
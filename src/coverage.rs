@@ -0,0 +1,325 @@
+//! The results of a coverage run, kept together with the lines and scenario they were calculated from
+//! so that a run can be saved with the `serde` feature, reloaded, compared with another and replayed.
+
+use crate::find_max_distance::find_max_distances_for_line_with;
+use crate::mixed_path::MixedPathMethod;
+use crate::propagation_model::Lfmf;
+use crate::scenario::Scenario;
+use crate::terrain::Line;
+use anyhow::{bail, Context, Result};
+use rayon::prelude::*;
+
+/// The maximum distances of a line for several minimum field strengths.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LineCoverage {
+    /// The line with the scenario already applied.
+    pub line: Line,
+    /// The maximum distance in km for each minimum field strength, `None` where it isn't reached within the line.
+    pub max_distances_km: Vec<Option<f64>>,
+}
+
+impl LineCoverage {
+    /// Fails if the field strength can't be calculated somewhere on the line.
+    pub fn calculate(line: Line, min_field_strengths: &[f64]) -> Result<Self> {
+        Self::calculate_with_method(line, min_field_strengths, MixedPathMethod::Millington)
+    }
+
+    /// Like [`LineCoverage::calculate`], but with the segments combined with `method` instead of Millington's.
+    pub fn calculate_with_method(
+        line: Line,
+        min_field_strengths: &[f64],
+        method: MixedPathMethod,
+    ) -> Result<Self> {
+        // A minimum that isn't reached within the line is `None` without failing the others.
+        let max_distances_km =
+            find_max_distances_for_line_with(min_field_strengths, &line, |distance| {
+                method.field_strength_at_km(&Lfmf, &line, distance)
+            })?;
+        Ok(Self {
+            line,
+            max_distances_km,
+        })
+    }
+}
+
+/// The results of finding the maximum distances of a set of lines.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Coverage {
+    /// The scenario that was applied to the lines.
+    pub scenario: Scenario,
+    /// Minimum usable field strengths in dB(uV)/m.
+    pub min_field_strengths: Vec<f64>,
+    /// How the segments of the lines were combined, Millington's method in runs saved before there was a choice.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub method: MixedPathMethod,
+    pub lines: Vec<LineCoverage>,
+}
+
+/// A maximum distance that is different in two coverages.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Difference {
+    pub angle: f64,
+    pub min_field_strength: f64,
+    /// `None` if it wasn't reached, or the first coverage has no such line or minimum.
+    pub before_km: Option<f64>,
+    /// `None` if it wasn't reached, or the second coverage has no such line or minimum.
+    pub after_km: Option<f64>,
+}
+
+impl Coverage {
+    /// Applies `scenario` to every line and finds their maximum distances in parallel.
+    /// Fails with the error of every line whose field strength can't be calculated.
+    pub fn calculate(
+        lines: &[Line],
+        scenario: Scenario,
+        min_field_strengths: Vec<f64>,
+    ) -> Result<Self> {
+        Self::calculate_with_method(
+            lines,
+            scenario,
+            min_field_strengths,
+            MixedPathMethod::Millington,
+        )
+    }
+
+    /// Like [`Coverage::calculate`], but with the segments combined with `method` instead of Millington's.
+    pub fn calculate_with_method(
+        lines: &[Line],
+        scenario: Scenario,
+        min_field_strengths: Vec<f64>,
+        method: MixedPathMethod,
+    ) -> Result<Self> {
+        let lines = lines.iter().map(|line| scenario.apply(line)).collect();
+        Self::calculate_applied(lines, scenario, min_field_strengths, method)
+    }
+
+    fn calculate_applied(
+        lines: Vec<Line>,
+        scenario: Scenario,
+        min_field_strengths: Vec<f64>,
+        method: MixedPathMethod,
+    ) -> Result<Self> {
+        let results = lines
+            .into_par_iter()
+            .map(|line| {
+                let angle = line.angle();
+                LineCoverage::calculate_with_method(line, &min_field_strengths, method)
+                    .with_context(|| format!("Error for angle {angle}"))
+            })
+            .collect::<Vec<_>>();
+        let errors = results
+            .iter()
+            .filter_map(|result| Some(format!("{:?}", result.as_ref().err()?)))
+            .collect::<Vec<_>>();
+        if !errors.is_empty() {
+            bail!("{}", errors.join("\n\n"));
+        }
+        Ok(Self {
+            scenario,
+            min_field_strengths,
+            method,
+            lines: results.into_iter().collect::<Result<_>>()?,
+        })
+    }
+
+    /// Calculates the maximum distances of the same lines again.
+    /// The result equals `self` exactly when nothing in the calculations has changed since.
+    pub fn replay(&self) -> Result<Self> {
+        let lines = self.lines.iter().map(|line| line.line.clone()).collect();
+        Self::calculate_applied(
+            lines,
+            self.scenario.clone(),
+            self.min_field_strengths.clone(),
+            self.method,
+        )
+    }
+
+    fn max_distance_km(&self, angle: f64, min_field_strength: f64) -> Option<f64> {
+        let line = self
+            .lines
+            .iter()
+            .find(|line| matches(line.line.angle(), angle))?;
+        let index = self
+            .min_field_strengths
+            .iter()
+            .position(|&min_e| matches(min_e, min_field_strength))?;
+        line.max_distances_km[index]
+    }
+
+    /// The maximum distances that differ from those of `other`, matched by angle and minimum field strength
+    /// within [`MATCH_TOLERANCE`], in the order of `self` followed by those only `other` has.
+    pub fn compare(&self, other: &Self) -> Vec<Difference> {
+        let keys = |coverage: &Self| {
+            coverage
+                .lines
+                .iter()
+                .flat_map(|line| {
+                    let angle = line.line.angle();
+                    coverage
+                        .min_field_strengths
+                        .iter()
+                        .map(move |&min_e| (angle, min_e))
+                })
+                .collect::<Vec<_>>()
+        };
+        let mut keys_of_both = keys(self);
+        for key in keys(other) {
+            let known = keys_of_both
+                .iter()
+                .any(|&(angle, min_e)| matches(angle, key.0) && matches(min_e, key.1));
+            if !known {
+                keys_of_both.push(key);
+            }
+        }
+        keys_of_both
+            .into_iter()
+            .filter_map(|(angle, min_field_strength)| {
+                let before_km = self.max_distance_km(angle, min_field_strength);
+                let after_km = other.max_distance_km(angle, min_field_strength);
+                (before_km != after_km).then_some(Difference {
+                    angle,
+                    min_field_strength,
+                    before_km,
+                    after_km,
+                })
+            })
+            .collect()
+    }
+}
+
+/// How far apart angles in degrees and minimum field strengths in dB(uV)/m may be to still be matched,
+/// since the same value can differ in its last bits when it was worked out another way.
+const MATCH_TOLERANCE: f64 = 1e-9;
+
+fn matches(a: f64, b: f64) -> bool {
+    (a - b).abs() <= MATCH_TOLERANCE
+}
+
+#[cfg(feature = "serde")]
+pub use self::format::Format;
+
+#[cfg(feature = "serde")]
+mod format {
+    use super::Coverage;
+    use anyhow::{bail, Context, Result};
+    use std::fs::File;
+    use std::io::{BufReader, BufWriter, Write};
+    use std::path::Path;
+
+    /// How a coverage is stored in a file.
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    pub enum Format {
+        Json,
+        /// The compact binary format of bincode.
+        Bincode,
+    }
+
+    impl Format {
+        /// Picks the format by the extension, `.json` or `.bin`.
+        pub fn from_path(path: &Path) -> Result<Self> {
+            match path.extension().and_then(|extension| extension.to_str()) {
+                Some("json") => Ok(Self::Json),
+                Some("bin") => Ok(Self::Bincode),
+                _ => bail!(
+                    "Can't tell the format of `{}` from its extension, expected `.json` or `.bin`.",
+                    path.display()
+                ),
+            }
+        }
+    }
+
+    impl Coverage {
+        /// Writes the coverage to `path` in the format of its extension.
+        pub fn save(&self, path: &Path) -> Result<()> {
+            let format = Format::from_path(path)?;
+            let file = File::create(path)
+                .with_context(|| format!("Could not create `{}`.", path.display()))?;
+            let mut writer = BufWriter::new(file);
+            match format {
+                Format::Json => serde_json::to_writer_pretty(&mut writer, self)?,
+                Format::Bincode => bincode::serialize_into(&mut writer, self)?,
+            }
+            writer
+                .flush()
+                .with_context(|| format!("Could not write `{}`.", path.display()))
+        }
+
+        /// Reads a coverage saved with [`Coverage::save`].
+        pub fn load(path: &Path) -> Result<Self> {
+            let format = Format::from_path(path)?;
+            let file = File::open(path)
+                .with_context(|| format!("Could not open `{}`.", path.display()))?;
+            let reader = BufReader::new(file);
+            let coverage = match format {
+                Format::Json => serde_json::from_reader(reader)?,
+                Format::Bincode => bincode::deserialize_from(reader)?,
+            };
+            Ok(coverage)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scenario::Parameter;
+    use crate::terrain::notation;
+
+    fn coverage(tx_power: f64) -> Coverage {
+        coverage_with_method(tx_power, MixedPathMethod::Millington)
+    }
+
+    fn coverage_with_method(tx_power: f64, method: MixedPathMethod) -> Coverage {
+        let lines = [
+            notation::parse("G38,S61,G191").unwrap(),
+            notation::parse("S20,G100").unwrap(),
+        ];
+        let scenario = Scenario::default().with(Parameter::TxPower, tx_power);
+        Coverage::calculate_with_method(&lines, scenario, vec![43.2, 10.0], method).unwrap()
+    }
+
+    #[test]
+    fn replay_and_compare() {
+        let coverage = coverage(10000.0);
+        assert_eq!(coverage.lines[0].max_distances_km[1], None);
+        assert_eq!(coverage.replay().unwrap(), coverage);
+        assert!(coverage.compare(&coverage).is_empty());
+        let mut rounded = coverage.clone();
+        rounded.min_field_strengths[0] = 43.2 * 3.0 / 3.0;
+        assert_ne!(rounded.min_field_strengths[0], 43.2);
+        assert!(coverage.compare(&rounded).is_empty());
+
+        let weaker = self::coverage(1000.0);
+        let differences = coverage.compare(&weaker);
+        assert_eq!(differences.len(), 2);
+        assert_eq!(differences[0].min_field_strength, 43.2);
+        assert!(differences[0].after_km < differences[0].before_km);
+
+        let scenario = Scenario::default().with(Parameter::TxPower, -1.0);
+        let failed = Coverage::calculate(
+            &[notation::parse("G38,S61,G191").unwrap()],
+            scenario,
+            vec![43.2],
+        );
+        assert!(format!("{:#}", failed.unwrap_err()).contains("angle 0"));
+
+        let kirke = self::coverage_with_method(10000.0, MixedPathMethod::Kirke);
+        assert_eq!(kirke.replay().unwrap(), kirke);
+        assert!(!coverage.compare(&kirke).is_empty());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn save_and_load_exactly() {
+        let coverage = coverage(10000.0);
+        let directory = tempfile::tempdir().unwrap();
+        for name in ["coverage.json", "coverage.bin"] {
+            let path = directory.path().join(name);
+            coverage.save(&path).unwrap();
+            assert_eq!(Coverage::load(&path).unwrap(), coverage);
+        }
+        assert!(Format::from_path("coverage.csv".as_ref()).is_err());
+    }
+}
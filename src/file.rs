@@ -1,83 +1,780 @@
 use crate::terrain::defaults::Terrain;
 use crate::terrain::{Line, LineSegment};
-use anyhow::{ensure, Context, Result};
+use anyhow::{bail, ensure, Context, Result};
+use calamine::{open_workbook_auto, Data, Reader};
+use std::cmp::Reverse;
+use std::fmt::{Display, Formatter};
 use std::path::Path;
 
+/// Which columns of a table hold the data of a line, found by patterns of their headers.
+///
+/// A pattern is matched against the whole header without its surrounding whitespace and regardless of case.
+/// `*` stands for any text and `|` separates alternatives, e.g. `land* px|sea* px`.
+#[derive(Debug, Clone)]
+pub struct Columns {
+    /// Pattern of the header of the angle in degrees.
+    pub angle: String,
+    /// Pattern of the header of the distance in px to the border, which the `border` tag refers to.
+    pub border: String,
+    /// Pattern of the headers of the distances in px of the boundaries, which are taken in the order of the columns
+    /// starting with the boundary from land to sea.
+    ///
+    /// Columns with an empty header that continue the spacing of the last two boundaries are boundaries too,
+    /// as the exports of our spreadsheet have the furthest ones without a header.
+    pub boundaries: String,
+}
+
+impl Default for Columns {
+    /// The columns of our spreadsheet.
+    fn default() -> Self {
+        Self {
+            angle: "degrees".to_string(),
+            border: "till border px".to_string(),
+            boundaries: "land* px|sea* px".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ReadOptions {
+    /// The sheet of a workbook to read, the first one if it's `None`.
+    pub sheet: Option<String>,
+    pub columns: Columns,
+    /// The delimiter of the fields of a csv file, e.g. `;` in the exports of a Greek-locale Excel.
+    pub delimiter: u8,
+    /// Reads numbers with a decimal comma, like `12,5`, which needs a delimiter other than `,`.
+    pub decimal_comma: bool,
+}
+
+impl Default for ReadOptions {
+    fn default() -> Self {
+        Self {
+            sheet: None,
+            columns: Columns::default(),
+            delimiter: b',',
+            decimal_comma: false,
+        }
+    }
+}
+
+/// Reads the lines of a csv file or an `.xlsx`, `.xlsm`, `.xlsb`, `.xls` or `.ods` workbook in our format.
 pub fn read(path: &Path) -> Result<Vec<Line>> {
-    let contents = std::fs::read_to_string(path)
+    read_with(path, &ReadOptions::default())
+}
+
+pub fn read_with(path: &Path, options: &ReadOptions) -> Result<Vec<Line>> {
+    read_table(path, options)?.lines(options)
+}
+
+fn read_table(path: &Path, options: &ReadOptions) -> Result<Table> {
+    ensure!(
+        !(options.decimal_comma && options.delimiter == b','),
+        "Decimal commas need a delimiter other than `,`, like `;`."
+    );
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_lowercase);
+    match extension.as_deref() {
+        Some("xlsx" | "xlsm" | "xlsb" | "xls" | "ods") => read_workbook(path, options),
+        _ => read_csv(path, options),
+    }
+}
+
+/// The header and the rows of a csv file or a sheet, with the cells as text.
+struct Table {
+    /// Where the table came from, for errors.
+    name: String,
+    headers: Vec<String>,
+    /// The rows after the header, with their row number in the file starting from 1.
+    rows: Vec<(u64, Vec<String>)>,
+}
+
+fn read_csv(path: &Path, options: &ReadOptions) -> Result<Table> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(options.delimiter)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_path(path)
         .with_context(|| format!("Could not read file `{}`", path.display()))?;
-    let mut lines = Vec::with_capacity(360 / 5);
-    // Skip the headers in the first line, read all the rest as lines.
-    for (i, file_line) in contents.lines().enumerate().skip(1) {
-        let line =
-            parse_to_line(file_line).with_context(|| format!("Failed to parse line #{}", i + 1))?;
-        lines.push(line);
+    let headers = reader
+        .headers()
+        .with_context(|| format!("Could not read the header of `{}`", path.display()))?
+        .iter()
+        .map(str::to_string)
+        .collect();
+    let mut rows = Vec::with_capacity(360 / 5);
+    for record in reader.records() {
+        let record = record.with_context(|| format!("Could not read `{}`", path.display()))?;
+        let row = record.position().map_or(0, |position| position.line());
+        if record.iter().all(str::is_empty) {
+            continue;
+        }
+        rows.push((row, record.iter().map(str::to_string).collect()));
     }
-    Ok(lines)
+    Ok(Table {
+        name: format!("`{}`", path.display()),
+        headers,
+        rows,
+    })
 }
 
-fn parse_to_line(file_line: &str) -> Result<Line> {
-    let mut columns = file_line.split(',').map(str::trim);
-    let angle: f64 = columns
-        .next()
-        .context("There was no 1st column (angle).")?
-        .parse()
-        .context("Could not parse 1st column (angle) to float.")?;
+fn read_workbook(path: &Path, options: &ReadOptions) -> Result<Table> {
+    let mut workbook = open_workbook_auto(path)
+        .with_context(|| format!("Could not open workbook `{}`", path.display()))?;
+    let sheet_names = workbook.sheet_names();
+    let sheet = match &options.sheet {
+        Some(sheet) => {
+            ensure!(
+                sheet_names.contains(sheet),
+                "There is no sheet `{sheet}` in `{}`. The sheets are: {}.",
+                path.display(),
+                sheet_names.join(", ")
+            );
+            sheet.clone()
+        }
+        None => sheet_names
+            .first()
+            .with_context(|| format!("There are no sheets in `{}`.", path.display()))?
+            .clone(),
+    };
+    let range = workbook
+        .worksheet_range(&sheet)
+        .with_context(|| format!("Could not read sheet `{sheet}` of `{}`", path.display()))?;
+    let first_row = range.start().map_or(0, |(row, _)| u64::from(row)) + 1;
 
-    // We'll actually use the pixel distances.
-    let border_column = columns
+    let mut table_rows = range.rows().zip(first_row..);
+    let (header, _) = table_rows
         .next()
-        .context("There was no 2nd column (end distance/border).")?;
-    let end_km_distance = if border_column.is_empty() {
+        .with_context(|| format!("Sheet `{sheet}` is empty."))?;
+    let headers = header.iter().map(|cell| cell.to_string()).collect();
+    let mut rows = Vec::with_capacity(360 / 5);
+    for (cells, row) in table_rows {
+        if cells.iter().all(|cell| *cell == Data::Empty) {
+            continue;
+        }
+        let cells = cells
+            .iter()
+            .enumerate()
+            .map(|(column, cell)| match cell {
+                Data::Error(error) => bail!(
+                    "Row {row}, column #{}: the cell has the error `{error}`.",
+                    column + 1
+                ),
+                cell => Ok(cell.to_string().trim().to_string()),
+            })
+            .collect::<Result<_>>()?;
+        rows.push((row, cells));
+    }
+    Ok(Table {
+        name: format!("sheet `{sheet}` of `{}`", path.display()),
+        headers,
+        rows,
+    })
+}
+
+/// The index of the first alternative of `pattern` that `header` matches, as described in [`Columns`].
+fn matching_alternative(pattern: &str, header: &str) -> Option<usize> {
+    let header = header.trim().to_lowercase();
+    pattern.split('|').position(|alternative| {
+        let alternative = alternative.trim().to_lowercase();
+        let mut parts = alternative.split('*');
+        let first = parts.next().unwrap_or_default();
+        let Some(mut rest) = header.strip_prefix(first) else {
+            return false;
+        };
+        let mut parts = parts.collect::<Vec<_>>();
+        let Some(last) = parts.pop() else {
+            // There was no `*`, so the whole header must have matched.
+            return rest.is_empty();
+        };
+        for part in parts {
+            match rest.find(part) {
+                Some(index) => rest = &rest[index + part.len()..],
+                None => return false,
+            }
+        }
+        rest.ends_with(last)
+    })
+}
+
+fn matches_pattern(pattern: &str, header: &str) -> bool {
+    matching_alternative(pattern, header).is_some()
+}
+
+/// The first number in a header, like 5 in `sea5 px`.
+fn header_number(header: &str) -> Option<u32> {
+    let digits = header
+        .chars()
+        .skip_while(|c| !c.is_ascii_digit())
+        .take_while(char::is_ascii_digit)
+        .collect::<String>();
+    digits.parse().ok()
+}
+
+impl Table {
+    fn column(&self, pattern: &str) -> Result<usize> {
+        let mut matching = self
+            .headers
+            .iter()
+            .enumerate()
+            .filter(|(_, header)| matches_pattern(pattern, header));
+        let (column, _) = matching.next().with_context(|| {
+            format!(
+                "{} has no column matching `{pattern}`. The columns are: {}.",
+                self.name,
+                self.headers.join(", ")
+            )
+        })?;
+        if let Some((other, _)) = matching.next() {
+            bail!(
+                "{} has more than one column matching `{pattern}`: `{}` and `{}`.",
+                self.name,
+                self.headers[column],
+                self.headers[other]
+            );
+        }
+        Ok(column)
+    }
+
+    /// The boundary columns of the header in order from the transmitter,
+    /// without those continuing them under an empty header.
+    ///
+    /// If every header has a number, like `land1 px` and `sea1 px`, they're ordered by it
+    /// and then by the alternative of the pattern they match, so columns can be in any order.
+    /// Otherwise they're in the order of the columns.
+    fn boundary_columns(&self, pattern: &str) -> Result<Vec<usize>> {
+        let mut columns = self
+            .headers
+            .iter()
+            .enumerate()
+            .filter_map(|(column, header)| {
+                let alternative = matching_alternative(pattern, header)?;
+                Some((column, header_number(header), alternative))
+            })
+            .collect::<Vec<_>>();
+        ensure!(
+            !columns.is_empty(),
+            "{} has no columns matching `{pattern}`. The columns are: {}.",
+            self.name,
+            self.headers.join(", ")
+        );
+        if columns.iter().all(|(_, number, _)| number.is_some()) {
+            columns.sort_by_key(|&(column, number, alternative)| (number, alternative, column));
+        }
+        Ok(columns.into_iter().map(|(column, _, _)| column).collect())
+    }
+
+    /// The name of a column for errors, its header if it has one.
+    fn column_name(&self, column: usize) -> String {
+        match self.headers.get(column).map(|header| header.trim()) {
+            Some(header) if !header.is_empty() => format!("column #{} `{header}`", column + 1),
+            _ => format!("column #{}", column + 1),
+        }
+    }
+
+    /// Parses every row, collecting all of their problems instead of stopping at the first one.
+    fn parse_rows(&self, options: &ReadOptions) -> Result<Vec<ParsedRow>> {
+        let angle_column = self.column(&options.columns.angle)?;
+        let border_column = self.column(&options.columns.border)?;
+        let boundary_columns = self.boundary_columns(&options.columns.boundaries)?;
+        let mut positions = boundary_columns.clone();
+        positions.sort_unstable();
+        let spacing = match positions[..] {
+            [.., before_last, last] => last - before_last,
+            _ => 2,
+        };
+        let last_boundary_column = *positions.last().expect("There is at least one.");
+
+        let parsed_rows = self
+            .rows
+            .iter()
+            .map(|(row, cells)| {
+                let cell = |column: usize| Cell {
+                    column: self.column_name(column),
+                    text: cells.get(column).map_or("", String::as_str),
+                    decimal_comma: options.decimal_comma,
+                };
+                let unnamed_boundaries = (last_boundary_column + spacing..cells.len())
+                    .step_by(spacing)
+                    .take_while(|&column| {
+                        self.headers
+                            .get(column)
+                            .is_none_or(|header| header.trim().is_empty())
+                    });
+                let boundaries = boundary_columns
+                    .iter()
+                    .copied()
+                    .chain(unnamed_boundaries)
+                    .map(cell);
+                let mut parsed = parse_row(cell(angle_column), cell(border_column), boundaries);
+                for problem in &mut parsed.problems {
+                    problem.row = Some(*row);
+                }
+                parsed
+            })
+            .collect();
+        Ok(parsed_rows)
+    }
+
+    fn lines(&self, options: &ReadOptions) -> Result<Vec<Line>> {
+        self.parse_rows(options)?
+            .into_iter()
+            .map(|parsed| match parsed.line {
+                Some(line) => Ok(line),
+                None => {
+                    let error = parsed.problems.first().expect(
+                        "Rows without a line have a problem that keeps them from being read.",
+                    );
+                    bail!("Failed to parse {}, {error}", self.name)
+                }
+            })
+            .collect()
+    }
+}
+
+/// How bad a [`Problem`] is.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// The line can be calculated but the data looks suspicious.
+    Warning,
+    /// The data is wrong, or the line can't be read or calculated.
+    Error,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Warning => write!(f, "warning"),
+            Self::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A problem found in the data of a file by [`validate`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Problem {
+    pub severity: Severity,
+    /// The row in the file starting from 1, if the problem is in a single row.
+    pub row: Option<u64>,
+    /// The column, like ``column #4 `land1 px` ``, if the problem is in a single cell.
+    pub column: Option<String>,
+    pub message: String,
+}
+
+impl Problem {
+    fn new(severity: Severity, column: Option<&str>, message: String) -> Self {
+        Self {
+            severity,
+            row: None,
+            column: column.map(str::to_string),
+            message,
+        }
+    }
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match (self.row, &self.column) {
+            (Some(row), Some(column)) => write!(f, "row {row}, {column}: {}", self.message),
+            (Some(row), None) => write!(f, "row {row}: {}", self.message),
+            (None, Some(column)) => write!(f, "{column}: {}", self.message),
+            (None, None) => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// The problems in the data of a file along with the lines it could still read.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Validation {
+    /// The lines of the rows without errors of their own, i.e. besides duplicate angles.
+    pub lines: Vec<Line>,
+    /// Every problem, ordered by row.
+    pub problems: Vec<Problem>,
+}
+
+/// Finds every problem in the data of a file instead of stopping at the first one like [`read_with`].
+/// It fails only if the file or the header of its columns can't be read.
+pub fn validate(path: &Path, options: &ReadOptions) -> Result<Validation> {
+    let table = read_table(path, options)?;
+    let parsed_rows = table.parse_rows(options)?;
+    let mut problems = parsed_rows
+        .iter()
+        .flat_map(|parsed| parsed.problems.iter().cloned())
+        .collect::<Vec<_>>();
+
+    let mut angles = parsed_rows
+        .iter()
+        .zip(&table.rows)
+        .filter_map(|(parsed, (row, _))| Some((parsed.angle?, *row)))
+        .collect::<Vec<_>>();
+    angles.sort_by(|a, b| a.0.total_cmp(&b.0));
+    for pair in angles.windows(2) {
+        let [(angle, first_row), (other_angle, row)] = pair else {
+            unreachable!()
+        };
+        if angle == other_angle {
+            problems.push(Problem {
+                row: Some(*row),
+                ..Problem::new(
+                    Severity::Error,
+                    None,
+                    format!("The angle {angle} is the same as the one of row {first_row}."),
+                )
+            });
+        }
+    }
+    angles.dedup_by(|a, b| a.0 == b.0);
+    let angles = angles
+        .into_iter()
+        .map(|(angle, _)| angle)
+        .filter(|angle| (0.0..=360.0).contains(angle))
+        .collect::<Vec<_>>();
+    problems.extend(missing_angles(&angles));
+
+    problems.sort_by_key(|problem| problem.row);
+    let lines = parsed_rows
+        .into_iter()
+        .filter(|parsed| {
+            parsed
+                .problems
+                .iter()
+                .all(|problem| problem.severity == Severity::Warning)
+        })
+        .filter_map(|parsed| parsed.line)
+        .collect();
+    Ok(Validation { lines, problems })
+}
+
+/// Warns about the gaps in `angles`, which are sorted and unique,
+/// taking the most common step between them as the expected one, or the smallest if none is more common.
+fn missing_angles(angles: &[f64]) -> Option<Problem> {
+    const TOLERANCE: f64 = 1e-9;
+    let mut steps = angles
+        .windows(2)
+        .map(|pair| pair[1] - pair[0])
+        .collect::<Vec<_>>();
+    steps.sort_by(f64::total_cmp);
+    let step = steps.iter().copied().min_by_key(|step| {
+        let count = steps
+            .iter()
+            .filter(|other| (*other - step).abs() < TOLERANCE)
+            .count();
+        Reverse(count)
+    })?;
+    let missing = angles
+        .windows(2)
+        .flat_map(|pair| {
+            let gaps = ((pair[1] - pair[0]) / step).round() as usize;
+            (1..gaps).map(move |i| pair[0] + step * i as f64)
+        })
+        .map(|angle| angle.to_string())
+        .collect::<Vec<_>>();
+    (!missing.is_empty()).then(|| {
+        Problem::new(
+            Severity::Warning,
+            None,
+            format!(
+                "The angles go in steps of {step} but these are missing: {}.",
+                missing.join(", ")
+            ),
+        )
+    })
+}
+
+/// The text of a cell along with what's needed to parse it and report errors about it.
+struct Cell<'a> {
+    column: String,
+    text: &'a str,
+    decimal_comma: bool,
+}
+
+impl Cell<'_> {
+    fn number(&self, what: &str) -> Result<f64, Problem> {
+        let text = if self.decimal_comma {
+            self.text.replace(',', ".")
+        } else {
+            self.text.to_string()
+        };
+        text.parse().map_err(|_| {
+            self.problem(
+                Severity::Error,
+                format!("Could not parse `{}` to a float as {what}.", self.text),
+            )
+        })
+    }
+
+    fn problem(&self, severity: Severity, message: String) -> Problem {
+        Problem::new(severity, Some(&self.column), message)
+    }
+}
+
+/// A row along with its problems. It has a line only if it has no errors.
+struct ParsedRow {
+    angle: Option<f64>,
+    line: Option<Line>,
+    problems: Vec<Problem>,
+}
+
+/// LFMF's limits on the length of a path in km.
+const MIN_PATH_KM: f64 = 0.001;
+const MAX_PATH_KM: f64 = 10000.0;
+
+/// Makes a line out of the cells of a row: its angle, the distance in px to the border
+/// and the distances in px of the boundaries, which end at an empty cell or the `border` tag.
+fn parse_row<'a>(
+    angle_cell: Cell,
+    border: Cell,
+    boundaries: impl Iterator<Item = Cell<'a>>,
+) -> ParsedRow {
+    // The problems that keep the row from being read as a line, unlike those of the limits of LFMF.
+    let mut unreadable = Vec::new();
+    let mut problems = Vec::new();
+    let angle = angle_cell
+        .number("an angle")
+        .map_err(|problem| unreadable.push(problem))
+        .ok();
+    let end_km_distance = if border.text.is_empty() {
         None
     } else {
-        let end_px_distance = border_column
-            .parse()
-            .context("Could not parse 2nd column (end distance/border) to float.")?;
-        Some(px_to_km(end_px_distance))
+        // We'll actually use the pixel distances.
+        border
+            .number("the pixel distance to the border")
+            .map(px_to_km)
+            .map_err(|problem| unreadable.push(problem))
+            .ok()
     };
+    if let Some(angle) = angle.filter(|angle| !(0.0..=360.0).contains(angle)) {
+        problems.push(angle_cell.problem(
+            Severity::Error,
+            format!("The angle {angle} is outside of 0 to 360 degrees."),
+        ));
+    }
 
-    // The start from the 4th column and measure all the segment distances.
     let mut segments = Vec::with_capacity(8);
     let mut current_km_distance = 0.0;
     let mut current_terrain = Terrain::Ground;
-    for column in columns.skip(1).step_by(2) {
-        if column == "border" {
-            let end_km_distance =
-                end_km_distance.context("`border` tag was used but not defined.")?;
-            let last_km_length = end_km_distance - current_km_distance;
-            ensure!(last_km_length > 0.0, "Length must be positive but it was {end_km_distance} - {current_km_distance} = {last_km_length} for line with angle {angle} degrees.");
-            segments.push(LineSegment::with_length(
-                current_terrain.parameters(),
-                last_km_length,
-            ));
+    // The problem of the last segment so far, if it's too short.
+    let mut last_short_segment = None;
+    for boundary in boundaries {
+        let km_distance = if boundary.text == "border" {
+            if border.text.is_empty() {
+                unreadable.push(boundary.problem(
+                    Severity::Error,
+                    format!("The `border` tag was used but {} is empty.", border.column),
+                ));
+            }
+            let Some(end_km_distance) = end_km_distance else {
+                break;
+            };
+            end_km_distance
+        } else if boundary.text.is_empty() {
             break;
-        } else if column.is_empty() {
-            break;
-        }
+        } else {
+            match boundary.number("a pixel distance") {
+                Ok(px_distance) => px_distance * 200.0 / 254.0,
+                Err(problem) => {
+                    unreadable.push(problem);
+                    current_terrain = alternate(current_terrain);
+                    continue;
+                }
+            }
+        };
 
-        let px_distance: f64 = column.parse().with_context(|| {
-            format!("Could not parse `{column}` to a float as a pixel distance.")
-        })?;
-        let km_distance = px_distance * 200.0 / 254.0;
         let km_length = km_distance - current_km_distance;
-        ensure!(km_length > 0.0, "Length must be positive but it was {km_distance} - {current_km_distance} = {km_length} for line with angle {angle} degrees.");
+        if km_length <= 0.0 {
+            let what = if boundary.text == "border" {
+                "The border"
+            } else {
+                "The boundary"
+            };
+            unreadable.push(boundary.problem(
+                Severity::Error,
+                format!("{what} at {km_distance} km isn't past the previous boundary at {current_km_distance} km."),
+            ));
+        } else {
+            // The first segment is calculated on its own by the forward sum of Millington's method and the last one
+            // by the reverse sum, which LFMF would reject, so only those in between can be shorter than its minimum.
+            last_short_segment = None;
+            if km_length < MIN_PATH_KM {
+                let severity = if segments.is_empty() {
+                    Severity::Error
+                } else {
+                    last_short_segment = Some(problems.len());
+                    Severity::Warning
+                };
+                problems.push(boundary.problem(
+                    severity,
+                    format!("The segment is {km_length} km long, shorter than LFMF's minimum of {MIN_PATH_KM} km."),
+                ));
+            }
+            segments.push(LineSegment::new(current_terrain.medium(), km_length));
+            current_km_distance += km_length;
+        }
+        if boundary.text == "border" {
+            break;
+        }
+        current_terrain = alternate(current_terrain);
+    }
+    if let Some(index) = last_short_segment {
+        problems[index].severity = Severity::Error;
+    }
 
-        segments.push(LineSegment::with_length(
-            current_terrain.parameters(),
-            km_length,
+    if current_km_distance > MAX_PATH_KM {
+        problems.push(Problem::new(
+            Severity::Error,
+            None,
+            format!("The line is {current_km_distance} km long, longer than LFMF's maximum of {MAX_PATH_KM} km."),
         ));
+    }
 
-        current_km_distance += km_length;
-        current_terrain = match current_terrain {
-            Terrain::Ground => Terrain::Sea,
-            Terrain::Sea => Terrain::Ground,
-        }
+    let line = angle
+        .filter(|_| unreadable.is_empty())
+        .map(|angle| Line::with_segments(angle, segments));
+    unreadable.extend(problems);
+    ParsedRow {
+        angle,
+        line,
+        problems: unreadable,
     }
+}
 
-    Ok(Line::with_segments(angle, segments))
+/// The terrain after a boundary, since land and sea alternate.
+fn alternate(terrain: Terrain) -> Terrain {
+    match terrain {
+        Terrain::Ground => Terrain::Sea,
+        Terrain::Sea => Terrain::Ground,
+    }
 }
 
-fn px_to_km(px: f64) -> f64 {
+/// Converts a distance measured in pixels on our map to km.
+pub fn px_to_km(px: f64) -> f64 {
     (px / 254.0) * 200.0
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn workbook_matches_csv() {
+        let from_csv = read(Path::new("data.csv")).unwrap();
+        let from_workbook = read(Path::new("data.xlsx")).unwrap();
+        assert_eq!(from_workbook, from_csv);
+        // The boundaries are converted exactly as they always were, so the results don't shift in the last bits.
+        let first = from_csv[3].segments().next().unwrap();
+        assert_eq!(first.length_km(), 104.5 * 200.0 / 254.0);
+
+        let options = ReadOptions {
+            sheet: Some("Sheet2".to_string()),
+            ..ReadOptions::default()
+        };
+        assert!(read_with(Path::new("data.xlsx"), &options).is_err());
+    }
+
+    #[test]
+    fn csv_by_header_with_semicolons_and_decimal_commas() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("greek-locale.csv");
+        std::fs::write(
+            &path,
+            "\"sea1 px\";degrees;\"land1 px\";till border px;sea1 km \n\
+             ;0;;204;\n\
+             \"125,4\";15;\"104,5\";243;98,7\n",
+        )
+        .unwrap();
+        let options = ReadOptions {
+            delimiter: b';',
+            decimal_comma: true,
+            ..ReadOptions::default()
+        };
+        let lines = read_with(&path, &options).unwrap();
+        let comma_lines = read(Path::new("data.csv")).unwrap();
+        assert_eq!(lines[1].angle(), 15.0);
+        assert_eq!(lines[1].segments().count(), 2);
+        assert_eq!(lines[1].segments().next(), comma_lines[3].segments().next());
+
+        std::fs::write(
+            &path,
+            "degrees;till border px;land1 px\n0;204;\n5;204;12.5x\n",
+        )
+        .unwrap();
+        let error = format!("{:#}", read_with(&path, &options).unwrap_err());
+        assert!(error.contains("row 3"), "{error}");
+        assert!(error.contains("column #3 `land1 px`"), "{error}");
+    }
+
+    #[test]
+    fn validation_reports_every_problem() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("problems.csv");
+        std::fs::write(
+            &path,
+            "degrees,till border px,land1 px,sea1 px,land2 px\n\
+             0,204,100,border,\n\
+             10,,100,border,\n\
+             10,204,100,90,150\n\
+             400,204,100,,\n\
+             30,204,100,100.001,border\n\
+             35,20000000,border,,\n\
+             40,204,100,100.001,\n",
+        )
+        .unwrap();
+        let validation = validate(&path, &ReadOptions::default()).unwrap();
+
+        let problems = validation
+            .problems
+            .iter()
+            .map(|problem| (problem.severity, problem.row))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            problems,
+            [
+                (Severity::Warning, None),
+                (Severity::Error, Some(3)),
+                (Severity::Error, Some(4)),
+                (Severity::Error, Some(4)),
+                (Severity::Error, Some(5)),
+                (Severity::Warning, Some(6)),
+                (Severity::Error, Some(7)),
+                (Severity::Error, Some(8)),
+            ]
+        );
+        let messages = validation
+            .problems
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert!(
+            messages[0].contains("missing: 5, 15, 20, 25"),
+            "{messages:?}"
+        );
+        assert!(messages[1].contains("`border` tag"), "{messages:?}");
+        assert!(messages[2].contains("column #4 `sea1 px`"), "{messages:?}");
+        assert!(
+            messages[3].contains("same as the one of row 3"),
+            "{messages:?}"
+        );
+        assert!(messages[4].contains("outside of 0 to 360"), "{messages:?}");
+        assert!(
+            messages[6].contains("longer than LFMF's maximum"),
+            "{messages:?}"
+        );
+        assert!(
+            messages[7].contains("shorter than LFMF's minimum"),
+            "{messages:?}"
+        );
+
+        let angles = validation.lines.iter().map(Line::angle).collect::<Vec<_>>();
+        assert_eq!(angles, [0.0, 30.0]);
+    }
+
+    #[test]
+    fn patterns_match_whole_headers() {
+        assert!(matches_pattern("land* px|sea* px", " Sea5 px "));
+        assert!(matches_pattern("degrees", "Degrees"));
+        assert!(!matches_pattern("land* px", "land1 km"));
+        assert!(!matches_pattern("degrees", "degrees 2"));
+        assert!(matches_pattern("*px*", "till border px"));
+    }
+}
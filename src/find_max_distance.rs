@@ -1,121 +1,372 @@
-use crate::lfmf::calc_LFMF;
-use crate::terrain::{Line, LineSegment};
+use crate::propagation_model::PropagationModel;
+use crate::terrain::defaults::Terrain;
+use crate::terrain::{Line, LineSegment, Medium};
 use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
 
-fn calc_one_way_field_strength_for_segments<'a, I: Iterator<Item = &'a LineSegment>>(
+pub(crate) fn calc_one_way_field_strength_for_segments<'a, I: Iterator<Item = &'a LineSegment>>(
+    model: &(impl PropagationModel + ?Sized),
+    line: &Line,
     segments: I,
 ) -> Result<f64> {
+    Ok(calc_one_way_terms_for_segments(model, line, segments)?
+        .last()
+        .map_or(0.0, |term| term.partial_sum))
+}
+
+fn calc_one_way_terms_for_segments<'a, I: Iterator<Item = &'a LineSegment>>(
+    model: &(impl PropagationModel + ?Sized),
+    line: &Line,
+    segments: I,
+) -> Result<Vec<MillingtonTerm>> {
+    let mut terms = Vec::new();
     let mut field_strength = 0.0;
     let mut old_distance = 0.0;
+    let mut term = |segment: &LineSegment, distance_km, subtracted| -> Result<()> {
+        let path = line.homogeneous_path(segment.medium());
+        let term_field_strength = model.field_strength(&path, distance_km)?;
+        if subtracted {
+            field_strength -= term_field_strength;
+        } else {
+            field_strength += term_field_strength;
+        }
+        terms.push(MillingtonTerm {
+            terrain: segment.terrain(),
+            medium: segment.medium(),
+            distance_km,
+            field_strength: term_field_strength,
+            subtracted,
+            partial_sum: field_strength,
+        });
+        Ok(())
+    };
     for segment in segments {
         let new_distance = old_distance + segment.length_km();
-        let mut parameters = segment.lfmf_parameters();
         if old_distance != 0.0 {
-            parameters.d__km = old_distance;
-            field_strength -= calc_LFMF(parameters)
-                .with_context(|| {
-                    format!("Failed to calculate field_strength for parameters {parameters:?}.")
-                })?
-                .E_dBuVm;
+            term(segment, old_distance, true)?;
         }
-        parameters.d__km = new_distance;
-        field_strength += calc_LFMF(parameters)
-            .with_context(|| {
-                format!("Failed to calculate field_strength for parameters {parameters:?}.")
-            })?
-            .E_dBuVm;
+        term(segment, new_distance, false)?;
         old_distance = new_distance;
     }
-    Ok(field_strength)
+    Ok(terms)
+}
+
+/// The field strength at a distance along a line, along with the two one way sums that Millington's method averages.
+#[derive(Debug, Copy, Clone)]
+pub struct MillingtonFieldStrength {
+    /// The sum going from the transmitter to the receiver in dB(uV)/m.
+    pub forward: f64,
+    /// The sum going from the receiver to the transmitter in dB(uV)/m.
+    pub reverse: f64,
+    /// The average of the two sums in dB(uV)/m.
+    pub field_strength: f64,
+}
+
+/// The field strength in dB(uV)/m at `distance` km along `line` by Millington's method over the segments evaluated by `model`.
+pub fn calc_field_strength_for_line_at_km(
+    model: &(impl PropagationModel + ?Sized),
+    line: &Line,
+    distance: f64,
+) -> Result<f64> {
+    Ok(calc_millington_field_strength_for_line_at_km(model, line, distance)?.field_strength)
 }
 
-pub fn calc_field_strength_for_line_at_km(line: &Line, distance: f64) -> Result<f64> {
+pub fn calc_millington_field_strength_for_line_at_km(
+    model: &(impl PropagationModel + ?Sized),
+    line: &Line,
+    distance: f64,
+) -> Result<MillingtonFieldStrength> {
+    let explanation = explain_millington_field_strength_for_line_at_km(model, line, distance)?;
+    Ok(MillingtonFieldStrength {
+        forward: explanation.forward,
+        reverse: explanation.reverse,
+        field_strength: explanation.field_strength,
+    })
+}
+
+/// A field strength over the ground of a segment that one of the sums of Millington's method adds or subtracts.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct MillingtonTerm {
+    /// The terrain of the segment, `None` if its medium is neither of our project's.
+    pub terrain: Option<Terrain>,
+    pub medium: Medium,
+    /// The distance from the start of the sum in km.
+    pub distance_km: f64,
+    /// The field strength over the ground of the segment at the distance in dB(uV)/m.
+    pub field_strength: f64,
+    /// Whether the field strength is subtracted from the sum rather than added to it.
+    pub subtracted: bool,
+    /// The sum up to and including this term in dB(uV)/m.
+    pub partial_sum: f64,
+}
+
+/// Every term of the two sums of Millington's method at a distance along a line, along with the sums and their average.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MillingtonExplanation {
+    /// The terms going from the transmitter to the receiver.
+    pub forward_terms: Vec<MillingtonTerm>,
+    /// The terms going from the receiver to the transmitter.
+    pub reverse_terms: Vec<MillingtonTerm>,
+    /// The sum going from the transmitter to the receiver in dB(uV)/m.
+    pub forward: f64,
+    /// The sum going from the receiver to the transmitter in dB(uV)/m.
+    pub reverse: f64,
+    /// The average of the two sums in dB(uV)/m.
+    pub field_strength: f64,
+}
+
+/// Like [`calc_millington_field_strength_for_line_at_km`], but with every term of the two sums.
+/// A line of a single segment up to the distance has the same single term in both.
+pub fn explain_millington_field_strength_for_line_at_km(
+    model: &(impl PropagationModel + ?Sized),
+    line: &Line,
+    distance: f64,
+) -> Result<MillingtonExplanation> {
     let segments = line.segments_until(distance).with_context(|| {
         format!("Could not get segments for distance of {distance} km in line {line:?}")
     })?;
     match segments.len() {
         0 => bail!("Got 0 segments for distance of {distance} km in line {line:?}"),
-        1 => Ok(calc_LFMF(segments[0].lfmf_parameters())
-            .with_context(|| {
-                format!(
-                    "Could not calculate field strength for segment {:?}",
-                    segments[0]
-                )
-            })?
-            .E_dBuVm),
+        1 => {
+            let terms = calc_one_way_terms_for_segments(model, line, segments.iter())
+                .with_context(|| {
+                    format!(
+                        "Could not calculate field strength for segment {:?}",
+                        segments[0]
+                    )
+                })?;
+            let field_strength = terms[0].field_strength;
+            Ok(MillingtonExplanation {
+                forward_terms: terms.clone(),
+                reverse_terms: terms,
+                forward: field_strength,
+                reverse: field_strength,
+                field_strength,
+            })
+        }
         _ => {
-            let field_strength1 = calc_one_way_field_strength_for_segments(segments.iter())
+            let forward_terms = calc_one_way_terms_for_segments(model, line, segments.iter())
                 .with_context(|| {
                     format!(
                         "Could not calculate forwards way field strength for segments {segments:?}."
                     )
                 })?;
-            let field_strength2 = calc_one_way_field_strength_for_segments(segments.iter().rev())
+            let reverse_terms = calc_one_way_terms_for_segments(model, line, segments.iter().rev())
                 .with_context(|| {
-                format!("Could not calculate reverse way field strength for segments {segments:?}.")
-            })?;
-            let field_strength_final = (field_strength1 + field_strength2) * 0.5;
-            Ok(field_strength_final)
+                    format!(
+                        "Could not calculate reverse way field strength for segments {segments:?}."
+                    )
+                })?;
+            let forward = forward_terms[forward_terms.len() - 1].partial_sum;
+            let reverse = reverse_terms[reverse_terms.len() - 1].partial_sum;
+            Ok(MillingtonExplanation {
+                forward_terms,
+                reverse_terms,
+                forward,
+                reverse,
+                field_strength: (forward + reverse) * 0.5,
+            })
+        }
+    }
+}
+
+/// Memoizes the field strength along a line by distance,
+/// so that the refinements of minimum field strengths crossed close together reuse each other's evaluations.
+struct FieldStrengthCache<'a, F> {
+    line: &'a Line,
+    field_strength_at_km: F,
+    field_strengths: HashMap<u64, f64>,
+}
+
+impl<'a, F: FnMut(f64) -> Result<f64>> FieldStrengthCache<'a, F> {
+    fn new(line: &'a Line, field_strength_at_km: F) -> Self {
+        Self {
+            line,
+            field_strength_at_km,
+            field_strengths: HashMap::new(),
+        }
+    }
+
+    fn at_km(&mut self, distance: f64) -> Result<f64> {
+        if let Some(field_strength) = self.field_strengths.get(&distance.to_bits()) {
+            return Ok(*field_strength);
         }
+        let field_strength = (self.field_strength_at_km)(distance)?;
+        self.field_strengths
+            .insert(distance.to_bits(), field_strength);
+        Ok(field_strength)
     }
 }
 
 /// Find the maximum distance between the transmitter and the receiver for a given minimum usable field strength in dB(uV)/m.
 ///
 /// # Arguments
+/// * `model` - The model the segments are evaluated by for Millington's method.
 /// * `min_usable_field_strength` - Minimum usable field strength in dB(uV)/m.
 /// * `line` - The line in which to search.
 ///
 /// # Returns
 /// A result of either the maximum distance in km where the field strength doesn't fall below the minimum or an error.
-pub fn find_max_distance_for_line(min_usable_field_strength: f64, line: &Line) -> Result<f64> {
-    const FIELD_STRENGTH_DB_TOLERANCE: f64 = 0.0001;
-    const MINIMUM_STEP: f64 = 0.001; // An accuracy of 1 m should be enough given how approximated the results of LFMF are and is inline with it's minimum distance.
-    let upper_bound = min_usable_field_strength + FIELD_STRENGTH_DB_TOLERANCE;
-    let lower_bound = min_usable_field_strength;
-
-    // First we'll try to find the first segment that contains the value we're searching for to isolate it.
-    let mut min_distance = 0.0;
-    let mut max_distance = 0.0;
-    let mut current_distance = 0.0;
+pub fn find_max_distance_for_line(
+    model: &(impl PropagationModel + ?Sized),
+    min_usable_field_strength: f64,
+    line: &Line,
+) -> Result<f64> {
+    find_max_distances_for_line(model, &[min_usable_field_strength], line)?[0]
+        .with_context(|| format!("The distance where the minimum field strength value is at is greater than the length of {} km of line {line:?}.", line.max_distance()))
+}
+
+/// Find the maximum distances for several minimum usable field strengths in dB(uV)/m in a single walk of the line.
+///
+/// # Returns
+/// A result of either the maximum distances in km in the same order as `min_usable_field_strengths`,
+/// `None` for a minimum that isn't reached within the line, or an error if the field strength couldn't be calculated.
+pub fn find_max_distances_for_line(
+    model: &(impl PropagationModel + ?Sized),
+    min_usable_field_strengths: &[f64],
+    line: &Line,
+) -> Result<Vec<Option<f64>>> {
+    find_max_distances_for_line_with(min_usable_field_strengths, line, |distance| {
+        calc_field_strength_for_line_at_km(model, line, distance)
+    })
+}
+
+/// Like [`find_max_distances_for_line`], but with the field strength in dB(uV)/m at a distance in km along `line`
+/// from `field_strength_at_km` instead of Millington's method, like another [`crate::mixed_path::MixedPathMethod`]
+/// or a [`crate::integral_equation::Solution`].
+pub fn find_max_distances_for_line_with(
+    min_usable_field_strengths: &[f64],
+    line: &Line,
+    field_strength_at_km: impl FnMut(f64) -> Result<f64>,
+) -> Result<Vec<Option<f64>>> {
+    let mut cache = FieldStrengthCache::new(line, field_strength_at_km);
+    search_max_distances(min_usable_field_strengths, &mut cache)
+}
+
+const FIELD_STRENGTH_DB_TOLERANCE: f64 = 0.0001;
+const MINIMUM_STEP: f64 = 0.001; // An accuracy of 1 m should be enough given how approximated the results of LFMF are and is inline with it's minimum distance.
+
+fn search_max_distances(
+    min_usable_field_strengths: &[f64],
+    cache: &mut FieldStrengthCache<impl FnMut(f64) -> Result<f64>>,
+) -> Result<Vec<Option<f64>>> {
+    let line = cache.line;
+    let mut max_distances = vec![None; min_usable_field_strengths.len()];
+
+    // First we'll find the segment that contains each value we're searching for to isolate it.
+    // The minimums still above the field strength at the end of every segment so far are pending.
+    let mut pending: Vec<usize> = (0..min_usable_field_strengths.len()).collect();
+    let mut segment_start = 0.0;
     for segment in line.segments() {
-        current_distance += segment.length_km();
-        let field_strength = calc_field_strength_for_line_at_km(line, current_distance).with_context(||format!("While searching for min and max distance, could not calculate field strength at {current_distance} km for line {line:?}."))?;
-        if field_strength > upper_bound {
-            min_distance = current_distance;
-        } else if field_strength <= upper_bound {
-            if lower_bound <= field_strength {
+        if pending.is_empty() {
+            break;
+        }
+        let segment_end = segment_start + segment.length_km();
+        let field_strength = cache.at_km(segment_end).with_context(||format!("While searching for min and max distance, could not calculate field strength at {segment_end} km for line {line:?}."))?;
+        let mut crossed = Vec::new();
+        pending.retain(|&i| {
+            let lower_bound = min_usable_field_strengths[i];
+            let upper_bound = lower_bound + FIELD_STRENGTH_DB_TOLERANCE;
+            if field_strength > upper_bound {
+                true
+            } else if lower_bound <= field_strength {
                 // Lucky find!
-                return Ok(current_distance);
+                max_distances[i] = Some(segment_end);
+                false
+            } else {
+                // Else we found a bound for the distance, the field strength must be in this segment.
+                crossed.push(i);
+                false
             }
-            // Else we found a bound for the distance, the field strength must be in this segment.
-            max_distance = current_distance;
-            break;
+        });
+        if !crossed.is_empty() {
+            walk_segment(
+                min_usable_field_strengths,
+                &crossed,
+                segment_start,
+                segment_end,
+                cache,
+                &mut max_distances,
+            )?;
         }
+        segment_start = segment_end;
     }
-    if max_distance == 0.0 {
-        bail!("The distance where the minimum field strength value is at is greater than the length of {min_distance} km of line {line:?}.");
-    }
+    // Whatever is still pending is greater than the length of the line.
+    Ok(max_distances)
+}
 
+/// Walks the segment from `segment_start` to `segment_end` km once for all the minimums in `crossed`,
+/// recording the distance at which each of them is crossed.
+fn walk_segment(
+    min_usable_field_strengths: &[f64],
+    crossed: &[usize],
+    segment_start: f64,
+    segment_end: f64,
+    cache: &mut FieldStrengthCache<impl FnMut(f64) -> Result<f64>>,
+    max_distances: &mut [Option<f64>],
+) -> Result<()> {
     // Ideally we could use the bisection method/binary search here, but Millington's method can produce unpredictable results.
     // For example, a sea path after a ground path will have the predicted field strength go upwards as you go further before it goes back down.
     // So we'll just do a slow but fault tolerant linear search.
-    let mut current_distance = min_distance;
-    let mut step = 5.0; // The choice of 5 km is a bit arbitrary but should be an ok compromise between speed and resistance to abnormalities.
+    const STEP: f64 = 5.0; // The choice of 5 km is a bit arbitrary but should be an ok compromise between speed and resistance to abnormalities.
+    let line = cache.line;
+    let mut walking = crossed.to_vec();
+    let mut current_distance = segment_start;
+    while !walking.is_empty() {
+        let new_distance = current_distance + STEP;
+        let field_strength = if new_distance < segment_end {
+            cache.at_km(new_distance).with_context(|| format!("While linearly searching, could not calculate field strength for distance {new_distance} km in line {line:?}"))?
+        } else {
+            f64::NEG_INFINITY
+        };
+        let mut still_walking = Vec::new();
+        for i in walking {
+            let lower_bound = min_usable_field_strengths[i];
+            let upper_bound = lower_bound + FIELD_STRENGTH_DB_TOLERANCE;
+            if field_strength < lower_bound {
+                max_distances[i] = Some(refine(
+                    lower_bound,
+                    current_distance,
+                    new_distance,
+                    STEP * 0.125,
+                    cache,
+                )?);
+            } else if field_strength <= upper_bound {
+                max_distances[i] = Some(new_distance);
+            } else {
+                still_walking.push(i);
+            }
+        }
+        walking = still_walking;
+        current_distance = new_distance;
+    }
+    Ok(())
+}
+
+/// Narrows down the distance where `lower_bound` is crossed between `current_distance` and `max_distance` km.
+fn refine(
+    lower_bound: f64,
+    mut current_distance: f64,
+    mut max_distance: f64,
+    mut step: f64,
+    cache: &mut FieldStrengthCache<impl FnMut(f64) -> Result<f64>>,
+) -> Result<f64> {
+    let upper_bound = lower_bound + FIELD_STRENGTH_DB_TOLERANCE;
+    let line = cache.line;
     loop {
+        if step < MINIMUM_STEP {
+            return Ok(current_distance); // As a last resort, while it may be out of the set bounds, return the last valid value.
+        }
         let new_distance = current_distance + step;
         let field_strength = if new_distance < max_distance {
-            calc_field_strength_for_line_at_km(line, new_distance).with_context(|| format!("While linearly searching, could not calculate field strength for distance {new_distance} km in line {line:?}"))?
+            cache.at_km(new_distance).with_context(|| format!("While linearly searching, could not calculate field strength for distance {new_distance} km in line {line:?}"))?
         } else {
             f64::NEG_INFINITY
         };
         if field_strength < lower_bound {
             max_distance = new_distance;
             step *= 0.125; // Division by 8. This means that it will take 8 iterations to get to the new maximum distance.
-            if step < MINIMUM_STEP {
-                return Ok(current_distance); // As a last resort, while it may be out of the set bounds, return the last valid value.
-            }
-        } else if field_strength <= upper_bound && lower_bound <= field_strength {
+        } else if field_strength <= upper_bound {
             return Ok(new_distance);
         } else {
             current_distance = new_distance;
@@ -126,6 +377,8 @@ pub fn find_max_distance_for_line(min_usable_field_strength: f64, line: &Line) -
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::lfmf::calc_LFMF;
+    use crate::propagation_model::Lfmf;
     use crate::terrain::defaults::Terrain;
     use std::iter::once;
 
@@ -134,17 +387,19 @@ mod tests {
     #[test]
     fn calc_field_strength_for_line_at_km_with_no_segments() {
         let line = Line::new(0.0);
-        let result = calc_field_strength_for_line_at_km(&line, 1000.0);
+        let result = calc_field_strength_for_line_at_km(&Lfmf, &line, 1000.0);
         assert!(result.is_err());
     }
 
     #[test]
     fn calc_field_strength_for_line_at_km_with_one_ground_segment_whole() {
         let distance = 142.092;
-        let segment = LineSegment::with_length(Terrain::Ground.parameters(), distance);
+        let segment = LineSegment::new(Terrain::Ground.medium(), distance);
         let line = Line::with_segments(0.0, once(segment));
-        let result = calc_field_strength_for_line_at_km(&line, distance).unwrap();
-        let expected = calc_LFMF(segment.lfmf_parameters()).unwrap().E_dBuVm;
+        let result = calc_field_strength_for_line_at_km(&Lfmf, &line, distance).unwrap();
+        let expected = calc_LFMF(line.lfmf_parameters(segment.medium(), distance))
+            .unwrap()
+            .E_dBuVm;
         assert_eq!(result, expected);
     }
 
@@ -152,11 +407,10 @@ mod tests {
     fn calc_field_strength_for_line_at_km_with_one_ground_segment_cut_short() {
         let distance = 203.539;
         let shorter_distance = 95.28;
-        let segment = LineSegment::with_length(Terrain::Ground.parameters(), distance);
+        let segment = LineSegment::new(Terrain::Ground.medium(), distance);
         let line = Line::with_segments(0.0, once(segment));
-        let result = calc_field_strength_for_line_at_km(&line, shorter_distance).unwrap();
-        let mut parameters = segment.lfmf_parameters();
-        parameters.d__km = shorter_distance;
+        let result = calc_field_strength_for_line_at_km(&Lfmf, &line, shorter_distance).unwrap();
+        let parameters = line.lfmf_parameters(segment.medium(), shorter_distance);
         let expected = calc_LFMF(parameters).unwrap().E_dBuVm;
         assert_eq!(result, expected);
     }
@@ -164,10 +418,12 @@ mod tests {
     #[test]
     fn calc_field_strength_for_line_at_km_with_one_sea_segment_whole() {
         let distance = 402.23;
-        let segment = LineSegment::with_length(Terrain::Sea.parameters(), distance);
+        let segment = LineSegment::new(Terrain::Sea.medium(), distance);
         let line = Line::with_segments(0.0, once(segment));
-        let result = calc_field_strength_for_line_at_km(&line, distance).unwrap();
-        let expected = calc_LFMF(segment.lfmf_parameters()).unwrap().E_dBuVm;
+        let result = calc_field_strength_for_line_at_km(&Lfmf, &line, distance).unwrap();
+        let expected = calc_LFMF(line.lfmf_parameters(segment.medium(), distance))
+            .unwrap()
+            .E_dBuVm;
         assert_eq!(result, expected);
     }
 
@@ -175,11 +431,10 @@ mod tests {
     fn calc_field_strength_for_line_at_km_with_one_sea_segment_cut_short() {
         let distance = 523.5;
         let shorter_distance = 235.74;
-        let segment = LineSegment::with_length(Terrain::Sea.parameters(), distance);
+        let segment = LineSegment::new(Terrain::Sea.medium(), distance);
         let line = Line::with_segments(0.0, once(segment));
-        let result = calc_field_strength_for_line_at_km(&line, shorter_distance).unwrap();
-        let mut parameters = segment.lfmf_parameters();
-        parameters.d__km = shorter_distance;
+        let result = calc_field_strength_for_line_at_km(&Lfmf, &line, shorter_distance).unwrap();
+        let parameters = line.lfmf_parameters(segment.medium(), shorter_distance);
         let expected = calc_LFMF(parameters).unwrap().E_dBuVm;
         assert_eq!(result, expected);
     }
@@ -188,11 +443,11 @@ mod tests {
     fn calc_field_strength_for_line_at_km_with_two_segments() {
         let distance = 193.9203;
         let segments = [
-            LineSegment::with_length(Terrain::Ground.parameters(), 50.0),
-            LineSegment::with_length(Terrain::Sea.parameters(), 200.0),
+            LineSegment::new(Terrain::Ground.medium(), 50.0),
+            LineSegment::new(Terrain::Sea.medium(), 200.0),
         ];
         let line = Line::with_segments(0.0, segments);
-        let result = calc_field_strength_for_line_at_km(&line, distance).unwrap();
+        let result = calc_field_strength_for_line_at_km(&Lfmf, &line, distance).unwrap();
         let expected_field_strength = {
             let mut ground_parameters = Terrain::Ground.parameters();
             let mut sea_parameters = Terrain::Sea.parameters();
@@ -226,12 +481,12 @@ mod tests {
     fn calc_field_strength_for_line_at_km_with_three_segments() {
         let distance = 180.0;
         let segments = [
-            LineSegment::with_length(Terrain::Ground.parameters(), 100.0),
-            LineSegment::with_length(Terrain::Sea.parameters(), 50.0),
-            LineSegment::with_length(Terrain::Ground.parameters(), 200.0),
+            LineSegment::new(Terrain::Ground.medium(), 100.0),
+            LineSegment::new(Terrain::Sea.medium(), 50.0),
+            LineSegment::new(Terrain::Ground.medium(), 200.0),
         ];
         let line = Line::with_segments(0.0, segments);
-        let result = calc_field_strength_for_line_at_km(&line, distance).unwrap();
+        let result = calc_field_strength_for_line_at_km(&Lfmf, &line, distance).unwrap();
         let expected_field_strength = {
             let mut ground_parameters = Terrain::Ground.parameters();
             let mut sea_parameters = Terrain::Sea.parameters();
@@ -269,11 +524,67 @@ mod tests {
         );
     }
 
+    #[test]
+    fn explain_millington_field_strength_for_line_at_km_with_three_segments() {
+        let segments = [
+            LineSegment::new(Terrain::Ground.medium(), 100.0),
+            LineSegment::new(Terrain::Sea.medium(), 50.0),
+            LineSegment::new(Terrain::Ground.medium(), 200.0),
+        ];
+        let line = Line::with_segments(0.0, segments);
+        let explanation =
+            explain_millington_field_strength_for_line_at_km(&Lfmf, &line, 180.0).unwrap();
+        let terms = |terms: &[MillingtonTerm]| {
+            terms
+                .iter()
+                .map(|term| (term.terrain, term.distance_km, term.subtracted))
+                .collect::<Vec<_>>()
+        };
+        let (ground, sea) = (Some(Terrain::Ground), Some(Terrain::Sea));
+        assert_eq!(
+            terms(&explanation.forward_terms),
+            [
+                (ground, 100.0, false),
+                (sea, 100.0, true),
+                (sea, 150.0, false),
+                (ground, 150.0, true),
+                (ground, 180.0, false)
+            ]
+        );
+        assert_eq!(
+            terms(&explanation.reverse_terms),
+            [
+                (ground, 30.0, false),
+                (sea, 30.0, true),
+                (sea, 80.0, false),
+                (ground, 80.0, true),
+                (ground, 180.0, false)
+            ]
+        );
+        let term = explanation.forward_terms[1];
+        let mut sea_parameters = Terrain::Sea.parameters();
+        sea_parameters.d__km = 100.0;
+        assert_eq!(
+            term.field_strength,
+            calc_LFMF(sea_parameters).unwrap().E_dBuVm
+        );
+        assert_eq!(
+            term.partial_sum,
+            explanation.forward_terms[0].field_strength - term.field_strength
+        );
+
+        let millington =
+            calc_millington_field_strength_for_line_at_km(&Lfmf, &line, 180.0).unwrap();
+        assert_eq!(explanation.forward, millington.forward);
+        assert_eq!(explanation.reverse, millington.reverse);
+        assert_eq!(explanation.field_strength, millington.field_strength);
+    }
+
     #[test]
     fn find_max_distance_for_old_min_strength_on_small_ground_line() {
-        let segment = LineSegment::with_length(Terrain::Ground.parameters(), 100.0);
+        let segment = LineSegment::new(Terrain::Ground.medium(), 100.0);
         let line = Line::with_segments(0.0, once(segment));
-        let max_distance = find_max_distance_for_line(61.9, &line).unwrap();
+        let max_distance = find_max_distance_for_line(&Lfmf, 61.9, &line).unwrap();
         let expected = 48.7;
         let error = max_distance - expected;
         assert!(
@@ -287,9 +598,9 @@ mod tests {
 
     #[test]
     fn find_max_distance_for_old_min_strength_on_medium_ground_line() {
-        let segment = LineSegment::with_length(Terrain::Ground.parameters(), 500.0);
+        let segment = LineSegment::new(Terrain::Ground.medium(), 500.0);
         let line = Line::with_segments(0.0, once(segment));
-        let max_distance = find_max_distance_for_line(61.9, &line).unwrap();
+        let max_distance = find_max_distance_for_line(&Lfmf, 61.9, &line).unwrap();
         let expected = 48.7;
         let error = max_distance - expected;
         assert!(
@@ -303,9 +614,9 @@ mod tests {
 
     #[test]
     fn find_max_distance_for_old_min_strength_on_biggest_ground_line() {
-        let segment = LineSegment::with_length(Terrain::Ground.parameters(), 10000.0);
+        let segment = LineSegment::new(Terrain::Ground.medium(), 10000.0);
         let line = Line::with_segments(0.0, once(segment));
-        let max_distance = find_max_distance_for_line(61.9, &line).unwrap();
+        let max_distance = find_max_distance_for_line(&Lfmf, 61.9, &line).unwrap();
         let expected = 48.7;
         let error = max_distance - expected;
         assert!(
@@ -319,17 +630,17 @@ mod tests {
 
     #[test]
     fn find_max_distance_for_old_min_strength_on_small_sea_line() {
-        let segment = LineSegment::with_length(Terrain::Sea.parameters(), 100.0);
+        let segment = LineSegment::new(Terrain::Sea.medium(), 100.0);
         let line = Line::with_segments(0.0, once(segment));
-        let max_distance = find_max_distance_for_line(61.9, &line);
+        let max_distance = find_max_distance_for_line(&Lfmf, 61.9, &line);
         assert!(max_distance.is_err());
     }
 
     #[test]
     fn find_max_distance_for_old_min_strength_on_medium_sea_line() {
-        let segment = LineSegment::with_length(Terrain::Sea.parameters(), 500.0);
+        let segment = LineSegment::new(Terrain::Sea.medium(), 500.0);
         let line = Line::with_segments(0.0, once(segment));
-        let max_distance = find_max_distance_for_line(61.9, &line).unwrap();
+        let max_distance = find_max_distance_for_line(&Lfmf, 61.9, &line).unwrap();
         let expected = 353.830;
         let error = max_distance - expected;
         assert!(
@@ -343,9 +654,9 @@ mod tests {
 
     #[test]
     fn find_max_distance_for_old_min_strength_on_biggest_sea_line() {
-        let segment = LineSegment::with_length(Terrain::Sea.parameters(), 10000.0);
+        let segment = LineSegment::new(Terrain::Sea.medium(), 10000.0);
         let line = Line::with_segments(0.0, once(segment));
-        let max_distance = find_max_distance_for_line(61.9, &line).unwrap();
+        let max_distance = find_max_distance_for_line(&Lfmf, 61.9, &line).unwrap();
         let expected = 353.830;
         let error = max_distance - expected;
         assert!(
@@ -359,17 +670,17 @@ mod tests {
 
     #[test]
     fn find_max_distance_for_new_min_strength_on_small_ground_line() {
-        let segment = LineSegment::with_length(Terrain::Ground.parameters(), 100.0);
+        let segment = LineSegment::new(Terrain::Ground.medium(), 100.0);
         let line = Line::with_segments(0.0, once(segment));
-        let max_distance = find_max_distance_for_line(43.2, &line);
+        let max_distance = find_max_distance_for_line(&Lfmf, 43.2, &line);
         assert!(max_distance.is_err());
     }
 
     #[test]
     fn find_max_distance_for_new_min_strength_on_medium_ground_line() {
-        let segment = LineSegment::with_length(Terrain::Ground.parameters(), 500.0);
+        let segment = LineSegment::new(Terrain::Ground.medium(), 500.0);
         let line = Line::with_segments(0.0, once(segment));
-        let max_distance = find_max_distance_for_line(43.2, &line).unwrap();
+        let max_distance = find_max_distance_for_line(&Lfmf, 43.2, &line).unwrap();
         let expected = 123.283;
         let error = max_distance - expected;
         assert!(
@@ -383,9 +694,9 @@ mod tests {
 
     #[test]
     fn find_max_distance_for_new_min_strength_on_biggest_ground_line() {
-        let segment = LineSegment::with_length(Terrain::Ground.parameters(), 10000.0);
+        let segment = LineSegment::new(Terrain::Ground.medium(), 10000.0);
         let line = Line::with_segments(0.0, once(segment));
-        let max_distance = find_max_distance_for_line(43.2, &line).unwrap();
+        let max_distance = find_max_distance_for_line(&Lfmf, 43.2, &line).unwrap();
         let expected = 123.283;
         let error = max_distance - expected;
         assert!(
@@ -399,25 +710,25 @@ mod tests {
 
     #[test]
     fn find_max_distance_for_new_min_strength_on_small_sea_line() {
-        let segment = LineSegment::with_length(Terrain::Sea.parameters(), 100.0);
+        let segment = LineSegment::new(Terrain::Sea.medium(), 100.0);
         let line = Line::with_segments(0.0, once(segment));
-        let max_distance = find_max_distance_for_line(43.2, &line);
+        let max_distance = find_max_distance_for_line(&Lfmf, 43.2, &line);
         assert!(max_distance.is_err());
     }
 
     #[test]
     fn find_max_distance_for_new_min_strength_on_medium_sea_line() {
-        let segment = LineSegment::with_length(Terrain::Sea.parameters(), 500.0);
+        let segment = LineSegment::new(Terrain::Sea.medium(), 500.0);
         let line = Line::with_segments(0.0, once(segment));
-        let max_distance = find_max_distance_for_line(43.2, &line);
+        let max_distance = find_max_distance_for_line(&Lfmf, 43.2, &line);
         assert!(max_distance.is_err());
     }
 
     #[test]
     fn find_max_distance_for_new_min_strength_on_biggest_sea_line() {
-        let segment = LineSegment::with_length(Terrain::Sea.parameters(), 10000.0);
+        let segment = LineSegment::new(Terrain::Sea.medium(), 10000.0);
         let line = Line::with_segments(0.0, once(segment));
-        let max_distance = find_max_distance_for_line(43.2, &line).unwrap();
+        let max_distance = find_max_distance_for_line(&Lfmf, 43.2, &line).unwrap();
         let expected = 749.350;
         let error = max_distance - expected;
         assert!(
@@ -432,13 +743,14 @@ mod tests {
     #[test]
     fn find_max_distance_for_ground_sea_line_far() {
         let segments = [
-            LineSegment::with_length(Terrain::Ground.parameters(), 50.0),
-            LineSegment::with_length(Terrain::Sea.parameters(), 200.0),
+            LineSegment::new(Terrain::Ground.medium(), 50.0),
+            LineSegment::new(Terrain::Sea.medium(), 200.0),
         ];
         let line = Line::with_segments(0.0, segments);
         let expected_distance = 193.9203;
-        let field_strength = calc_field_strength_for_line_at_km(&line, expected_distance).unwrap();
-        let max_distance = find_max_distance_for_line(field_strength, &line).expect("The distance it should find is within the bounds and has already been calculated to be valid.");
+        let field_strength =
+            calc_field_strength_for_line_at_km(&Lfmf, &line, expected_distance).unwrap();
+        let max_distance = find_max_distance_for_line(&Lfmf, field_strength, &line).expect("The distance it should find is within the bounds and has already been calculated to be valid.");
         let error = max_distance - expected_distance;
         assert!(
             error.abs() < DISTANCE_TOLERANCE,
@@ -452,13 +764,14 @@ mod tests {
     #[test]
     fn find_max_distance_for_ground_sea_line_near() {
         let segments = [
-            LineSegment::with_length(Terrain::Ground.parameters(), 50.0),
-            LineSegment::with_length(Terrain::Sea.parameters(), 200.0),
+            LineSegment::new(Terrain::Ground.medium(), 50.0),
+            LineSegment::new(Terrain::Sea.medium(), 200.0),
         ];
         let line = Line::with_segments(0.0, segments);
         let expected_distance = 42.356;
-        let field_strength = calc_field_strength_for_line_at_km(&line, expected_distance).unwrap();
-        let max_distance = find_max_distance_for_line(field_strength, &line).expect("The distance it should find is within the bounds and has already been calculated to be valid.");
+        let field_strength =
+            calc_field_strength_for_line_at_km(&Lfmf, &line, expected_distance).unwrap();
+        let max_distance = find_max_distance_for_line(&Lfmf, field_strength, &line).expect("The distance it should find is within the bounds and has already been calculated to be valid.");
         let error = max_distance - expected_distance;
         assert!(
             error.abs() < DISTANCE_TOLERANCE,
@@ -472,14 +785,15 @@ mod tests {
     #[test]
     fn find_max_distance_for_ground_sea_ground_line_far() {
         let segments = [
-            LineSegment::with_length(Terrain::Ground.parameters(), 100.0),
-            LineSegment::with_length(Terrain::Sea.parameters(), 50.0),
-            LineSegment::with_length(Terrain::Ground.parameters(), 200.0),
+            LineSegment::new(Terrain::Ground.medium(), 100.0),
+            LineSegment::new(Terrain::Sea.medium(), 50.0),
+            LineSegment::new(Terrain::Ground.medium(), 200.0),
         ];
         let line = Line::with_segments(0.0, segments);
         let expected_distance = 180.0;
-        let field_strength = calc_field_strength_for_line_at_km(&line, expected_distance).unwrap();
-        let max_distance = find_max_distance_for_line(field_strength, &line)
+        let field_strength =
+            calc_field_strength_for_line_at_km(&Lfmf, &line, expected_distance).unwrap();
+        let max_distance = find_max_distance_for_line(&Lfmf, field_strength, &line)
             .expect("The distance it should find is within the bounds and has already been calculated to be valid.");
         let error = max_distance - expected_distance;
         assert!(
@@ -494,16 +808,17 @@ mod tests {
     #[test]
     fn find_max_distance_for_ground_sea_ground_line_medium() {
         let segments = [
-            LineSegment::with_length(Terrain::Ground.parameters(), 100.0),
-            LineSegment::with_length(Terrain::Sea.parameters(), 50.0),
-            LineSegment::with_length(Terrain::Ground.parameters(), 200.0),
+            LineSegment::new(Terrain::Ground.medium(), 100.0),
+            LineSegment::new(Terrain::Sea.medium(), 50.0),
+            LineSegment::new(Terrain::Ground.medium(), 200.0),
         ];
         let line = Line::with_segments(0.0, segments);
         // Fun fact: you can find the same field strength for this distance at ~115 km.
         // That's why this test isn't in the sea segment.
         let expected_distance = 82.096;
-        let field_strength = calc_field_strength_for_line_at_km(&line, expected_distance).unwrap();
-        let max_distance = find_max_distance_for_line(field_strength, &line)
+        let field_strength =
+            calc_field_strength_for_line_at_km(&Lfmf, &line, expected_distance).unwrap();
+        let max_distance = find_max_distance_for_line(&Lfmf, field_strength, &line)
             .expect("The distance it should find is within the bounds and has already been calculated to be valid.");
         let error = max_distance - expected_distance;
         assert!(
@@ -518,14 +833,15 @@ mod tests {
     #[test]
     fn find_max_distance_for_ground_sea_ground_line_near() {
         let segments = [
-            LineSegment::with_length(Terrain::Ground.parameters(), 100.0),
-            LineSegment::with_length(Terrain::Sea.parameters(), 50.0),
-            LineSegment::with_length(Terrain::Ground.parameters(), 200.0),
+            LineSegment::new(Terrain::Ground.medium(), 100.0),
+            LineSegment::new(Terrain::Sea.medium(), 50.0),
+            LineSegment::new(Terrain::Ground.medium(), 200.0),
         ];
         let line = Line::with_segments(0.0, segments);
         let expected_distance = 32.637;
-        let field_strength = calc_field_strength_for_line_at_km(&line, expected_distance).unwrap();
-        let max_distance = find_max_distance_for_line(field_strength, &line)
+        let field_strength =
+            calc_field_strength_for_line_at_km(&Lfmf, &line, expected_distance).unwrap();
+        let max_distance = find_max_distance_for_line(&Lfmf, field_strength, &line)
             .expect("The distance it should find is within the bounds and has already been calculated to be valid.");
         let error = max_distance - expected_distance;
         assert!(
@@ -540,15 +856,16 @@ mod tests {
     #[test]
     fn find_max_distance_for_long_4_segment_line() {
         let segments = [
-            LineSegment::with_length(Terrain::Ground.parameters(), 100.0),
-            LineSegment::with_length(Terrain::Sea.parameters(), 300.0),
-            LineSegment::with_length(Terrain::Ground.parameters(), 100.0),
-            LineSegment::with_length(Terrain::Sea.parameters(), 500.0),
+            LineSegment::new(Terrain::Ground.medium(), 100.0),
+            LineSegment::new(Terrain::Sea.medium(), 300.0),
+            LineSegment::new(Terrain::Ground.medium(), 100.0),
+            LineSegment::new(Terrain::Sea.medium(), 500.0),
         ];
         let line = Line::with_segments(0.0, segments);
         let expected_distance = 932.2594;
-        let field_strength = calc_field_strength_for_line_at_km(&line, expected_distance).unwrap();
-        let max_distance = find_max_distance_for_line(field_strength, &line)
+        let field_strength =
+            calc_field_strength_for_line_at_km(&Lfmf, &line, expected_distance).unwrap();
+        let max_distance = find_max_distance_for_line(&Lfmf, field_strength, &line)
             .expect("The distance it should find is within the bounds and has already been calculated to be valid.");
         let error = max_distance - expected_distance;
         assert!(
@@ -560,10 +877,39 @@ mod tests {
         );
     }
 
+    #[test]
+    fn find_max_distances_for_several_min_strengths() {
+        let segments = [
+            LineSegment::new(Terrain::Ground.medium(), 100.0),
+            LineSegment::new(Terrain::Sea.medium(), 50.0),
+            LineSegment::new(Terrain::Ground.medium(), 200.0),
+        ];
+        let line = Line::with_segments(0.0, segments);
+        let min_field_strengths = [43.2, 50.0, 60.0, 70.0];
+        let max_distances =
+            find_max_distances_for_line(&Lfmf, &min_field_strengths, &line).unwrap();
+        for (min_field_strength, max_distance) in min_field_strengths.iter().zip(max_distances) {
+            let expected = find_max_distance_for_line(&Lfmf, *min_field_strength, &line).unwrap();
+            assert_eq!(max_distance, Some(expected));
+        }
+    }
+
+    #[test]
+    fn find_max_distances_for_unreachable_min_strength() {
+        let segment = LineSegment::new(Terrain::Ground.medium(), 100.0);
+        let line = Line::with_segments(0.0, once(segment));
+        let max_distances = find_max_distances_for_line(&Lfmf, &[61.9, 43.2], &line).unwrap();
+        assert_eq!(
+            max_distances[0],
+            Some(find_max_distance_for_line(&Lfmf, 61.9, &line).unwrap())
+        );
+        assert_eq!(max_distances[1], None);
+    }
+
     #[test]
     fn find_max_distance_for_empty_line() {
         let line = Line::new(0.0);
-        let max_distance = find_max_distance_for_line(61.9, &line);
+        let max_distance = find_max_distance_for_line(&Lfmf, 61.9, &line);
         assert!(max_distance.is_err());
     }
 }
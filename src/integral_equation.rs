@@ -0,0 +1,259 @@
+//! Ground wave over irregular terrain with the integral equation method of Hufford, as in `Gwave_Paper.doc`,
+//! instead of LFMF over a smooth earth and Millington.
+//!
+//! The attenuation function `W` of a path is marched out from the transmitter over an elevation profile,
+//! with the ground constants of the line at every step, so that a single solution gives the field strength
+//! at every distance along the line. Both antennas are taken to be on the ground.
+
+use crate::terrain::{Line, Medium, Polarization, Transmitter};
+use anyhow::{ensure, Context, Result};
+use num_complex::Complex64;
+use std::f64::consts::{FRAC_PI_4, PI};
+
+/// The speed of light in m/s.
+const C: f64 = 299_792_458.0;
+/// The intrinsic impedance of free space in ohms, the same as in LFMF.
+const ETA: f64 = 119.9169832 * PI;
+/// The radius of the earth in km, the same as in LFMF.
+const EARTH_RADIUS_KM: f64 = 6370.0;
+/// The gain of the short monopole of LFMF in dBi.
+const ANTENNA_GAIN_DBI: f64 = 4.77;
+/// The most steps a line is solved in, which bounds the time of the quadratic march on long lines.
+const MAX_STEPS: f64 = 4000.0;
+
+/// The heights of the ground in metres every `step_km` from the transmitter, with the first at the transmitter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Profile {
+    step_km: f64,
+    heights_m: Vec<f64>,
+}
+
+impl Profile {
+    pub fn new(step_km: f64, heights_m: Vec<f64>) -> Result<Self> {
+        ensure!(
+            step_km > 0.0,
+            "The step of the profile must be positive but it was {step_km} km."
+        );
+        ensure!(
+            heights_m.len() >= 2,
+            "The profile must have at least 2 heights."
+        );
+        Ok(Self { step_km, heights_m })
+    }
+
+    /// A profile of a smooth earth, at sea level all the way.
+    pub fn smooth(length_km: f64, step_km: f64) -> Result<Self> {
+        let samples = (length_km / step_km).round() as usize;
+        Self::new(step_km, vec![0.0; samples + 1])
+    }
+
+    pub fn step_km(&self) -> f64 {
+        self.step_km
+    }
+
+    pub fn length_km(&self) -> f64 {
+        (self.heights_m.len() - 1) as f64 * self.step_km
+    }
+
+    /// The step in km to solve `line` in, about a quarter of a wavelength unless the line is too long for it,
+    /// so that the line ends on a step.
+    pub fn step_km_for(line: &Line) -> f64 {
+        let wavelength_km = C / (line.transmitter().frequency_mhz * 1e6) / 1000.0;
+        let steps = (line.max_distance() / (wavelength_km / 4.0))
+            .ceil()
+            .clamp(1.0, MAX_STEPS);
+        line.max_distance() / steps
+    }
+}
+
+/// The field strength along a line over a profile, from its attenuation function.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Solution {
+    step_km: f64,
+    /// The field strength in dB(uV)/m at every step of the profile after the transmitter.
+    field_strengths: Vec<f64>,
+}
+
+impl Solution {
+    /// The field strength in dB(uV)/m at `distance` km, interpolated between the steps of the profile.
+    pub fn field_strength_at_km(&self, distance: f64) -> Result<f64> {
+        let length_km = self.field_strengths.len() as f64 * self.step_km;
+        ensure!(
+            distance >= self.step_km && distance <= length_km * (1.0 + 1e-9),
+            "The distance of {distance} km is outside of the {} to {length_km} km that were solved.",
+            self.step_km
+        );
+        let position = (distance / self.step_km - 1.0).max(0.0);
+        let index = (position.floor() as usize).min(self.field_strengths.len() - 1);
+        let Some(&next) = self.field_strengths.get(index + 1) else {
+            return Ok(self.field_strengths[index]);
+        };
+        let previous = self.field_strengths[index];
+        Ok(previous + (next - previous) * (position - index as f64))
+    }
+}
+
+/// Solves the integral equation along `line` over the terrain of `profile`, which must reach the end of the line.
+pub fn solve(line: &Line, profile: &Profile) -> Result<Solution> {
+    line.validate()
+        .context("The line can't be solved with the integral equation")?;
+    ensure!(
+        profile.length_km() >= line.max_distance() - profile.step_km / 2.0,
+        "The profile ends at {} km before the end of the line at {} km.",
+        profile.length_km(),
+        line.max_distance()
+    );
+
+    let transmitter = line.transmitter();
+    let wavelength_km = C / (transmitter.frequency_mhz * 1e6) / 1000.0;
+    let k = 2.0 * PI / wavelength_km;
+    let effective_radius_km =
+        EARTH_RADIUS_KM / (1.0 - 0.04665 * (0.005577 * line.surface_refractivity()).exp());
+    let step_km = profile.step_km;
+    let steps = ((line.max_distance() / step_km).round() as usize).min(profile.heights_m.len() - 1);
+
+    // The heights of the surface in km under the chord of the curved earth from the transmitter.
+    let heights = profile.heights_m[..=steps]
+        .iter()
+        .enumerate()
+        .map(|(i, height_m)| {
+            let x = i as f64 * step_km;
+            height_m / 1000.0 - x * x / (2.0 * effective_radius_km)
+        })
+        .collect::<Vec<_>>();
+    // The surface impedance, the height and the slope in the middle of every step.
+    let impedances = (0..steps)
+        .map(|i| surface_impedance(medium_at(line, (i as f64 + 0.5) * step_km), transmitter))
+        .collect::<Vec<_>>();
+    let middles = heights
+        .windows(2)
+        .map(|pair| ((pair[0] + pair[1]) / 2.0, (pair[1] - pair[0]) / step_km))
+        .collect::<Vec<_>>();
+
+    let mut attenuations = vec![Complex64::new(1.0, 0.0)];
+    let mut kernel = Vec::with_capacity(steps);
+    for n in 1..=steps {
+        let x = n as f64 * step_km;
+        let y = heights[n];
+        // The weight of every step is the exact integral of 1 / sqrt(ξ (x - ξ)) over it.
+        let angle = |i: usize| (i as f64 / n as f64).sqrt().asin();
+        kernel.clear();
+        kernel.extend((0..n).map(|i| {
+            let xi = (i as f64 + 0.5) * step_km;
+            let (y_xi, slope) = middles[i];
+            let geometry = (y - y_xi) / (x - xi) - slope;
+            let path_difference = (y_xi - heights[0]).powi(2) / (2.0 * xi)
+                + (y - y_xi).powi(2) / (2.0 * (x - xi))
+                - (y - heights[0]).powi(2) / (2.0 * x);
+            let weight = 2.0 * (angle(i + 1) - angle(i));
+            (impedances[i] - geometry) * Complex64::from_polar(weight, -k * path_difference)
+        }));
+        let factor = Complex64::from_polar((x / wavelength_km).sqrt(), FRAC_PI_4);
+        // W is taken as the average of the ends of every step, so the unknown W(x) appears in the last one.
+        let known = (0..n - 1)
+            .map(|i| kernel[i] * (attenuations[i] + attenuations[i + 1]) / 2.0)
+            .sum::<Complex64>()
+            + kernel[n - 1] * attenuations[n - 1] / 2.0;
+        let attenuation = (1.0 - factor * known) / (1.0 + factor * kernel[n - 1] / 2.0);
+        ensure!(
+            attenuation.is_finite(),
+            "The integral equation diverged at {x} km."
+        );
+        attenuations.push(attenuation);
+    }
+
+    // The field of the short monopole of LFMF in mV/m at 1 km, doubled by the ground under it.
+    let gain = 10f64.powf(ANTENNA_GAIN_DBI / 10.0);
+    let e_0 = (ETA * transmitter.power_w * gain / (4.0 * PI)).sqrt();
+    let field_strengths = attenuations[1..]
+        .iter()
+        .enumerate()
+        .map(|(i, attenuation)| {
+            let x = (i + 1) as f64 * step_km;
+            60.0 + 20.0 * (attenuation.norm() * e_0 / x).log10()
+        })
+        .collect();
+    Ok(Solution {
+        step_km,
+        field_strengths,
+    })
+}
+
+/// The medium of the segment of `line` at `distance` km, or of the last one beyond its end.
+fn medium_at(line: &Line, distance: f64) -> Medium {
+    let mut end_km = 0.0;
+    let mut medium = None;
+    for segment in line.segments() {
+        end_km += segment.length_km();
+        medium = Some(segment.medium());
+        if distance < end_km {
+            break;
+        }
+    }
+    medium.expect("The line was validated to have segments.")
+}
+
+/// The normalised surface impedance of the ground, the same as in LFMF.
+fn surface_impedance(medium: Medium, transmitter: Transmitter) -> Complex64 {
+    const EPSILON_0: f64 = 8.854187817e-12;
+    let frequency_hz = transmitter.frequency_mhz * 1e6;
+    let eta = Complex64::new(
+        medium.epsilon,
+        -medium.sigma / (EPSILON_0 * 2.0 * PI * frequency_hz),
+    );
+    let delta = (eta - 1.0).sqrt();
+    match transmitter.polarization {
+        Polarization::Vertical => delta / eta,
+        Polarization::Horizontal => delta,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::find_max_distance::calc_field_strength_for_line_at_km;
+    use crate::propagation_model::Lfmf;
+    use crate::terrain::defaults::Terrain;
+
+    #[test]
+    fn smooth_earth_is_close_to_lfmf() {
+        for terrain in [Terrain::Ground, Terrain::Sea] {
+            let line = Line::builder(0.0).segment(terrain, 200.0).build().unwrap();
+            let profile = Profile::smooth(200.0, Profile::step_km_for(&line)).unwrap();
+            let solution = solve(&line, &profile).unwrap();
+            for distance in [10.0, 50.0, 100.0, 200.0] {
+                let expected = calc_field_strength_for_line_at_km(&Lfmf, &line, distance).unwrap();
+                let field_strength = solution.field_strength_at_km(distance).unwrap();
+                assert!(
+                    (field_strength - expected).abs() < 0.5,
+                    "Expected {expected} dB(uV)/m over {terrain:?} at {distance} km, got {field_strength} dB(uV)/m."
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn a_ridge_shadows_the_field_behind_it() {
+        let line = Line::builder(0.0)
+            .segment(Terrain::Ground, 60.0)
+            .build()
+            .unwrap();
+        let step_km = Profile::step_km_for(&line);
+        let smooth = solve(&line, &Profile::smooth(60.0, step_km).unwrap()).unwrap();
+        // A ridge 1 km high and 4 km wide at 20 km.
+        let heights = (0..=(60.0 / step_km).round() as usize)
+            .map(|i| 1000.0 * (1.0 - (i as f64 * step_km - 20.0).abs() / 2.0).max(0.0))
+            .collect();
+        let ridge = solve(&line, &Profile::new(step_km, heights).unwrap()).unwrap();
+        let difference = |distance| {
+            ridge.field_strength_at_km(distance).unwrap()
+                - smooth.field_strength_at_km(distance).unwrap()
+        };
+        // The march only looks back, so nothing changes before the ridge.
+        assert_eq!(difference(10.0), 0.0);
+        assert!(difference(20.0) > 3.0, "{}", difference(20.0));
+        assert!(difference(22.0) < -3.0, "{}", difference(22.0));
+        assert!(difference(22.0) < difference(60.0), "{}", difference(60.0));
+        assert!(smooth.field_strength_at_km(60.5).is_err());
+    }
+}
@@ -5,6 +5,9 @@ use std::error::Error;
 use std::ffi::{c_double, c_int};
 use std::fmt::{Display, Formatter};
 
+use crate::terrain::defaults::Terrain;
+use crate::terrain::{Medium, Receiver, Transmitter};
+
 #[repr(C)]
 #[derive(Debug, Copy, Clone)]
 struct c_Result {
@@ -31,20 +34,174 @@ extern "C" {
     ) -> c_int;
 }
 
-#[derive(Debug, Copy, Clone)]
+/// The inputs of LFMF for a single path of homogeneous ground.
+/// Outside of this crate they are made with [`LFMF_Parameters::builder`], which checks them against the ranges that LFMF accepts.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LFMF_Parameters {
-    pub h_tx__meter: f64,
-    pub h_rx__meter: f64,
-    pub f__mhz: f64,
-    pub P_tx__watt: f64,
-    pub N_s: f64,
-    pub d__km: f64,
-    pub epsilon: f64,
-    pub sigma: f64,
-    pub pol: i32,
+    pub(crate) h_tx__meter: f64,
+    pub(crate) h_rx__meter: f64,
+    pub(crate) f__mhz: f64,
+    pub(crate) P_tx__watt: f64,
+    pub(crate) N_s: f64,
+    pub(crate) d__km: f64,
+    pub(crate) epsilon: f64,
+    pub(crate) sigma: f64,
+    pub(crate) pol: i32,
 }
 
+impl LFMF_Parameters {
+    /// Puts together the parameters of a path of `d__km` over `medium` between the stations.
+    pub(crate) const fn assemble(
+        transmitter: &Transmitter,
+        receiver: &Receiver,
+        N_s: f64,
+        medium: &Medium,
+        d__km: f64,
+    ) -> Self {
+        Self {
+            h_tx__meter: transmitter.height_m,
+            h_rx__meter: receiver.height_m,
+            f__mhz: transmitter.frequency_mhz,
+            P_tx__watt: transmitter.power_w,
+            N_s,
+            d__km,
+            epsilon: medium.epsilon,
+            sigma: medium.sigma,
+            pol: transmitter.polarization.lfmf_code(),
+        }
+    }
+
+    /// Starts from the parameters of our project over land.
+    pub fn builder() -> LFMF_ParametersBuilder {
+        LFMF_ParametersBuilder {
+            parameters: Terrain::Ground.parameters(),
+        }
+    }
+
+    /// Transmitter height in m.
+    pub fn h_tx__meter(&self) -> f64 {
+        self.h_tx__meter
+    }
+    /// Receiver height in m.
+    pub fn h_rx__meter(&self) -> f64 {
+        self.h_rx__meter
+    }
+    /// Frequency in MHz.
+    pub fn f__mhz(&self) -> f64 {
+        self.f__mhz
+    }
+    /// Transmitter power in W.
+    pub fn P_tx__watt(&self) -> f64 {
+        self.P_tx__watt
+    }
+    /// Surface refractivity in N-units.
+    pub fn N_s(&self) -> f64 {
+        self.N_s
+    }
+    /// Path distance in km.
+    pub fn d__km(&self) -> f64 {
+        self.d__km
+    }
+    /// Relative permittivity of the ground.
+    pub fn epsilon(&self) -> f64 {
+        self.epsilon
+    }
+    /// Conductivity of the ground in S/m.
+    pub fn sigma(&self) -> f64 {
+        self.sigma
+    }
+    /// Polarization, 0 for horizontal and 1 for vertical.
+    pub fn pol(&self) -> i32 {
+        self.pol
+    }
+
+    /// Checks the parameters the same way LFMF does before calculating anything.
+    pub fn validate(&self) -> Result<(), LFMF_Error> {
+        let status = if !(0.0..=50.0).contains(&self.h_tx__meter) {
+            1000
+        } else if !(0.0..=50.0).contains(&self.h_rx__meter) {
+            1001
+        } else if !(0.01..=30.0).contains(&self.f__mhz) {
+            1002
+        } else if self.P_tx__watt <= 0.0 {
+            1003
+        } else if !(250.0..=400.0).contains(&self.N_s) {
+            1004
+        } else if !(0.001..=10000.0).contains(&self.d__km) {
+            1005
+        } else if self.epsilon < 1.0 {
+            1006
+        } else if self.sigma <= 0.0 {
+            1007
+        } else if !matches!(self.pol, 0 | 1) {
+            1008
+        } else {
+            return Ok(());
+        };
+        Err(LFMF_Error::new(status, self))
+    }
+}
+
+/// Builds [`LFMF_Parameters`], checking them once they are complete.
 #[derive(Debug, Copy, Clone)]
+pub struct LFMF_ParametersBuilder {
+    parameters: LFMF_Parameters,
+}
+
+impl LFMF_ParametersBuilder {
+    pub fn h_tx__meter(mut self, h_tx__meter: f64) -> Self {
+        self.parameters.h_tx__meter = h_tx__meter;
+        self
+    }
+    pub fn h_rx__meter(mut self, h_rx__meter: f64) -> Self {
+        self.parameters.h_rx__meter = h_rx__meter;
+        self
+    }
+    pub fn f__mhz(mut self, f__mhz: f64) -> Self {
+        self.parameters.f__mhz = f__mhz;
+        self
+    }
+    pub fn P_tx__watt(mut self, P_tx__watt: f64) -> Self {
+        self.parameters.P_tx__watt = P_tx__watt;
+        self
+    }
+    pub fn N_s(mut self, N_s: f64) -> Self {
+        self.parameters.N_s = N_s;
+        self
+    }
+    pub fn d__km(mut self, d__km: f64) -> Self {
+        self.parameters.d__km = d__km;
+        self
+    }
+    pub fn epsilon(mut self, epsilon: f64) -> Self {
+        self.parameters.epsilon = epsilon;
+        self
+    }
+    pub fn sigma(mut self, sigma: f64) -> Self {
+        self.parameters.sigma = sigma;
+        self
+    }
+    /// Sets both electrical parameters to those of a terrain.
+    pub fn terrain(mut self, terrain: Terrain) -> Self {
+        let medium = terrain.medium();
+        self.parameters.epsilon = medium.epsilon;
+        self.parameters.sigma = medium.sigma;
+        self
+    }
+    pub fn pol(mut self, pol: i32) -> Self {
+        self.parameters.pol = pol;
+        self
+    }
+
+    pub fn build(self) -> Result<LFMF_Parameters, LFMF_Error> {
+        self.parameters.validate()?;
+        Ok(self.parameters)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LFMF_Result {
     pub A_btl__db: f64,
     pub E_dBuVm: f64,
@@ -53,6 +210,32 @@ pub struct LFMF_Result {
     pub method: i32,
 }
 
+/// The gain of the short vertical monopoles that LFMF assumes for both terminals.
+const ANTENNA_GAIN__DBI: f64 = 4.77;
+/// Intrinsic impedance of free space in ohms.
+const ETA: f64 = 119.9169832 * std::f64::consts::PI;
+/// Speed of light in m/s.
+const C: f64 = 299792458.0;
+
+/// The received power in dBm for a field strength in dB(uV)/m, calculated the same way LFMF does for its own result.
+/// Useful for field strengths that LFMF didn't calculate directly, like those of mixed paths.
+pub fn received_power_dbm(E_dBuVm: f64, f__mhz: f64) -> f64 {
+    E_dBuVm + ANTENNA_GAIN__DBI - 20.0 * (f__mhz * 1e6).log10() + 42.8
+}
+
+/// The basic transmission loss in dB for a field strength in dB(uV)/m, calculated the same way LFMF does for its own result.
+/// Useful for field strengths that LFMF didn't calculate directly, like those of mixed paths.
+pub fn basic_transmission_loss_db(E_dBuVm: f64, f__mhz: f64, P_tx__watt: f64) -> f64 {
+    let G_tx = 10f64.powf(ANTENNA_GAIN__DBI / 10.0);
+    // The field strength in dB(V/m).
+    let E_dBVm = E_dBuVm - 120.0;
+    10.0 * (P_tx__watt * G_tx).log10()
+        + 10.0 * (ETA * 4.0 * std::f64::consts::PI).log10()
+        + 20.0 * (f__mhz * 1e6).log10()
+        - E_dBVm
+        - 20.0 * C.log10()
+}
+
 #[derive(Debug, Clone)]
 pub struct LFMF_Error {
     pub status: i32,
@@ -67,97 +250,130 @@ impl Display for LFMF_Error {
 
 impl Error for LFMF_Error {}
 
-pub fn calc_LFMF(parameters: LFMF_Parameters) -> Result<LFMF_Result, LFMF_Error> {
-    let mut c_result = c_Result {
-        A_btl__db: 0.0,
-        E_dBuVm: 0.0,
-        P_rx__dbm: 0.0,
-        method: 0,
-    };
-    let status = unsafe {
-        LFMF(
-            parameters.h_tx__meter,
-            parameters.h_rx__meter,
-            parameters.f__mhz,
-            parameters.P_tx__watt,
-            parameters.N_s,
-            parameters.d__km,
-            parameters.epsilon,
-            parameters.sigma,
-            parameters.pol,
-            &mut c_result,
-        )
-    };
-    match status {
-        0 => Ok(LFMF_Result {
-            A_btl__db: c_result.A_btl__db,
-            E_dBuVm: c_result.E_dBuVm,
-            P_rx__dbm: c_result.P_rx__dbm,
-            method: c_result.method,
-        }),
-        1000 => Err(LFMF_Error {
-            status,
-            message: format!(
+impl LFMF_Error {
+    fn new(status: i32, parameters: &LFMF_Parameters) -> Self {
+        let message = match status {
+            1000 => format!(
                 "TX terminal height is {} which is out of the range 0 <= h_tx__meter <= 50.",
                 { parameters.h_tx__meter }
             ),
-        }),
-        1001 => Err(LFMF_Error {
-            status,
-            message: format!(
+            1001 => format!(
                 "RX terminal height is {} which is out of the range 0 <= h_rx__meter <= 50.",
                 { parameters.h_rx__meter }
             ),
-        }),
-        1002 => Err(LFMF_Error {
-            status,
-            message: format!(
+            1002 => format!(
                 "Frequency is {} which is out of the range 0.01 <= f__mhz <= 30.",
                 { parameters.f__mhz }
             ),
-        }),
-        1003 => Err(LFMF_Error {
-            status,
-            message: format!(
+            1003 => format!(
                 "Transmit power is {} which is out of the range 0 < P_tx__watt.",
                 { parameters.P_tx__watt }
             ),
-        }),
-        1004 => Err(LFMF_Error {
-            status,
-            message: format!(
+            1004 => format!(
                 "Surface refractivity is {} which is out of the range 250 <= N_s <= 400.",
                 { parameters.N_s }
             ),
-        }),
-        1005 => Err(LFMF_Error {
-            status,
-            message: format!(
+            1005 => format!(
                 "Path distance is {} which is out of the range 0.001 <= d__km <= 10000.",
                 { parameters.d__km }
             ),
-        }),
-        1006 => Err(LFMF_Error {
-            status,
-            message: format!(
+            1006 => format!(
                 "Epsilon (relative permittivity) is {} which is out of the range 1 <= epsilon.",
                 { parameters.epsilon }
             ),
-        }),
-        1007 => Err(LFMF_Error {
-            status,
-            message: format!(
+            1007 => format!(
                 "Sigma (conductivity) is {} which is out of the range 0 < sigma.",
                 { parameters.sigma }
             ),
-        }),
-        1008 => Err(LFMF_Error {
-            status,
-            message: format!(
+            1008 => format!(
                 "Polarization is {} which is invalid as it must be either 0 or 1.",
                 { parameters.pol }
             ),
+            _ => unreachable!("Unknown lfmf error status: {}", status),
+        };
+        Self { status, message }
+    }
+}
+
+pub fn calc_LFMF(parameters: LFMF_Parameters) -> Result<LFMF_Result, LFMF_Error> {
+    let mut c_result = c_Result {
+        A_btl__db: 0.0,
+        E_dBuVm: 0.0,
+        P_rx__dbm: 0.0,
+        method: 0,
+    };
+    let status = unsafe {
+        LFMF(
+            parameters.h_tx__meter,
+            parameters.h_rx__meter,
+            parameters.f__mhz,
+            parameters.P_tx__watt,
+            parameters.N_s,
+            parameters.d__km,
+            parameters.epsilon,
+            parameters.sigma,
+            parameters.pol,
+            &mut c_result,
+        )
+    };
+    match status {
+        0 => Ok(LFMF_Result {
+            A_btl__db: c_result.A_btl__db,
+            E_dBuVm: c_result.E_dBuVm,
+            P_rx__dbm: c_result.P_rx__dbm,
+            method: c_result.method,
         }),
-        _ => unreachable!("Unknown lfmf error status: {}", status),
+        status => Err(LFMF_Error::new(status, &parameters)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DB_TOLERANCE: f64 = 1e-9;
+
+    #[test]
+    fn received_power_and_loss_match_lfmf() {
+        for terrain in [Terrain::Ground, Terrain::Sea] {
+            let parameters = terrain.parameters();
+            let result = calc_LFMF(parameters).unwrap();
+            let received_power = received_power_dbm(result.E_dBuVm, parameters.f__mhz);
+            let loss = basic_transmission_loss_db(
+                result.E_dBuVm,
+                parameters.f__mhz,
+                parameters.P_tx__watt,
+            );
+            assert!((received_power - result.P_rx__dbm).abs() < DB_TOLERANCE);
+            assert!((loss - result.A_btl__db).abs() < DB_TOLERANCE);
+        }
+    }
+
+    #[test]
+    fn builder_rejects_what_lfmf_rejects() {
+        let invalid = [
+            LFMF_Parameters::builder().h_tx__meter(51.0),
+            LFMF_Parameters::builder().h_rx__meter(-1.0),
+            LFMF_Parameters::builder().f__mhz(0.001),
+            LFMF_Parameters::builder().P_tx__watt(0.0),
+            LFMF_Parameters::builder().N_s(500.0),
+            LFMF_Parameters::builder().d__km(20000.0),
+            LFMF_Parameters::builder().epsilon(0.5),
+            LFMF_Parameters::builder().sigma(-0.1),
+            LFMF_Parameters::builder().pol(2),
+        ];
+        for builder in invalid {
+            let error = builder.build().unwrap_err();
+            let lfmf_error = calc_LFMF(builder.parameters).unwrap_err();
+            assert_eq!(error.status, lfmf_error.status);
+        }
+
+        let parameters = LFMF_Parameters::builder()
+            .terrain(Terrain::Sea)
+            .d__km(50.0)
+            .build()
+            .unwrap();
+        assert_eq!(parameters.sigma(), Terrain::Sea.parameters().sigma);
+        assert!(calc_LFMF(parameters).is_ok());
     }
 }
@@ -0,0 +1,54 @@
+//! Ground wave coverage of a transmitter over mixed paths of land and sea.
+//!
+//! A path is a [`Line`] of segments of homogeneous ground, each evaluated with a [`PropagationModel`]
+//! such as LFMF (ITU-R P.368) and combined with the Millington method. The coverage of a line is the furthest distance at which
+//! the field strength is still at least a minimum usable one.
+//!
+//! ```
+//! use wave_propagation::{
+//!     calc_field_strength_for_line_at_km, find_max_distance_for_line, Lfmf, Line, Terrain,
+//! };
+//!
+//! let line = Line::builder(50.0)
+//!     .segment(Terrain::Ground, 38.0)
+//!     .segment(Terrain::Sea, 61.0)
+//!     .segment(Terrain::Ground, 191.0)
+//!     .build()?;
+//! let field_strength = calc_field_strength_for_line_at_km(&Lfmf, &line, 87.0)?;
+//! let max_distance = find_max_distance_for_line(&Lfmf, 43.2, &line)?;
+//! assert!(field_strength > 43.2 && max_distance > 87.0);
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+//!
+//! Lines can also be read from our csv files and workbooks with [`file::read`] or from the path notation with [`notation::parse`].
+
+#[cfg(feature = "capi")]
+mod capi;
+pub mod coverage;
+pub mod file;
+pub mod find_max_distance;
+pub mod integral_equation;
+pub mod lfmf;
+pub mod mixed_path;
+pub mod monte_carlo;
+pub mod plot;
+pub mod propagation_model;
+#[cfg(feature = "python")]
+mod python;
+pub mod radials;
+pub mod scenario;
+pub mod sensitivity;
+pub mod server;
+pub mod skywave;
+pub mod terrain;
+
+pub use find_max_distance::{
+    calc_field_strength_for_line_at_km, calc_millington_field_strength_for_line_at_km,
+    explain_millington_field_strength_for_line_at_km, find_max_distance_for_line,
+    find_max_distances_for_line,
+};
+pub use lfmf::{LFMF_Parameters, LFMF_ParametersBuilder};
+pub use mixed_path::MixedPathMethod;
+pub use propagation_model::{HomogeneousPath, Lfmf, PropagationModel};
+pub use terrain::defaults::Terrain;
+pub use terrain::{notation, Line, LineBuilder, LineSegment};
@@ -1,39 +1,161 @@
-use crate::find_max_distance::{calc_field_strength_for_line_at_km, find_max_distance_for_line};
-use crate::terrain::defaults::Terrain;
-use crate::terrain::{Line, LineSegment};
-use anyhow::{bail, Result};
+use crate::cli::{
+    Command, CoverageArgs, ExplainArgs, Input, InputArgs, InspectArgs, Method, MonteCarloArgs,
+    PointArgs, ProfileArgs, ScenarioArgs, SensitivityArgs, ServeArgs, SweepArgs, TuiArgs,
+    ValidateArgs,
+};
+use anyhow::{bail, ensure, Context, Result};
 use rayon::prelude::*;
+use serde::Serialize;
+use std::fmt::Write as _;
 use std::path::Path;
+use wave_propagation::file::Severity;
+use wave_propagation::find_max_distance::{
+    calc_field_strength_for_line_at_km, calc_millington_field_strength_for_line_at_km,
+    explain_millington_field_strength_for_line_at_km, find_max_distance_for_line,
+    find_max_distances_for_line_with, MillingtonTerm,
+};
+use wave_propagation::integral_equation::{self, Profile, Solution};
+use wave_propagation::mixed_path::MixedPathMethod;
+use wave_propagation::propagation_model::{Lfmf, PropagationModel, TabulatedCurves};
+use wave_propagation::radials::atlas::Atlas;
+use wave_propagation::radials::dem::Dem;
+use wave_propagation::radials::vector::RadialOptions;
+use wave_propagation::radials::{mask, osm, vector};
+use wave_propagation::skywave::Skywave;
+use wave_propagation::terrain::{notation, Line};
+use wave_propagation::{file, lfmf, monte_carlo, plot, scenario, sensitivity, server};
+#[cfg(feature = "serde")]
+use {
+    crate::cli::{CompareArgs, ReplayArgs},
+    wave_propagation::coverage::{Coverage, Difference, LineCoverage},
+};
+
 mod cli;
-mod file;
-mod find_max_distance;
-mod lfmf;
-mod plot;
-mod terrain;
+mod tui;
 
 fn main() -> Result<()> {
     let cli = cli::parse();
 
-    print!("Hello, world!\n\n");
+    match cli.command {
+        Command::Coverage(args) => find_coverage(args),
+        Command::Point(args) => query_point(args),
+        Command::Explain(args) => explain(args),
+        Command::Profile(args) => export_profile(args),
+        Command::Sweep(args) => sweep(args),
+        Command::Validate(args) => validate(args),
+        Command::Inspect(args) => inspect(args),
+        Command::Sensitivity(args) => analyse_sensitivity(args),
+        Command::MonteCarlo(args) => run_monte_carlo(args),
+        Command::Serve(args) => serve(args),
+        Command::Tui(args) => explore(args),
+        #[cfg(feature = "serde")]
+        Command::Replay(args) => replay(args),
+        #[cfg(feature = "serde")]
+        Command::Compare(args) => compare(args),
+    }
+}
 
-    let min_e = cli.min_field_strength;
-    match cli.input_file {
-        Some(path) => {
-            find_distances_for_input_file(min_e, &path, !cli.no_plot)?;
-        }
-        None => {
-            find_distance_for_hardcoded_line(min_e, !cli.no_plot)?;
+fn find_coverage(args: CoverageArgs) -> Result<()> {
+    let lines = load_lines(&args.input, &args.scenario)?;
+    let solutions = match args.method {
+        Method::Millington | Method::Kirke | Method::Suda | Method::EquivalentDistance => None,
+        Method::IntegralEquation => {
+            #[cfg(feature = "serde")]
+            ensure!(
+                args.save.is_none(),
+                "Runs of the integral equation can't be saved, since replaying them needs the elevation tiles."
+            );
+            Some(solve_integral_equation(&lines, &args)?)
         }
+    };
+    #[cfg(feature = "serde")]
+    ensure!(
+        args.save.is_none() || args.curves.is_none(),
+        "Runs with tabulated curves can't be saved, since replaying them needs the curves."
+    );
+    let model = propagation_model(args.curves.as_deref())?;
+    let method = args.method.mixed_path().unwrap_or_default();
+    let field_strength = FieldStrength {
+        model: model.as_ref(),
+        method,
+        solutions: solutions.as_deref(),
+    };
+    let transmitter_location = args.input.radials.transmitter_lat_lon;
+    let skywave = args.night.skywave();
+    ensure!(
+        skywave.is_none() || transmitter_location.is_some(),
+        "Night mode needs the position of the transmitter with `--transmitter-lat-lon` for the skywave."
+    );
+    let min_es = args.min_field_strength.0;
+    let max_distances = if let [min_e] = min_es[..] {
+        find_distances_for_lines(min_e, &lines, &field_strength, !args.no_plot)?
+            .into_iter()
+            .map(|max_distance| vec![Some(max_distance)])
+            .collect()
+    } else {
+        find_distances_for_lines_at_thresholds(&min_es, &lines, &field_strength, !args.no_plot)?
+    };
+
+    if let (Some(ratio), Some(skywave), Some(location)) =
+        (args.night.night_ratio, skywave, transmitter_location)
+    {
+        find_night_distances(
+            ratio,
+            &skywave,
+            location,
+            &lines,
+            &field_strength,
+            &min_es,
+            &max_distances,
+        )?;
     }
 
+    #[cfg(feature = "serde")]
+    if let Some(path) = &args.save {
+        let lines = lines
+            .into_iter()
+            .zip(max_distances)
+            .map(|(line, max_distances)| LineCoverage {
+                line,
+                max_distances_km: max_distances,
+            })
+            .collect();
+        let coverage = Coverage {
+            scenario: args.scenario.scenario(),
+            min_field_strengths: min_es,
+            method,
+            lines,
+        };
+        coverage.save(path)?;
+        println!("Saved the run to `{}`.", path.display());
+    }
+    #[cfg(not(feature = "serde"))]
+    let _ = max_distances;
     Ok(())
 }
 
-fn find_distances_for_input_file(min_e: f64, input_file: &Path, plot: bool) -> Result<()> {
-    let lines = file::read(input_file)?;
-    let results: Vec<_> = lines
-        .par_iter()
-        .map(|line| find_max_distance_for_line(min_e, line))
+/// Finds where the groundwave of every line falls to `ratio` dB over the skywave of its transmitter at `location`,
+/// printing the usable distances at night, the shorter of that and the maximum distance of each of `min_es`, as a table.
+fn find_night_distances(
+    ratio: f64,
+    skywave: &Skywave,
+    (latitude, longitude): (f64, f64),
+    lines: &[Line],
+    field_strength: &FieldStrength,
+    min_es: &[f64],
+    max_distances: &[Vec<Option<f64>>],
+) -> Result<()> {
+    let results: Vec<_> = (0..lines.len())
+        .into_par_iter()
+        .map(|i| {
+            skywave.find_night_distance_for_line_with(
+                ratio,
+                &lines[i],
+                latitude,
+                longitude,
+                |distance| field_strength.at_km(lines, i, distance),
+            )
+        })
         .collect();
 
     // Print all errors, if any exist.
@@ -49,22 +171,148 @@ fn find_distances_for_input_file(min_e: f64, input_file: &Path, plot: bool) -> R
         bail!("{}", errors.join("\n\n"));
     }
 
-    for (i, result) in results.into_iter().enumerate() {
-        let max_distance = result.expect(
-            "Already checked that there are no Errors in results so everything should be Ok.",
+    println!("At night, with the groundwave at least {ratio} dB over the skywave:");
+    let mut header = format!("{:>8} {:>18}", "Angle", "Skywave limit (km)");
+    for min_e in min_es {
+        write!(header, " {:>14}", format!("{min_e} (km)"))?;
+    }
+    println!("{header}");
+    let format_distance =
+        |distance: Option<f64>| distance.map_or_else(|| "-".to_string(), |d| format!("{d:.3}"));
+    for ((line, result), max_distances) in lines.iter().zip(results).zip(max_distances) {
+        let night_distance = result.expect("Already checked that there are no Errors in results.");
+        let mut row = format!(
+            "{:>8} {:>18}",
+            line.angle(),
+            format_distance(night_distance)
         );
+        for &max_distance in max_distances {
+            let usable = match (night_distance, max_distance) {
+                (Some(night), Some(max_distance)) => Some(night.min(max_distance)),
+                (night, max_distance) => night.or(max_distance),
+            };
+            write!(row, " {:>14}", format_distance(usable))?;
+        }
+        println!("{row}");
+    }
+    Ok(())
+}
+
+/// Solves the integral equation along every line over the elevation profile of its angle from the transmitter.
+fn solve_integral_equation(lines: &[Line], args: &CoverageArgs) -> Result<Vec<Solution>> {
+    let (latitude, longitude) = args.input.radials.transmitter_lat_lon.context(
+        "`--transmitter-lat-lon` is needed to take the elevation profiles of the lines from `--dem`.",
+    )?;
+    let dem = Dem::read(&args.dem)?;
+    lines
+        .par_iter()
+        .map(|line| {
+            let step_km = Profile::step_km_for(line);
+            let heights = dem.profile(
+                latitude,
+                longitude,
+                line.angle(),
+                line.max_distance(),
+                step_km,
+            );
+            integral_equation::solve(line, &Profile::new(step_km, heights)?).with_context(|| {
+                format!(
+                    "Could not solve the integral equation for angle {}",
+                    line.angle()
+                )
+            })
+        })
+        .collect()
+}
+
+/// How the field strength along the lines of a coverage run is calculated.
+struct FieldStrength<'a> {
+    /// The model of the segments.
+    model: &'a dyn PropagationModel,
+    /// How the segments are combined.
+    method: MixedPathMethod,
+    /// The solutions of the integral equation for every line, used instead of the segments.
+    solutions: Option<&'a [Solution]>,
+}
+
+impl FieldStrength<'_> {
+    /// The field strength at `distance` km along the line at `index` of `lines`.
+    fn at_km(&self, lines: &[Line], index: usize, distance: f64) -> Result<f64> {
+        match self.solutions {
+            Some(solutions) => solutions[index].field_strength_at_km(distance),
+            None => self
+                .method
+                .field_strength_at_km(self.model, &lines[index], distance),
+        }
+    }
+
+    /// The maximum distances of the line at `index` of `lines` for each of `min_es`.
+    fn max_distances(
+        &self,
+        min_es: &[f64],
+        lines: &[Line],
+        index: usize,
+    ) -> Result<Vec<Option<f64>>> {
+        find_max_distances_for_line_with(min_es, &lines[index], |distance| {
+            self.at_km(lines, index, distance)
+        })
+    }
+}
+
+/// Finds the maximum distance of every line, printing and plotting them.
+fn find_distances_for_lines(
+    min_e: f64,
+    lines: &[Line],
+    field_strength: &FieldStrength,
+    plot: bool,
+) -> Result<Vec<f64>> {
+    let results: Vec<_> = (0..lines.len())
+        .into_par_iter()
+        .map(|i| {
+            field_strength.max_distances(&[min_e], lines, i)?[0].with_context(|| {
+                format!(
+                    "The distance where the minimum field strength value is at is greater than the length of {} km of line {:?}.",
+                    lines[i].max_distance(),
+                    lines[i]
+                )
+            })
+        })
+        .collect();
+
+    // Print all errors, if any exist.
+    let errors = results
+        .iter()
+        .enumerate()
+        .filter_map(|(i, result)| match result {
+            Err(error) => Some(format!("Error for angle {}: {error:?}", lines[i].angle())),
+            Ok(_) => None,
+        })
+        .collect::<Vec<_>>();
+    if !errors.is_empty() {
+        bail!("{}", errors.join("\n\n"));
+    }
+
+    let max_distances = results
+        .into_iter()
+        .map(|result| {
+            result.expect(
+                "Already checked that there are no Errors in results so everything should be Ok.",
+            )
+        })
+        .collect::<Vec<_>>();
+    for (i, &max_distance) in max_distances.iter().enumerate() {
         println!("Angle: {}", lines[i].angle());
         println!("Maximum distance: {max_distance} km");
-        let field_strength_at_max_distance =
-            calc_field_strength_for_line_at_km(&lines[i], max_distance)
-                .expect("It was already calculated, should compute again.");
+        let field_strength_at_max_distance = field_strength
+            .at_km(lines, i, max_distance)
+            .expect("It was already calculated, should compute again.");
         println!("Field strength: {field_strength_at_max_distance} dB(uV)/m");
 
         if plot {
-            let plot_result = plot::line_with_divider(
+            let plot_result = plot::curve_with_dividers(
                 &lines[i],
-                max_distance as f32,
-                field_strength_at_max_distance as f32,
+                |distance| field_strength.at_km(lines, i, distance),
+                &[(max_distance as f32, field_strength_at_max_distance as f32)],
                 0.5,
             );
             if let Err(error) = plot_result {
@@ -75,36 +323,654 @@ fn find_distances_for_input_file(min_e: f64, input_file: &Path, plot: bool) -> R
         }
     }
 
-    Ok(())
+    Ok(max_distances)
 }
 
-fn find_distance_for_hardcoded_line(min_e: f64, plot: bool) -> Result<()> {
-    let segments = [
-        LineSegment::with_length(Terrain::Ground.parameters(), 200.0),
-        // LineSegment::with_length(Terrain::Sea.parameters(), 100.0),
-        // LineSegment::with_length(Terrain::Ground.parameters(), 280.0),
-    ];
-    let line = Line::with_segments(0.0, segments);
+/// Finds the maximum distances of every line for each of `min_es`, printing them as a table.
+fn find_distances_for_lines_at_thresholds(
+    min_es: &[f64],
+    lines: &[Line],
+    field_strength: &FieldStrength,
+    plot: bool,
+) -> Result<Vec<Vec<Option<f64>>>> {
+    let results: Vec<_> = (0..lines.len())
+        .into_par_iter()
+        .map(|i| field_strength.max_distances(min_es, lines, i))
+        .collect();
 
-    let max_distance = find_max_distance_for_line(min_e, &line)?;
-    println!("Maximum distance: {max_distance} km");
-    let field_strength_at_max_distance = calc_field_strength_for_line_at_km(&line, max_distance)
-        .expect("It was already calculated, should compute again.");
-    println!("Field strength: {field_strength_at_max_distance} dB(uV)/m");
+    // Print all errors, if any exist.
+    let errors = results
+        .iter()
+        .enumerate()
+        .filter_map(|(i, result)| match result {
+            Err(error) => Some(format!("Error for angle {}: {error:?}", lines[i].angle())),
+            Ok(_) => None,
+        })
+        .collect::<Vec<_>>();
+    if !errors.is_empty() {
+        bail!("{}", errors.join("\n\n"));
+    }
+    let max_distances = results
+        .into_iter()
+        .map(|result| {
+            result.expect(
+                "Already checked that there are no Errors in results so everything should be Ok.",
+            )
+        })
+        .collect::<Vec<_>>();
 
     if plot {
-        let plot_result = plot::line_with_divider(
-            &line,
-            max_distance as f32,
-            field_strength_at_max_distance as f32,
-            0.5,
+        for (i, (line, max_distances)) in lines.iter().zip(&max_distances).enumerate() {
+            println!("Angle: {}", line.angle());
+            let dividers = max_distances
+                .iter()
+                .zip(min_es)
+                .filter_map(|(&max_distance, &min_e)| Some((max_distance? as f32, min_e as f32)))
+                .collect::<Vec<_>>();
+            let at_km = |distance| field_strength.at_km(lines, i, distance);
+            if let Err(error) = plot::curve_with_dividers(line, at_km, &dividers, 0.5) {
+                println!("Error plotting graph: {error:#}");
+            }
+        }
+    }
+
+    println!("Maximum distance in km for each minimum field strength in dB(uV)/m, n/a where it isn't reached within the line:");
+    print!("{:>8}", "Angle");
+    for min_e in min_es {
+        print!(" {min_e:>12}");
+    }
+    println!();
+    for (line, max_distances) in lines.iter().zip(&max_distances) {
+        print!("{:>8}", line.angle());
+        for max_distance in max_distances {
+            match max_distance {
+                Some(max_distance) => print!(" {max_distance:>12.3}"),
+                None => print!(" {:>12}", "n/a"),
+            }
+        }
+        println!();
+    }
+
+    Ok(max_distances)
+}
+
+#[cfg(feature = "serde")]
+fn replay(args: ReplayArgs) -> Result<()> {
+    let saved = Coverage::load(&args.run)?;
+    let replayed = saved
+        .replay()
+        .context("The saved run could not be calculated again.")?;
+    let differences = saved.compare(&replayed);
+    if differences.is_empty() {
+        println!("Every line gives exactly the saved maximum distances.");
+        return Ok(());
+    }
+    print_differences(&differences);
+    bail!(
+        "{} maximum distances differ from the saved run.",
+        differences.len()
+    )
+}
+
+#[cfg(feature = "serde")]
+fn compare(args: CompareArgs) -> Result<()> {
+    let before = Coverage::load(&args.before)?;
+    let after = Coverage::load(&args.after)?;
+    if before.scenario != after.scenario {
+        let format_scenario = |scenario: &scenario::Scenario| {
+            let overrides = scenario
+                .overrides()
+                .map(|(parameter, value)| format!("{}={value}", parameter.key()))
+                .collect::<Vec<_>>();
+            if overrides.is_empty() {
+                "the defaults".to_string()
+            } else {
+                overrides.join(", ")
+            }
+        };
+        println!(
+            "The scenarios differ: {} before and {} after.",
+            format_scenario(&before.scenario),
+            format_scenario(&after.scenario)
+        );
+    }
+    if before.method != after.method {
+        println!(
+            "The mixed-path methods differ: {} before and {} after.",
+            before.method.name(),
+            after.method.name()
         );
-        if let Err(error) = plot_result {
-            println!("Error making chart: {error:#}");
+    }
+    let differences = before.compare(&after);
+    if differences.is_empty() {
+        println!("The runs have the same maximum distances.");
+    } else {
+        print_differences(&differences);
+    }
+    Ok(())
+}
+
+#[cfg(feature = "serde")]
+fn print_differences(differences: &[Difference]) {
+    let format_distance =
+        |distance: Option<f64>| distance.map_or_else(|| "-".to_string(), |d| format!("{d:.3}"));
+    println!(
+        "{:>8} {:>12} {:>12} {:>12} {:>12}",
+        "Angle", "Min E", "Before (km)", "After (km)", "Change (km)"
+    );
+    for difference in differences {
+        let change = difference
+            .before_km
+            .zip(difference.after_km)
+            .map(|(before, after)| after - before);
+        println!(
+            "{:>8} {:>12} {:>12} {:>12} {:>12}",
+            difference.angle,
+            difference.min_field_strength,
+            format_distance(difference.before_km),
+            format_distance(difference.after_km),
+            format_distance(change)
+        );
+    }
+}
+
+/// The line used when no input is given.
+const DEFAULT_PATH: &str = "land:200";
+
+/// Reads the lines of the input and applies the scenario to them.
+fn load_lines(input: &InputArgs, scenario: &ScenarioArgs) -> Result<Vec<Line>> {
+    let lines = match &input.input {
+        Some(Input::File(path)) => file::read_with(path, &input.read_options())?,
+        Some(Input::Mask(path)) => mask::trace(path, &input.mask_options()?)?,
+        Some(Input::Polygons(path)) => polygons(path, input)?,
+        Some(Input::Osm(path)) => osm_extract(path, input)?,
+        Some(Input::Path(line)) => vec![line.clone()],
+        None => vec![notation::parse(DEFAULT_PATH)?],
+    };
+    let scenario = scenario.scenario();
+    Ok(lines.iter().map(|line| scenario.apply(line)).collect())
+}
+
+/// Casts the lines over the land polygons of `land` from the options of `input`.
+fn polygons(land: &Path, input: &InputArgs) -> Result<Vec<Line>> {
+    let border = input.radials.border.as_deref();
+    let options = input.radial_options()?;
+    with_atlas(vector::trace(land, border, &options)?, &options, input)
+}
+
+/// Casts the lines over the coastlines of the OpenStreetMap extract `path` from the options of `input`.
+fn osm_extract(path: &Path, input: &InputArgs) -> Result<Vec<Line>> {
+    let border = input.radials.border.as_deref();
+    let options = input.radial_options()?;
+    let lines = osm::trace(path, border, input.radials.water, &options)?;
+    with_atlas(lines, &options, input)
+}
+
+/// Gives the land of `lines` the media of the conductivity atlas of `input`, if it has one.
+fn with_atlas(lines: Vec<Line>, options: &RadialOptions, input: &InputArgs) -> Result<Vec<Line>> {
+    let Some(conductivity) = &input.radials.conductivity else {
+        return Ok(lines);
+    };
+    let atlas = Atlas::read(conductivity, input.radials.permittivity.as_deref())?;
+    Ok(atlas.apply(lines, options.latitude, options.longitude))
+}
+
+/// Picks the line with `angle` out of `lines`, or all of them if there's no `angle`.
+fn select_lines(lines: Vec<Line>, angle: Option<f64>) -> Result<Vec<Line>> {
+    let Some(angle) = angle else {
+        return Ok(lines);
+    };
+    let angles = lines
+        .iter()
+        .map(|line| line.angle().to_string())
+        .collect::<Vec<_>>();
+    let line = lines
+        .into_iter()
+        .find(|line| line.angle() == angle)
+        .with_context(|| {
+            format!(
+                "There is no line with angle {angle}. The available angles are: {}.",
+                angles.join(", ")
+            )
+        })?;
+    Ok(vec![line])
+}
+
+fn analyse_sensitivity(args: SensitivityArgs) -> Result<()> {
+    let lines = load_lines(&args.input, &args.scenario)?;
+    let steps = sensitivity::Steps {
+        relative: args.relative_step,
+        boundary_km: args.boundary_step.unwrap_or_else(|| file::px_to_km(1.0)),
+    };
+    let results: Vec<_> = lines
+        .par_iter()
+        .map(|line| sensitivity::analyse_line(args.min_field_strength, line, steps))
+        .collect();
+
+    let mut analyses = Vec::with_capacity(results.len());
+    let mut errors = Vec::new();
+    for (line, result) in lines.iter().zip(results) {
+        match result {
+            Ok(analysis) => analyses.push(analysis),
+            Err(error) => errors.push(format!("Error for angle {}: {error:?}", line.angle())),
         }
+    }
+    if !errors.is_empty() {
+        bail!("{}", errors.join("\n\n"));
+    }
+
+    for analysis in &analyses {
+        sensitivity::print_line(analysis);
+    }
+    sensitivity::print_summary(&sensitivity::summarise(&analyses));
+
+    Ok(())
+}
+
+fn run_monte_carlo(args: MonteCarloArgs) -> Result<()> {
+    let lines = load_lines(&args.input, &args.scenario)?;
+    let distributions = monte_carlo::Distributions {
+        land_epsilon: args.land_epsilon_distribution,
+        land_sigma: args.land_sigma_distribution,
+        sea_epsilon: args.sea_epsilon_distribution,
+        sea_sigma: args.sea_sigma_distribution,
+        boundary_offset_km: args.boundary_offset,
+    };
+    let bands = monte_carlo::run(
+        args.min_field_strength,
+        &lines,
+        &distributions,
+        args.samples,
+        args.seed,
+    )?;
+
+    monte_carlo::print(&bands);
+    if let Some(path) = &args.output_file {
+        monte_carlo::export(&bands, path)?;
+        println!("\nWrote the percentile contours to `{}`.", path.display());
+    }
+
+    Ok(())
+}
+
+fn query_point(args: PointArgs) -> Result<()> {
+    let line = select_line(load_lines(&args.input, &args.scenario)?, args.angle)?;
+    let distance = args.distance;
+    let model = propagation_model(args.curves.as_deref())?;
+    let millington = calc_millington_field_strength_for_line_at_km(&model, &line, distance)?;
+    let transmitter = line.transmitter();
+
+    println!("Angle: {}", line.angle());
+    println!("Distance: {distance} km");
+    println!("Field strength: {} dB(uV)/m", millington.field_strength);
+    println!("  Forward Millington sum: {} dB(uV)/m", millington.forward);
+    println!("  Reverse Millington sum: {} dB(uV)/m", millington.reverse);
+    println!(
+        "Received power: {} dBm",
+        lfmf::received_power_dbm(millington.field_strength, transmitter.frequency_mhz)
+    );
+    println!(
+        "Basic transmission loss: {} dB",
+        lfmf::basic_transmission_loss_db(
+            millington.field_strength,
+            transmitter.frequency_mhz,
+            transmitter.power_w
+        )
+    );
+
+    Ok(())
+}
+
+/// The model of the segments, the tabulated curves at `curves` or else LFMF.
+fn propagation_model(curves: Option<&Path>) -> Result<Box<dyn PropagationModel>> {
+    Ok(match curves {
+        Some(path) => Box::new(TabulatedCurves::read(path)?),
+        None => Box::new(Lfmf),
+    })
+}
+
+/// Picks the line of `angle`, which is only needed if there are several.
+fn select_line(lines: Vec<Line>, angle: Option<f64>) -> Result<Line> {
+    if angle.is_some() || lines.len() == 1 {
+        Ok(select_lines(lines, angle)?.remove(0))
+    } else if lines.len() > 1 {
+        bail!("`--angle` is needed to pick a line from the input file.");
     } else {
-        println!()
+        bail!("The input has no lines.");
+    }
+}
+
+#[derive(Serialize)]
+struct ExplanationJson {
+    angle: f64,
+    distance_km: f64,
+    forward_terms: Vec<TermJson>,
+    reverse_terms: Vec<TermJson>,
+    forward: f64,
+    reverse: f64,
+    field_strength: f64,
+}
+
+#[derive(Serialize)]
+struct TermJson {
+    /// The name of the terrain, `null` for a medium of neither.
+    ground: Option<&'static str>,
+    epsilon: f64,
+    sigma: f64,
+    distance_km: f64,
+    field_strength: f64,
+    subtracted: bool,
+    partial_sum: f64,
+}
+
+impl From<&MillingtonTerm> for TermJson {
+    fn from(term: &MillingtonTerm) -> Self {
+        Self {
+            ground: term.terrain.map(|terrain| terrain.name()),
+            epsilon: term.medium.epsilon,
+            sigma: term.medium.sigma,
+            distance_km: term.distance_km,
+            field_strength: term.field_strength,
+            subtracted: term.subtracted,
+            partial_sum: term.partial_sum,
+        }
+    }
+}
+
+fn explain(args: ExplainArgs) -> Result<()> {
+    let line = select_line(load_lines(&args.input, &args.scenario)?, args.angle)?;
+    let distance = args.distance;
+    let model = propagation_model(args.curves.as_deref())?;
+    let explanation = explain_millington_field_strength_for_line_at_km(&model, &line, distance)?;
+
+    if args.json {
+        let json = ExplanationJson {
+            angle: line.angle(),
+            distance_km: distance,
+            forward_terms: explanation
+                .forward_terms
+                .iter()
+                .map(TermJson::from)
+                .collect(),
+            reverse_terms: explanation
+                .reverse_terms
+                .iter()
+                .map(TermJson::from)
+                .collect(),
+            forward: explanation.forward,
+            reverse: explanation.reverse,
+            field_strength: explanation.field_strength,
+        };
+        println!("{}", serde_json::to_string_pretty(&json)?);
+        return Ok(());
+    }
+
+    println!("Angle: {}", line.angle());
+    println!("Distance: {distance} km");
+    println!(
+        "{:<8} {:>4} {:<22} {:>14} {:>14} {:>14}",
+        "Sum", "Term", "Ground", "Distance (km)", "E (dB(uV)/m)", "Partial sum"
+    );
+    for (sum, terms) in [
+        ("Forward", &explanation.forward_terms),
+        ("Reverse", &explanation.reverse_terms),
+    ] {
+        for (i, term) in terms.iter().enumerate() {
+            let ground = match term.terrain {
+                Some(terrain) => terrain.name().to_string(),
+                None => format!("ε {}, σ {} S/m", term.medium.epsilon, term.medium.sigma),
+            };
+            let sign = if term.subtracted { '-' } else { '+' };
+            let field_strength = format!("{sign}{:.3}", term.field_strength);
+            println!(
+                "{sum:<8} {:>4} {ground:<22} {:>14.3} {field_strength:>14} {:>14.3}",
+                i + 1,
+                term.distance_km,
+                term.partial_sum
+            );
+        }
+    }
+    println!(
+        "Terms: {}",
+        explanation.forward_terms.len() + explanation.reverse_terms.len()
+    );
+    println!("Forward sum: {} dB(uV)/m", explanation.forward);
+    println!("Reverse sum: {} dB(uV)/m", explanation.reverse);
+    println!(
+        "Field strength (average): {} dB(uV)/m",
+        explanation.field_strength
+    );
+
+    Ok(())
+}
+
+fn export_profile(args: ProfileArgs) -> Result<()> {
+    ensure!(0.0 < args.step, "The step must be positive.");
+    let lines = select_lines(load_lines(&args.input, &args.scenario)?, args.angle)?;
+    let model = propagation_model(args.curves.as_deref())?;
+
+    let mut csv = String::from(
+        "degrees,distance km,field strength dB(uV)/m,forward dB(uV)/m,reverse dB(uV)/m\n",
+    );
+    for line in &lines {
+        let samples = (line.max_distance() / args.step).floor() as usize;
+        let rows = (1..=samples)
+            .into_par_iter()
+            .map(|sample| {
+                let distance = args.step * sample as f64;
+                calc_millington_field_strength_for_line_at_km(&model, line, distance)
+                    .map(|millington| (distance, millington))
+            })
+            .collect::<Result<Vec<_>>>()
+            .with_context(|| {
+                format!("Could not calculate the profile of angle {}.", line.angle())
+            })?;
+        for (distance, millington) in rows {
+            writeln!(
+                csv,
+                "{},{distance},{},{},{}",
+                line.angle(),
+                millington.field_strength,
+                millington.forward,
+                millington.reverse
+            )
+            .expect("Writing to a String can't fail.");
+        }
+
+        if args.plot {
+            println!("Angle: {}", line.angle());
+            if let Err(error) = plot::line(&model, line, args.step) {
+                println!("Error plotting graph: {error:#}");
+            }
+        }
+    }
+
+    match &args.output_file {
+        Some(path) => {
+            std::fs::write(path, csv)
+                .with_context(|| format!("Could not write file `{}`", path.display()))?;
+            println!("Wrote the profiles to `{}`.", path.display());
+        }
+        None => print!("{csv}"),
     }
 
     Ok(())
 }
+
+fn sweep(args: SweepArgs) -> Result<()> {
+    ensure!(0 < args.steps, "At least one step is needed.");
+    ensure!(
+        !args.log || (0.0 < args.from && 0.0 < args.to),
+        "A logarithmic sweep needs positive values."
+    );
+    let lines = load_lines(&args.input, &args.scenario)?;
+    let values = (0..args.steps)
+        .map(|step| {
+            let fraction = if args.steps == 1 {
+                0.0
+            } else {
+                step as f64 / (args.steps - 1) as f64
+            };
+            if args.log {
+                (args.from.ln() + (args.to.ln() - args.from.ln()) * fraction).exp()
+            } else {
+                args.from + (args.to - args.from) * fraction
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let max_distances = values
+        .par_iter()
+        .map(|&value| {
+            let scenario = scenario::Scenario::default().with(args.parameter, value);
+            lines
+                .par_iter()
+                .map(|line| {
+                    find_max_distance_for_line(
+                        &Lfmf,
+                        args.min_field_strength,
+                        &scenario.apply(line),
+                    )
+                    .ok()
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    println!(
+        "Maximum distance in km for each value of {} ({}), `-` where it couldn't be found:",
+        args.parameter,
+        args.parameter.unit()
+    );
+    print!("{:>8}", "Angle");
+    for value in &values {
+        print!(" {:>12}", format!("{value:.6}"));
+    }
+    println!();
+    for (i, line) in lines.iter().enumerate() {
+        print!("{:>8}", line.angle());
+        for max_distances in &max_distances {
+            match max_distances[i] {
+                Some(max_distance) => print!(" {max_distance:>12.3}"),
+                None => print!(" {:>12}", "-"),
+            }
+        }
+        println!();
+    }
+
+    Ok(())
+}
+
+fn validate(args: ValidateArgs) -> Result<()> {
+    let (lines, mut problems) = match &args.input.input {
+        Some(Input::File(path)) => {
+            let validation = file::validate(path, &args.input.read_options())?;
+            (validation.lines, validation.problems)
+        }
+        Some(Input::Mask(path)) => (mask::trace(path, &args.input.mask_options()?)?, Vec::new()),
+        Some(Input::Polygons(path)) => (polygons(path, &args.input)?, Vec::new()),
+        Some(Input::Osm(path)) => (osm_extract(path, &args.input)?, Vec::new()),
+        Some(Input::Path(line)) => (vec![line.clone()], Vec::new()),
+        None => (vec![notation::parse(DEFAULT_PATH)?], Vec::new()),
+    };
+
+    // The rows with errors aren't among the lines, so only the scenario or LFMF can fail these.
+    let scenario = args.scenario.scenario();
+    problems.par_extend(lines.par_iter().filter_map(|line| {
+        let line = scenario.apply(line);
+        let error = if line.is_empty() {
+            anyhow::anyhow!("It has no segments.")
+        } else if line.max_distance() > 10000.0 {
+            anyhow::anyhow!(
+                "It's {} km long, longer than the 10000 km LFMF allows.",
+                line.max_distance()
+            )
+        } else {
+            calc_field_strength_for_line_at_km(&Lfmf, &line, line.max_distance()).err()?
+        };
+        Some(file::Problem {
+            severity: Severity::Error,
+            row: None,
+            column: None,
+            message: format!("Angle {}: {error:#}", line.angle()),
+        })
+    }));
+
+    for problem in &problems {
+        println!("{}: {problem}", problem.severity);
+    }
+    let count = |severity| {
+        problems
+            .iter()
+            .filter(|problem| problem.severity == severity)
+            .count()
+    };
+    let (errors, warnings) = (count(Severity::Error), count(Severity::Warning));
+    ensure!(
+        errors == 0,
+        "Found {errors} errors and {warnings} warnings, {} lines are valid.",
+        lines.len()
+    );
+    println!(
+        "All {} lines are valid, with {warnings} warnings.",
+        lines.len()
+    );
+    Ok(())
+}
+
+fn inspect(args: InspectArgs) -> Result<()> {
+    let lines = load_lines(&args.input, &args.scenario)?;
+    for line in &lines {
+        println!(
+            "Angle: {} ({} segments, {} km): {}",
+            line.angle(),
+            line.segments().count(),
+            line.max_distance(),
+            notation::format(line)
+        );
+        println!(
+            "  {:>3} {:<8} {:>12} {:>12} {:>12} {:>8} {:>10}",
+            "#", "Terrain", "Start (km)", "End (km)", "Length (km)", "ε", "σ (S/m)"
+        );
+        let mut start = 0.0;
+        for (i, segment) in line.segments().enumerate() {
+            let end = start + segment.length_km();
+            let medium = segment.medium();
+            println!(
+                "  {:>3} {:<8} {start:>12.3} {end:>12.3} {:>12.3} {:>8} {:>10}",
+                i + 1,
+                segment.terrain().map_or("?", |terrain| terrain.name()),
+                segment.length_km(),
+                medium.epsilon,
+                medium.sigma
+            );
+            start = end;
+        }
+        println!();
+    }
+    Ok(())
+}
+
+fn serve(args: ServeArgs) -> Result<()> {
+    ensure!(
+        0 < args.max_concurrent_requests,
+        "At least one concurrent request is needed."
+    );
+    let limits = server::Limits {
+        max_concurrent_requests: args.max_concurrent_requests,
+        max_samples: args.max_samples,
+        ..server::Limits::default()
+    };
+    let server = server::Server::bind((args.address.as_str(), args.port), args.threads, limits)?;
+    if let Some(address) = server.local_addr() {
+        println!("Listening on http://{address}");
+    }
+    server.run();
+    Ok(())
+}
+
+fn explore(args: TuiArgs) -> Result<()> {
+    let lines = load_lines(&args.input, &args.scenario)?;
+    ensure!(!lines.is_empty(), "There are no lines to explore.");
+    tui::run(lines, args.scenario.scenario(), args.threshold)
+}
@@ -0,0 +1,257 @@
+//! Methods of combining the field strengths of the homogeneous segments of a mixed path,
+//! as alternatives to Millington's for validation and for coastal paths where it misbehaves.
+
+use crate::find_max_distance::{
+    calc_field_strength_for_line_at_km, calc_one_way_field_strength_for_segments,
+};
+use crate::propagation_model::{HomogeneousPath, PropagationModel};
+use crate::terrain::{Line, LineSegment};
+use anyhow::{bail, ensure, Context, Result};
+
+/// The shortest distance in km the equivalent distance is searched from, the minimum distance of LFMF.
+const MIN_DISTANCE_KM: f64 = 0.001;
+/// The longest distance in km the equivalent distance is searched up to, LFMF's maximum.
+const MAX_DISTANCE_KM: f64 = 10_000.0;
+/// How close in km the equivalent distance is found.
+const DISTANCE_TOLERANCE_KM: f64 = 0.0001;
+
+/// How the field strengths of the segments of a line are combined.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum MixedPathMethod {
+    /// The average of the forward sum of Kirke's method and the same sum from the receiver, which is reciprocal.
+    #[default]
+    Millington,
+    /// Every segment continues with the curve of its ground, shifted to the field strength at its start.
+    Kirke,
+    /// The inverse distance field, attenuated by every segment as much as if its ground reached
+    /// from the transmitter for just its length.
+    Suda,
+    /// Every segment continues the curve of its ground from the distance at which that curve has the field strength
+    /// at its start, as by Eckersley and Kirke.
+    EquivalentDistance,
+}
+
+impl MixedPathMethod {
+    pub const ALL: [Self; 4] = [
+        Self::Millington,
+        Self::Kirke,
+        Self::Suda,
+        Self::EquivalentDistance,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Millington => "millington",
+            Self::Kirke => "kirke",
+            Self::Suda => "suda",
+            Self::EquivalentDistance => "equivalent-distance",
+        }
+    }
+
+    /// The field strength in dB(uV)/m at `distance` km along `line`, with the segments evaluated by `model`.
+    pub fn field_strength_at_km(
+        self,
+        model: &(impl PropagationModel + ?Sized),
+        line: &Line,
+        distance: f64,
+    ) -> Result<f64> {
+        match self {
+            Self::Millington => calc_field_strength_for_line_at_km(model, line, distance),
+            Self::Kirke => self.along_segments(line, distance, |segments| {
+                calc_one_way_field_strength_for_segments(model, line, segments.iter())
+            }),
+            Self::Suda => {
+                self.along_segments(line, distance, |segments| suda(model, line, segments))
+            }
+            Self::EquivalentDistance => self.along_segments(line, distance, |segments| {
+                equivalent_distance(model, line, segments)
+            }),
+        }
+    }
+
+    /// Combines the segments of `line` until `distance` km with `combine`.
+    fn along_segments(
+        self,
+        line: &Line,
+        distance: f64,
+        combine: impl FnOnce(&[LineSegment]) -> Result<f64>,
+    ) -> Result<f64> {
+        let segments = line.segments_until(distance).with_context(|| {
+            format!("Could not get segments for distance of {distance} km in line {line:?}")
+        })?;
+        ensure!(
+            !segments.is_empty(),
+            "Got 0 segments for distance of {distance} km in line {line:?}"
+        );
+        combine(&segments).with_context(|| {
+            format!(
+                "Could not calculate the field strength with the {} method for segments {segments:?}.",
+                self.name()
+            )
+        })
+    }
+}
+
+fn suda(
+    model: &(impl PropagationModel + ?Sized),
+    line: &Line,
+    segments: &[LineSegment],
+) -> Result<f64> {
+    let distance = segments.iter().map(LineSegment::length_km).sum();
+    let mut field_strength = inverse_distance_field_strength(line, distance);
+    for segment in segments {
+        let length_km = segment.length_km();
+        let path = line.homogeneous_path(segment.medium());
+        field_strength += model.field_strength(&path, length_km)?
+            - inverse_distance_field_strength(line, length_km);
+    }
+    Ok(field_strength)
+}
+
+/// The field strength in dB(uV)/m of the short monopole of LFMF at `distance` km without any attenuation,
+/// 300 mV/m at 1 km for 1 kW.
+fn inverse_distance_field_strength(line: &Line, distance: f64) -> f64 {
+    20.0 * (300_000.0 * (line.transmitter().power_w / 1000.0).sqrt() / distance).log10()
+}
+
+fn equivalent_distance(
+    model: &(impl PropagationModel + ?Sized),
+    line: &Line,
+    segments: &[LineSegment],
+) -> Result<f64> {
+    let mut field_strength = None;
+    for segment in segments {
+        let path = line.homogeneous_path(segment.medium());
+        let start_km = match field_strength {
+            Some(field_strength) => distance_of(model, &path, field_strength)?,
+            None => 0.0,
+        };
+        field_strength = Some(model.field_strength(&path, start_km + segment.length_km())?);
+    }
+    Ok(field_strength.expect("There is at least one segment."))
+}
+
+/// The distance at which the field strength along `path` falls to `field_strength`,
+/// found by doubling and then bisecting, since it falls with the distance over homogeneous ground.
+fn distance_of(
+    model: &(impl PropagationModel + ?Sized),
+    path: &HomogeneousPath,
+    field_strength: f64,
+) -> Result<f64> {
+    let at_km = |distance_km| model.field_strength(path, distance_km);
+    let mut near = MIN_DISTANCE_KM;
+    if at_km(near)? <= field_strength {
+        return Ok(near);
+    }
+    let mut far = near * 2.0;
+    while at_km(far)? > field_strength {
+        if far >= MAX_DISTANCE_KM {
+            bail!("The field strength doesn't fall to {field_strength} dB(uV)/m within {MAX_DISTANCE_KM} km.");
+        }
+        near = far;
+        far = (far * 2.0).min(MAX_DISTANCE_KM);
+    }
+    while far - near > DISTANCE_TOLERANCE_KM {
+        let middle = (near + far) / 2.0;
+        if at_km(middle)? > field_strength {
+            near = middle;
+        } else {
+            far = middle;
+        }
+    }
+    Ok((near + far) / 2.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::find_max_distance::calc_field_strength_for_line_at_km;
+    use crate::propagation_model::{Lfmf, Mock};
+    use crate::terrain::defaults::Terrain;
+
+    #[test]
+    fn the_methods_agree_where_they_should() {
+        // The inverse distance field losing 0.1 dB per km over land and 0.05 dB per km over sea.
+        let line = Line::builder(0.0)
+            .segment(Terrain::Ground, 100.0)
+            .segment(Terrain::Sea, 200.0)
+            .build()
+            .unwrap();
+        let mock = Mock::new(|path: &HomogeneousPath, distance_km| {
+            let loss_per_km = if path.medium == Terrain::Sea.medium() {
+                0.05
+            } else {
+                0.1
+            };
+            inverse_distance_field_strength(&line, distance_km) - loss_per_km * distance_km
+        });
+        let expected = inverse_distance_field_strength(&line, 200.0) - 15.0;
+        let at_km = |method: MixedPathMethod, distance| {
+            method.field_strength_at_km(&mock, &line, distance).unwrap()
+        };
+        for method in [
+            MixedPathMethod::Millington,
+            MixedPathMethod::Kirke,
+            MixedPathMethod::Suda,
+        ] {
+            let field_strength = at_km(method, 200.0);
+            assert!(
+                (field_strength - expected).abs() < 1e-9,
+                "{method:?} gave {field_strength} dB(uV)/m instead of {expected} dB(uV)/m."
+            );
+        }
+        // The sea continues from 140.69 km, where its curve has the field strength of the land at the coast.
+        let equivalent = at_km(MixedPathMethod::EquivalentDistance, 200.0);
+        assert!(
+            (equivalent - expected - 1.3568).abs() < 1e-3,
+            "{equivalent}"
+        );
+        for method in MixedPathMethod::ALL {
+            assert!((at_km(method, 50.0) - at_km(MixedPathMethod::Millington, 50.0)).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn the_equivalent_distance_is_searched_within_lfmf_limits() {
+        let line = Line::builder(0.0)
+            .segment(Terrain::Ground, 100.0)
+            .segment(Terrain::Sea, 100.0)
+            .build()
+            .unwrap();
+        // The sea never falls to the field strength of the land at the coast.
+        let mock = Mock::new(|path: &HomogeneousPath, distance_km| {
+            assert!(distance_km <= MAX_DISTANCE_KM, "{distance_km}");
+            if path.medium == Terrain::Sea.medium() {
+                100.0
+            } else {
+                50.0
+            }
+        });
+        let field_strength =
+            MixedPathMethod::EquivalentDistance.field_strength_at_km(&mock, &line, 150.0);
+        assert!(field_strength.is_err());
+    }
+
+    #[test]
+    fn only_millington_recovers_over_the_sea() {
+        let line = Line::builder(0.0)
+            .segment(Terrain::Ground, 100.0)
+            .segment(Terrain::Sea, 100.0)
+            .build()
+            .unwrap();
+        let at_km = |method: MixedPathMethod, distance| {
+            method.field_strength_at_km(&Lfmf, &line, distance).unwrap()
+        };
+        let coast = calc_field_strength_for_line_at_km(&Lfmf, &line, 100.0).unwrap();
+        assert!(at_km(MixedPathMethod::Millington, 110.0) > coast);
+        for method in [MixedPathMethod::Kirke, MixedPathMethod::EquivalentDistance] {
+            assert!(at_km(method, 110.0) < coast, "{method:?}");
+            assert!(at_km(method, 150.0) < at_km(method, 110.0), "{method:?}");
+        }
+    }
+}
@@ -0,0 +1,463 @@
+use crate::find_max_distance::find_max_distances_for_line;
+use crate::propagation_model::Lfmf;
+use crate::terrain::defaults::Terrain;
+use crate::terrain::Line;
+use anyhow::{ensure, Context, Result};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rand_distr::{LogNormal, Normal};
+use rayon::prelude::*;
+use std::fmt::Write as _;
+use std::path::Path;
+use std::str::FromStr;
+
+/// The smallest length a segment can be left with after its boundaries are moved, equal to LFMF's minimum distance.
+const MINIMUM_SEGMENT_KM: f64 = 0.001;
+
+/// A probability distribution for an uncertain input, written as `kind:a:b` on the command line.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Distribution {
+    /// `fixed:value` or just `value`.
+    Fixed(f64),
+    /// `normal:mean:standard_deviation`.
+    Normal { mean: f64, standard_deviation: f64 },
+    /// `lognormal:median:factor`, where about 68% of the samples are within the median multiplied or divided by the factor.
+    LogNormal { median: f64, factor: f64 },
+    /// `uniform:min:max`.
+    Uniform { min: f64, max: f64 },
+    /// `loguniform:min:max`, uniform in the logarithm, which suits values only known within an order of magnitude.
+    LogUniform { min: f64, max: f64 },
+}
+
+impl Distribution {
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> f64 {
+        match *self {
+            Self::Fixed(value) => value,
+            Self::Normal {
+                mean,
+                standard_deviation,
+            } => rng.sample(
+                Normal::new(mean, standard_deviation).expect("Validated when it was parsed."),
+            ),
+            Self::LogNormal { median, factor } => rng.sample(
+                LogNormal::new(median.ln(), factor.ln()).expect("Validated when it was parsed."),
+            ),
+            Self::Uniform { min, max } => rng.gen_range(min..=max),
+            Self::LogUniform { min, max } => rng.gen_range(min.ln()..=max.ln()).exp(),
+        }
+    }
+}
+
+impl FromStr for Distribution {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |value: &str| {
+            value
+                .trim()
+                .parse::<f64>()
+                .map_err(|_| format!("`{value}` in `{s}` isn't a number."))
+        };
+        let parts = s.split(':').collect::<Vec<_>>();
+        let distribution = match parts.as_slice() {
+            [value] => Self::Fixed(parse(value)?),
+            ["fixed", value] => Self::Fixed(parse(value)?),
+            ["normal", mean, standard_deviation] => Self::Normal {
+                mean: parse(mean)?,
+                standard_deviation: parse(standard_deviation)?,
+            },
+            ["lognormal", median, factor] => Self::LogNormal {
+                median: parse(median)?,
+                factor: parse(factor)?,
+            },
+            ["uniform", min, max] => Self::Uniform {
+                min: parse(min)?,
+                max: parse(max)?,
+            },
+            ["loguniform", min, max] => Self::LogUniform {
+                min: parse(min)?,
+                max: parse(max)?,
+            },
+            _ => {
+                return Err(format!(
+                    "`{s}` isn't a distribution. Expected one of `value`, `fixed:value`, `normal:mean:sd`, `lognormal:median:factor`, `uniform:min:max` or `loguniform:min:max`."
+                ))
+            }
+        };
+
+        let valid = match distribution {
+            Self::Fixed(value) => value.is_finite(),
+            Self::Normal {
+                mean,
+                standard_deviation,
+            } => mean.is_finite() && standard_deviation.is_finite() && 0.0 <= standard_deviation,
+            Self::LogNormal { median, factor } => {
+                median.is_finite() && factor.is_finite() && 0.0 < median && 1.0 <= factor
+            }
+            Self::Uniform { min, max } => min.is_finite() && max.is_finite() && min <= max,
+            Self::LogUniform { min, max } => 0.0 < min && min <= max && max.is_finite(),
+        };
+        if valid {
+            Ok(distribution)
+        } else {
+            Err(format!("`{s}` has invalid arguments for its distribution."))
+        }
+    }
+}
+
+/// The distributions of the uncertain inputs. The inputs without one are kept at the values the line already has.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Distributions {
+    pub land_epsilon: Option<Distribution>,
+    pub land_sigma: Option<Distribution>,
+    pub sea_epsilon: Option<Distribution>,
+    pub sea_sigma: Option<Distribution>,
+    /// Offset in km added to the position of every boundary between two segments, independently for each boundary.
+    pub boundary_offset_km: Option<Distribution>,
+}
+
+impl Distributions {
+    /// Makes a random variation of `line`.
+    /// The ground constants are drawn before the boundaries so that, for the same `rng` state,
+    /// every line gets the same ground constants.
+    fn sample_line<R: Rng>(&self, line: &Line, rng: &mut R) -> Line {
+        let [land_epsilon, land_sigma, sea_epsilon, sea_sigma] = [
+            self.land_epsilon,
+            self.land_sigma,
+            self.sea_epsilon,
+            self.sea_sigma,
+        ]
+        .map(|distribution| distribution.map(|distribution| distribution.sample(rng)));
+
+        let segments = line.segments().copied().collect::<Vec<_>>();
+        let mut boundaries = segments
+            .iter()
+            .scan(0.0, |distance, segment| {
+                *distance += segment.length_km();
+                Some(*distance)
+            })
+            .collect::<Vec<_>>();
+        let end = boundaries.last().copied().unwrap_or(0.0);
+        if let Some(offset) = self.boundary_offset_km {
+            // The end of the last segment is where the line stops, not a boundary between terrains.
+            let inner = boundaries.len().saturating_sub(1);
+            let mut previous = 0.0;
+            for (index, boundary) in boundaries[..inner].iter_mut().enumerate() {
+                let remaining = (inner - index) as f64;
+                let latest = end - remaining * MINIMUM_SEGMENT_KM;
+                *boundary = (*boundary + offset.sample(rng)).clamp(
+                    previous + MINIMUM_SEGMENT_KM,
+                    latest.max(previous + MINIMUM_SEGMENT_KM),
+                );
+                previous = *boundary;
+            }
+        }
+
+        let mut sampled = line.clone();
+        let mut start = 0.0;
+        for (segment, boundary) in sampled.segments_mut().iter_mut().zip(boundaries) {
+            let (epsilon, sigma) = match segment.terrain() {
+                Some(Terrain::Ground) => (land_epsilon, land_sigma),
+                Some(Terrain::Sea) => (sea_epsilon, sea_sigma),
+                None => (None, None),
+            };
+            let medium = segment.medium_mut();
+            medium.epsilon = epsilon.unwrap_or(medium.epsilon);
+            medium.sigma = sigma.unwrap_or(medium.sigma);
+            segment.set_length_km(boundary - start);
+            start = boundary;
+        }
+        sampled
+    }
+}
+
+/// A percentile of the maximum distances of the samples of a line.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Percentile {
+    pub km: f64,
+    /// Whether it's taken from censored samples, so the maximum distance is only known to be at least `km`.
+    pub at_least: bool,
+}
+
+impl Percentile {
+    fn format(&self) -> String {
+        let sign = if self.at_least { "≥" } else { "" };
+        format!("{sign}{:.3}", self.km)
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct Band {
+    pub angle: f64,
+    /// `None` if any sample failed, since leaving it out would bias the percentiles.
+    pub p10: Option<Percentile>,
+    pub median: Option<Percentile>,
+    pub p90: Option<Percentile>,
+    /// The number of samples still over the minimum at the end of the line, counted at its length.
+    pub censored_samples: usize,
+    /// The number of samples whose field strength couldn't be calculated, e.g. for a negative conductivity.
+    pub failed_samples: usize,
+    pub samples: usize,
+}
+
+/// The maximum distance of a sample.
+enum Sample {
+    Reached(f64),
+    /// Still over the minimum at the end of the line of this length in km.
+    Censored(f64),
+    Failed,
+}
+
+/// Runs `samples` random variations of every line and finds the 10th, 50th and 90th percentiles of their maximum distances.
+///
+/// Sample `i` of every line is drawn from a generator seeded with `seed + i`,
+/// so that the results are reproducible and all lines share the same ground constants in the same sample.
+pub fn run(
+    min_usable_field_strength: f64,
+    lines: &[Line],
+    distributions: &Distributions,
+    samples: usize,
+    seed: u64,
+) -> Result<Vec<Band>> {
+    ensure!(0 < samples, "At least one sample is needed.");
+    let bands = lines
+        .par_iter()
+        .map(|line| {
+            let results: Vec<Sample> = (0..samples)
+                .into_par_iter()
+                .map(|sample| {
+                    let mut rng = StdRng::seed_from_u64(seed.wrapping_add(sample as u64));
+                    let sampled = distributions.sample_line(line, &mut rng);
+                    match find_max_distances_for_line(&Lfmf, &[min_usable_field_strength], &sampled)
+                    {
+                        Ok(distances) => match distances[0] {
+                            Some(distance) => Sample::Reached(distance),
+                            None => Sample::Censored(sampled.max_distance()),
+                        },
+                        Err(_) => Sample::Failed,
+                    }
+                })
+                .collect();
+            let mut distances = Vec::with_capacity(samples);
+            let mut censored_samples = 0;
+            let mut failed_samples = 0;
+            for result in results {
+                match result {
+                    Sample::Reached(distance) => distances.push((distance, false)),
+                    Sample::Censored(length) => {
+                        distances.push((length, true));
+                        censored_samples += 1;
+                    }
+                    Sample::Failed => failed_samples += 1,
+                }
+            }
+            // Censored samples go after reached ones of the same distance, as theirs is further.
+            distances.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+            let percentile =
+                |percent| (failed_samples == 0).then(|| percentile(&distances, percent));
+            Band {
+                angle: line.angle(),
+                p10: percentile(10.0),
+                median: percentile(50.0),
+                p90: percentile(90.0),
+                censored_samples,
+                failed_samples,
+                samples,
+            }
+        })
+        .collect();
+    Ok(bands)
+}
+
+/// Linearly interpolated percentile of already sorted, non-empty distances and whether they're censored.
+fn percentile(sorted: &[(f64, bool)], percent: f64) -> Percentile {
+    let rank = percent / 100.0 * (sorted.len() - 1) as f64;
+    let (lower_km, lower_censored) = sorted[rank.floor() as usize];
+    let (upper_km, upper_censored) = sorted[rank.ceil() as usize];
+    Percentile {
+        km: lower_km + (upper_km - lower_km) * rank.fract(),
+        at_least: lower_censored || upper_censored,
+    }
+}
+
+/// The percentile, or n/a if the band is invalid.
+fn format_percentile(percentile: Option<Percentile>) -> String {
+    percentile.map_or_else(|| "n/a".to_string(), |percentile| percentile.format())
+}
+
+pub fn print(bands: &[Band]) {
+    println!(
+        "{:>8} {:>12} {:>12} {:>12} {:>10} {:>10}",
+        "Angle", "P10 (km)", "Median (km)", "P90 (km)", "Censored", "Failed"
+    );
+    for band in bands {
+        println!(
+            "{:>8} {:>12} {:>12} {:>12} {:>10} {:>10}",
+            band.angle,
+            format_percentile(band.p10),
+            format_percentile(band.median),
+            format_percentile(band.p90),
+            format!("{}/{}", band.censored_samples, band.samples),
+            format!("{}/{}", band.failed_samples, band.samples)
+        );
+    }
+    if bands.iter().any(|band| band.censored_samples > 0) {
+        println!("≥: Some samples were still over the minimum at the end of the line, so the distance is at least that.");
+    }
+    if bands.iter().any(|band| band.failed_samples > 0) {
+        println!("n/a: Some samples failed to be calculated, so the percentiles of the rest would be biased.");
+    }
+}
+
+/// Writes the percentile contours as csv, one row per angle.
+/// A percentile that is only a lower bound is prefixed with `>=` and one of an invalid band is left empty.
+pub fn export(bands: &[Band], path: &Path) -> Result<()> {
+    let mut contents =
+        String::from("degrees,p10 km,median km,p90 km,censored samples,failed samples,samples\n");
+    let format = |percentile: Option<Percentile>| match percentile {
+        Some(Percentile { km, at_least: true }) => format!(">={km}"),
+        Some(Percentile { km, .. }) => km.to_string(),
+        None => String::new(),
+    };
+    for band in bands {
+        writeln!(
+            contents,
+            "{},{},{},{},{},{},{}",
+            band.angle,
+            format(band.p10),
+            format(band.median),
+            format(band.p90),
+            band.censored_samples,
+            band.failed_samples,
+            band.samples
+        )
+        .expect("Writing to a String can't fail.");
+    }
+    std::fs::write(path, contents)
+        .with_context(|| format!("Could not write file `{}`", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::find_max_distance::find_max_distance_for_line;
+    use crate::terrain::LineSegment;
+
+    #[test]
+    fn parse_distributions() {
+        assert_eq!("3".parse(), Ok(Distribution::Fixed(3.0)));
+        assert_eq!(
+            "lognormal:0.003:2".parse(),
+            Ok(Distribution::LogNormal {
+                median: 0.003,
+                factor: 2.0
+            })
+        );
+        assert_eq!(
+            "normal:0:0.8".parse(),
+            Ok(Distribution::Normal {
+                mean: 0.0,
+                standard_deviation: 0.8
+            })
+        );
+        assert!("uniform:5:1".parse::<Distribution>().is_err());
+        assert!("loguniform:0:1".parse::<Distribution>().is_err());
+        assert!("lognormal:0.003:inf".parse::<Distribution>().is_err());
+        assert!("lognormal:inf:2".parse::<Distribution>().is_err());
+        assert!("triangle:0:1:2".parse::<Distribution>().is_err());
+        assert!("normal:a:1".parse::<Distribution>().is_err());
+    }
+
+    #[test]
+    fn percentile_interpolates() {
+        let values = [
+            (1.0, false),
+            (2.0, false),
+            (3.0, false),
+            (4.0, false),
+            (5.0, true),
+        ];
+        assert_eq!(percentile(&values, 50.0).km, 3.0);
+        assert!(!percentile(&values, 50.0).at_least);
+        assert!((percentile(&values, 10.0).km - 1.4).abs() < 1e-12);
+        assert!((percentile(&values, 90.0).km - 4.6).abs() < 1e-12);
+        assert!(percentile(&values, 90.0).at_least);
+    }
+
+    #[test]
+    fn sampled_boundaries_stay_ordered() {
+        let segments = [
+            LineSegment::new(Terrain::Ground.medium(), 10.0),
+            LineSegment::new(Terrain::Sea.medium(), 0.5),
+            LineSegment::new(Terrain::Ground.medium(), 10.0),
+        ];
+        let line = Line::with_segments(0.0, segments);
+        let distributions = Distributions {
+            boundary_offset_km: Some(Distribution::Normal {
+                mean: 0.0,
+                standard_deviation: 5.0,
+            }),
+            ..Default::default()
+        };
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..100 {
+            let sampled = distributions.sample_line(&line, &mut rng);
+            assert!(sampled.segments().all(|segment| segment.length_km() > 0.0));
+            assert!((sampled.max_distance() - line.max_distance()).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn run_without_distributions_is_the_nominal_distance() {
+        let segment = LineSegment::new(Terrain::Ground.medium(), 500.0);
+        let line = Line::with_segments(0.0, [segment]);
+        let bands = run(
+            43.2,
+            std::slice::from_ref(&line),
+            &Distributions::default(),
+            3,
+            0,
+        )
+        .unwrap();
+        let nominal = Percentile {
+            km: find_max_distance_for_line(&Lfmf, 43.2, &line).unwrap(),
+            at_least: false,
+        };
+        assert_eq!(bands[0].median, Some(nominal));
+        assert_eq!(bands[0].p10, Some(nominal));
+        assert_eq!(bands[0].failed_samples, 0);
+    }
+
+    #[test]
+    fn samples_over_the_minimum_at_the_end_are_censored() {
+        let segment = LineSegment::new(Terrain::Ground.medium(), 20.0);
+        let line = Line::with_segments(0.0, [segment]);
+        let bands = run(
+            43.2,
+            std::slice::from_ref(&line),
+            &Distributions::default(),
+            3,
+            0,
+        )
+        .unwrap();
+        let at_the_end = Percentile {
+            km: 20.0,
+            at_least: true,
+        };
+        assert_eq!(bands[0].median, Some(at_the_end));
+        assert_eq!(bands[0].censored_samples, 3);
+    }
+
+    #[test]
+    fn a_failed_sample_invalidates_the_band() {
+        let segment = LineSegment::new(Terrain::Ground.medium(), 500.0);
+        let line = Line::with_segments(0.0, [segment]);
+        let distributions = Distributions {
+            land_sigma: Some(Distribution::Fixed(-1.0)),
+            ..Default::default()
+        };
+        let bands = run(43.2, std::slice::from_ref(&line), &distributions, 3, 0).unwrap();
+        assert_eq!(bands[0].median, None);
+        assert_eq!(bands[0].failed_samples, 3);
+    }
+}
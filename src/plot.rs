@@ -1,10 +1,15 @@
 use crate::find_max_distance::calc_field_strength_for_line_at_km;
+use crate::propagation_model::PropagationModel;
 use crate::terrain::Line;
 use anyhow::{ensure, Result};
 use rayon::prelude::*;
 use textplots::{Chart, Plot, Shape};
 
-fn calc_points(line: &Line, step_km: f64) -> Result<Vec<(f32, f32)>> {
+fn calc_points(
+    line: &Line,
+    field_strength_at_km: impl Fn(f64) -> Result<f64> + Sync,
+    step_km: f64,
+) -> Result<Vec<(f32, f32)>> {
     ensure!(0.0 < step_km, "`step_km` must be positive.");
     let line_max_distance = line.max_distance();
     ensure!(
@@ -17,21 +22,18 @@ fn calc_points(line: &Line, step_km: f64) -> Result<Vec<(f32, f32)>> {
     );
 
     let iterations = (line_max_distance / step_km).floor() as usize;
-    let points: Vec<_> = (1..iterations)
+    (1..iterations)
         .into_par_iter()
         .map(|iteration| {
             let distance = step_km * iteration as f64;
-            let field_strength = calc_field_strength_for_line_at_km(line, distance)
-                .expect("The assurances at the start should be enough.");
-            (distance as f32, field_strength as f32)
+            let field_strength = field_strength_at_km(distance)?;
+            Ok((distance as f32, field_strength as f32))
         })
-        .collect();
-
-    Ok(points)
+        .collect()
 }
 
-pub fn line(line: &Line, step_km: f64) -> Result<()> {
-    let points = calc_points(line, step_km)?;
+pub fn line(model: &(impl PropagationModel + ?Sized), line: &Line, step_km: f64) -> Result<()> {
+    let points = calc_points(line, millington(model, line), step_km)?;
     Chart::new(300, 100, 0.0, line.max_distance() as f32)
         .lineplot(&Shape::Points(&points))
         .nice();
@@ -39,18 +41,50 @@ pub fn line(line: &Line, step_km: f64) -> Result<()> {
 }
 
 pub fn line_with_divider(
+    model: &(impl PropagationModel + ?Sized),
     line: &Line,
     divider_x: f32,
     divider_y_centre: f32,
     step_km: f64,
 ) -> Result<()> {
-    let points = calc_points(line, step_km)?;
-    Chart::new(300, 100, 0.0, line.max_distance() as f32)
-        .lineplot(&Shape::Points(&points))
-        .lineplot(&Shape::Lines(&[
-            (divider_x, divider_y_centre - 20.0),
-            (divider_x, divider_y_centre + 20.0),
-        ]))
-        .nice();
+    curve_with_dividers(
+        line,
+        millington(model, line),
+        &[(divider_x, divider_y_centre)],
+        step_km,
+    )
+}
+
+/// Plots the field strength in dB(uV)/m at a distance in km along `line` from `field_strength_at_km`,
+/// with a vertical divider centred at each of the `(x, y)` points.
+pub fn curve_with_dividers(
+    line: &Line,
+    field_strength_at_km: impl Fn(f64) -> Result<f64> + Sync,
+    dividers: &[(f32, f32)],
+    step_km: f64,
+) -> Result<()> {
+    let points = calc_points(line, field_strength_at_km, step_km)?;
+    let dividers = dividers
+        .iter()
+        .map(|&(x, y_centre)| [(x, y_centre - 20.0), (x, y_centre + 20.0)])
+        .collect::<Vec<_>>();
+    let divider_shapes = dividers
+        .iter()
+        .map(|divider| Shape::Lines(divider))
+        .collect::<Vec<_>>();
+    let points = Shape::Points(&points);
+    let mut chart = Chart::new(300, 100, 0.0, line.max_distance() as f32);
+    let mut chart = chart.lineplot(&points);
+    for shape in &divider_shapes {
+        chart = chart.lineplot(shape);
+    }
+    chart.nice();
     Ok(())
 }
+
+fn millington<'a>(
+    model: &'a (impl PropagationModel + ?Sized),
+    line: &'a Line,
+) -> impl Fn(f64) -> Result<f64> + Sync + 'a {
+    |distance| calc_field_strength_for_line_at_km(model, line, distance)
+}
@@ -0,0 +1,318 @@
+//! Models of the field strength over a path of homogeneous ground, which Millington's method combines over mixed paths.
+//!
+//! [`Lfmf`] is the one of our project. [`TabulatedCurves`] interpolates curves like those of ITU-R P.368,
+//! and [`Mock`] stands in for a model in tests.
+
+use crate::lfmf::{calc_LFMF, LFMF_Parameters};
+use crate::terrain::{Medium, Receiver, Transmitter};
+use anyhow::{bail, ensure, Context, Result};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The stations, the atmosphere and the ground of a path of homogeneous ground, of any length.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct HomogeneousPath {
+    pub transmitter: Transmitter,
+    pub receiver: Receiver,
+    /// Surface refractivity in N-units.
+    pub surface_refractivity: f64,
+    pub medium: Medium,
+}
+
+impl HomogeneousPath {
+    /// The parameters for LFMF of `distance_km` of the path.
+    pub fn lfmf_parameters(&self, distance_km: f64) -> LFMF_Parameters {
+        LFMF_Parameters::assemble(
+            &self.transmitter,
+            &self.receiver,
+            self.surface_refractivity,
+            &self.medium,
+            distance_km,
+        )
+    }
+}
+
+/// The field strength of a path of homogeneous ground.
+pub trait PropagationModel: Sync {
+    /// The field strength in dB(uV)/m at `distance_km` along `path`.
+    fn field_strength(&self, path: &HomogeneousPath, distance_km: f64) -> Result<f64>;
+}
+
+impl<M: PropagationModel + ?Sized> PropagationModel for &M {
+    fn field_strength(&self, path: &HomogeneousPath, distance_km: f64) -> Result<f64> {
+        (**self).field_strength(path, distance_km)
+    }
+}
+
+impl<M: PropagationModel + ?Sized> PropagationModel for Box<M> {
+    fn field_strength(&self, path: &HomogeneousPath, distance_km: f64) -> Result<f64> {
+        (**self).field_strength(path, distance_km)
+    }
+}
+
+/// LFMF over a smooth earth (ITU-R P.368).
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Lfmf;
+
+impl PropagationModel for Lfmf {
+    fn field_strength(&self, path: &HomogeneousPath, distance_km: f64) -> Result<f64> {
+        let parameters = path.lfmf_parameters(distance_km);
+        Ok(calc_LFMF(parameters)
+            .with_context(|| {
+                format!("Failed to calculate field_strength for parameters {parameters:?}.")
+            })?
+            .E_dBuVm)
+    }
+}
+
+/// The field strength in dB(uV)/m of a transmitter of 1 kW by distance in km over a medium at a frequency.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Curve {
+    pub frequency_mhz: f64,
+    pub medium: Medium,
+    /// The points of the curve in increasing distance.
+    pub points: Vec<(f64, f64)>,
+}
+
+/// Curves of the field strength by distance, interpolated linearly in the logarithm of the distance
+/// and scaled to the power of the transmitter. They don't depend on the heights of the stations or the refractivity.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TabulatedCurves {
+    curves: Vec<Curve>,
+}
+
+/// The power in W that the field strengths of the curves are for.
+const CURVE_POWER_W: f64 = 1000.0;
+
+impl TabulatedCurves {
+    pub fn new(curves: Vec<Curve>) -> Result<Self> {
+        for curve in &curves {
+            ensure!(
+                curve.points.len() >= 2,
+                "The curve of {} MHz over ε {} and σ {} S/m has less than 2 points.",
+                curve.frequency_mhz,
+                curve.medium.epsilon,
+                curve.medium.sigma
+            );
+            ensure!(
+                curve.points[0].0 > 0.0
+                    && curve.points.windows(2).all(|pair| pair[0].0 < pair[1].0),
+                "The distances of the curve of {} MHz over ε {} and σ {} S/m must be positive and increasing.",
+                curve.frequency_mhz,
+                curve.medium.epsilon,
+                curve.medium.sigma
+            );
+        }
+        Ok(Self { curves })
+    }
+
+    /// Reads curves from a csv file with the columns `frequency MHz`, `epsilon`, `sigma S/m`, `distance km`
+    /// and `field strength dB(uV)/m` for 1 kW, with a row for every point.
+    pub fn read(path: &Path) -> Result<Self> {
+        let mut reader = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_path(path)
+            .with_context(|| format!("Could not open the curves `{}`", path.display()))?;
+        let mut curves: Vec<Curve> = Vec::new();
+        for (i, record) in reader.records().enumerate() {
+            let record = record
+                .with_context(|| format!("Could not read row {} of `{}`", i + 2, path.display()))?;
+            let values = record
+                .iter()
+                .map(|value| value.parse::<f64>())
+                .collect::<Result<Vec<_>, _>>()
+                .with_context(|| {
+                    format!("Could not parse row {} of `{}`", i + 2, path.display())
+                })?;
+            let [frequency_mhz, epsilon, sigma, distance_km, field_strength] = values[..] else {
+                bail!(
+                    "Row {} of `{}` should have 5 columns but it has {}.",
+                    i + 2,
+                    path.display(),
+                    values.len()
+                );
+            };
+            let medium = Medium { epsilon, sigma };
+            match curves
+                .iter_mut()
+                .find(|curve| curve.frequency_mhz == frequency_mhz && curve.medium == medium)
+            {
+                Some(curve) => curve.points.push((distance_km, field_strength)),
+                None => curves.push(Curve {
+                    frequency_mhz,
+                    medium,
+                    points: vec![(distance_km, field_strength)],
+                }),
+            }
+        }
+        Self::new(curves).with_context(|| format!("Invalid curves in `{}`", path.display()))
+    }
+
+    /// Tabulates `model` at `distances_km` for `paths` with a transmitter of 1 kW,
+    /// making a curve for the frequency and medium of every path.
+    pub fn tabulate(
+        model: &impl PropagationModel,
+        paths: &[HomogeneousPath],
+        distances_km: &[f64],
+    ) -> Result<Self> {
+        let curves = paths
+            .iter()
+            .map(|path| {
+                let mut path = *path;
+                path.transmitter.power_w = CURVE_POWER_W;
+                let points = distances_km
+                    .iter()
+                    .map(|&distance_km| {
+                        Ok((distance_km, model.field_strength(&path, distance_km)?))
+                    })
+                    .collect::<Result<_>>()?;
+                Ok(Curve {
+                    frequency_mhz: path.transmitter.frequency_mhz,
+                    medium: path.medium,
+                    points,
+                })
+            })
+            .collect::<Result<_>>()?;
+        Self::new(curves)
+    }
+}
+
+impl PropagationModel for TabulatedCurves {
+    fn field_strength(&self, path: &HomogeneousPath, distance: f64) -> Result<f64> {
+        let (frequency_mhz, medium) = (path.transmitter.frequency_mhz, path.medium);
+        let curve = self
+            .curves
+            .iter()
+            .find(|curve| curve.frequency_mhz == frequency_mhz && curve.medium == medium)
+            .with_context(|| {
+                format!(
+                    "There is no curve of {frequency_mhz} MHz over ε {} and σ {} S/m.",
+                    medium.epsilon, medium.sigma
+                )
+            })?;
+        let (first, last) = (curve.points[0].0, curve.points[curve.points.len() - 1].0);
+        ensure!(
+            (first..=last).contains(&distance),
+            "The distance of {distance} km is outside of the {first} to {last} km of the curve of {frequency_mhz} MHz over ε {} and σ {} S/m.",
+            medium.epsilon,
+            medium.sigma
+        );
+        let next = curve.points.partition_point(|&(d, _)| d < distance).max(1);
+        let ((d_0, e_0), (d_1, e_1)) = (curve.points[next - 1], curve.points[next]);
+        let fraction = (distance / d_0).ln() / (d_1 / d_0).ln();
+        let field_strength = e_0 + (e_1 - e_0) * fraction;
+        Ok(field_strength + 10.0 * (path.transmitter.power_w / CURVE_POWER_W).log10())
+    }
+}
+
+/// A model of any function of the path and the distance in km that counts how many times it was used.
+pub struct Mock<F> {
+    field_strength: F,
+    calls: AtomicUsize,
+}
+
+impl<F: Fn(&HomogeneousPath, f64) -> f64 + Sync> Mock<F> {
+    pub fn new(field_strength: F) -> Self {
+        Self {
+            field_strength,
+            calls: AtomicUsize::new(0),
+        }
+    }
+
+    /// How many field strengths the model gave so far.
+    pub fn calls(&self) -> usize {
+        self.calls.load(Ordering::Relaxed)
+    }
+}
+
+impl<F: Fn(&HomogeneousPath, f64) -> f64 + Sync> PropagationModel for Mock<F> {
+    fn field_strength(&self, path: &HomogeneousPath, distance_km: f64) -> Result<f64> {
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        Ok((self.field_strength)(path, distance_km))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::find_max_distance::{
+        calc_field_strength_for_line_at_km, find_max_distance_for_line, find_max_distances_for_line,
+    };
+    use crate::terrain::defaults::Terrain;
+    use crate::terrain::Line;
+
+    #[test]
+    fn millington_and_the_search_use_the_model() {
+        // Losing 0.1 dB per km over land and 0.05 dB per km over sea makes Millington exact.
+        let mock = Mock::new(|path: &HomogeneousPath, distance_km| {
+            let loss_per_km = if path.medium == Terrain::Sea.medium() {
+                0.05
+            } else {
+                0.1
+            };
+            100.0 - loss_per_km * distance_km
+        });
+        let line = Line::builder(0.0)
+            .segment(Terrain::Ground, 100.0)
+            .segment(Terrain::Sea, 200.0)
+            .build()
+            .unwrap();
+        let field_strength = calc_field_strength_for_line_at_km(&mock, &line, 200.0);
+        assert!((field_strength.unwrap() - 85.0).abs() < 1e-9);
+        assert_eq!(mock.calls(), 6);
+
+        let max_distances = find_max_distances_for_line(&mock, &[85.0, 80.0], &line);
+        let max_distances = max_distances.unwrap();
+        assert!(
+            (max_distances[0].unwrap() - 200.0).abs() < 0.01,
+            "{max_distances:?}"
+        );
+        assert!(
+            (max_distances[1].unwrap() - 300.0).abs() < 0.01,
+            "{max_distances:?}"
+        );
+    }
+
+    #[test]
+    fn tabulated_lfmf_is_close_to_lfmf() {
+        let paths = [Terrain::Ground.path(), Terrain::Sea.path()];
+        let distances_km = (0..=60)
+            .map(|i| 10f64.powf(i as f64 / 20.0))
+            .collect::<Vec<_>>();
+        let curves = TabulatedCurves::tabulate(&Lfmf, &paths, &distances_km).unwrap();
+
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("curves.csv");
+        let mut csv =
+            String::from("frequency MHz,epsilon,sigma S/m,distance km,field strength dB(uV)/m\n");
+        for curve in &curves.curves {
+            for (distance, field_strength) in &curve.points {
+                csv += &format!(
+                    "{},{},{},{distance},{field_strength}\n",
+                    curve.frequency_mhz, curve.medium.epsilon, curve.medium.sigma
+                );
+            }
+        }
+        std::fs::write(&path, csv).unwrap();
+        let read = TabulatedCurves::read(&path);
+        assert_eq!(read.unwrap(), curves);
+
+        let line = Line::builder(0.0)
+            .segment(Terrain::Ground, 100.0)
+            .segment(Terrain::Sea, 50.0)
+            .segment(Terrain::Ground, 200.0)
+            .build()
+            .unwrap();
+        let expected = find_max_distance_for_line(&Lfmf, 43.2, &line).unwrap();
+        let max_distance = find_max_distance_for_line(&curves, 43.2, &line).unwrap();
+        assert!(
+            (max_distance - expected).abs() < 0.5,
+            "Expected {expected} km, got {max_distance} km."
+        );
+
+        let mut outside = Terrain::Ground.path();
+        assert!(curves.field_strength(&outside, 2000.0).is_err());
+        outside.transmitter.frequency_mhz = 2.0;
+        assert!(curves.field_strength(&outside, 100.0).is_err());
+    }
+}
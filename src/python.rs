@@ -0,0 +1,231 @@
+//! The `wave_propagation` Python extension module, built with the `python` feature.
+//!
+//! The calculations run without the GIL, so other Python threads keep running while rayon works.
+
+#![allow(non_snake_case)]
+
+use crate::find_max_distance::{
+    calc_millington_field_strength_for_line_at_km, find_max_distance_for_line,
+};
+use crate::lfmf::{calc_LFMF, LFMF_Parameters, LFMF_Result};
+use crate::propagation_model::Lfmf;
+use crate::scenario::{Parameter, Scenario};
+use crate::terrain::{notation, Line};
+use numpy::{PyArray1, PyReadonlyArray1};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rayon::prelude::*;
+
+fn to_py_err(error: impl Into<anyhow::Error>) -> PyErr {
+    PyValueError::new_err(format!("{:#}", error.into()))
+}
+
+/// A path from the transmitter, written like `land:100 sea:50 land:200` or `G100,S50,G200`.
+#[pyclass(name = "Line", frozen)]
+struct PyLine(Line);
+
+#[pymethods]
+impl PyLine {
+    #[new]
+    #[pyo3(signature = (path, angle = 0.0))]
+    fn new(path: &str, angle: f64) -> PyResult<Self> {
+        let mut line = notation::parse(path).map_err(to_py_err)?;
+        line.set_angle(angle);
+        Ok(Self(line))
+    }
+
+    /// A copy of the line with a parameter like `tx-power` or `land-sigma` changed.
+    fn with_parameter(&self, parameter: &str, value: f64) -> PyResult<Self> {
+        let parameter: Parameter = parameter.parse().map_err(PyValueError::new_err)?;
+        Ok(Self(
+            Scenario::default().with(parameter, value).apply(&self.0),
+        ))
+    }
+
+    #[getter]
+    fn angle(&self) -> f64 {
+        self.0.angle()
+    }
+
+    #[getter]
+    fn max_distance(&self) -> f64 {
+        self.0.max_distance()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Line('{}', angle={})",
+            notation::format(&self.0),
+            self.0.angle()
+        )
+    }
+}
+
+#[pyclass(name = "LFMFResult", frozen, get_all)]
+struct PyLFMFResult {
+    A_btl__db: f64,
+    E_dBuVm: f64,
+    P_rx__dbm: f64,
+    method: i32,
+}
+
+impl From<LFMF_Result> for PyLFMFResult {
+    fn from(result: LFMF_Result) -> Self {
+        Self {
+            A_btl__db: result.A_btl__db,
+            E_dBuVm: result.E_dBuVm,
+            P_rx__dbm: result.P_rx__dbm,
+            method: result.method,
+        }
+    }
+}
+
+/// Runs LFMF for a single path of homogeneous ground. The defaults are the parameters of our project over land.
+#[pyfunction(name = "calc_LFMF")]
+#[pyo3(signature = (
+    d__km,
+    h_tx__meter = 10.0,
+    h_rx__meter = 10.0,
+    f__mhz = 1.0,
+    P_tx__watt = 10000.0,
+    N_s = 300.0,
+    epsilon = 22.0,
+    sigma = 0.003,
+    pol = 1,
+))]
+#[allow(clippy::too_many_arguments)]
+fn py_calc_LFMF(
+    d__km: f64,
+    h_tx__meter: f64,
+    h_rx__meter: f64,
+    f__mhz: f64,
+    P_tx__watt: f64,
+    N_s: f64,
+    epsilon: f64,
+    sigma: f64,
+    pol: i32,
+) -> PyResult<PyLFMFResult> {
+    let parameters = LFMF_Parameters::builder()
+        .h_tx__meter(h_tx__meter)
+        .h_rx__meter(h_rx__meter)
+        .f__mhz(f__mhz)
+        .P_tx__watt(P_tx__watt)
+        .N_s(N_s)
+        .d__km(d__km)
+        .epsilon(epsilon)
+        .sigma(sigma)
+        .pol(pol)
+        .build()
+        .map_err(to_py_err)?;
+    calc_LFMF(parameters).map(Into::into).map_err(to_py_err)
+}
+
+/// The Millington field strength at a distance in dB(uV)/m, with the forward and reverse sums it averages.
+#[pyfunction]
+fn millington_field_strength(
+    py: Python<'_>,
+    line: &PyLine,
+    distance_km: f64,
+) -> PyResult<(f64, f64, f64)> {
+    let millington = py
+        .detach(|| calc_millington_field_strength_for_line_at_km(&Lfmf, &line.0, distance_km))
+        .map_err(to_py_err)?;
+    Ok((
+        millington.field_strength,
+        millington.forward,
+        millington.reverse,
+    ))
+}
+
+#[pyfunction(name = "find_max_distance_for_line")]
+fn py_find_max_distance_for_line(
+    py: Python<'_>,
+    min_field_strength: f64,
+    line: &PyLine,
+) -> PyResult<f64> {
+    py.detach(|| find_max_distance_for_line(&Lfmf, min_field_strength, &line.0))
+        .map_err(to_py_err)
+}
+
+/// The maximum distances of several lines, searched in parallel.
+#[pyfunction]
+fn find_max_distances<'py>(
+    py: Python<'py>,
+    min_field_strength: f64,
+    lines: Vec<PyRef<'py, PyLine>>,
+) -> PyResult<Bound<'py, PyArray1<f64>>> {
+    let lines = lines.iter().map(|line| line.0.clone()).collect::<Vec<_>>();
+    let distances = py
+        .detach(|| {
+            lines
+                .par_iter()
+                .map(|line| find_max_distance_for_line(&Lfmf, min_field_strength, line))
+                .collect::<anyhow::Result<Vec<_>>>()
+        })
+        .map_err(to_py_err)?;
+    Ok(PyArray1::from_vec(py, distances))
+}
+
+/// The Millington field strength and its forward and reverse sums at every distance, calculated in parallel.
+#[pyfunction]
+#[allow(clippy::type_complexity)]
+fn sample_profile<'py>(
+    py: Python<'py>,
+    line: &PyLine,
+    distances_km: PyReadonlyArray1<'py, f64>,
+) -> PyResult<(
+    Bound<'py, PyArray1<f64>>,
+    Bound<'py, PyArray1<f64>>,
+    Bound<'py, PyArray1<f64>>,
+)> {
+    let distances_km = distances_km.as_array().to_vec();
+    let samples = py
+        .detach(|| {
+            distances_km
+                .par_iter()
+                .map(|&distance| {
+                    calc_millington_field_strength_for_line_at_km(&Lfmf, &line.0, distance)
+                })
+                .collect::<anyhow::Result<Vec<_>>>()
+        })
+        .map_err(to_py_err)?;
+    let field_strengths = samples.iter().map(|sample| sample.field_strength);
+    let forward = samples.iter().map(|sample| sample.forward);
+    let reverse = samples.iter().map(|sample| sample.reverse);
+    Ok((
+        PyArray1::from_iter(py, field_strengths),
+        PyArray1::from_iter(py, forward),
+        PyArray1::from_iter(py, reverse),
+    ))
+}
+
+/// Reads the lines of a csv file or a sheet of a workbook in our format.
+#[pyfunction]
+#[pyo3(signature = (path, sheet = None))]
+fn read_lines(
+    py: Python<'_>,
+    path: std::path::PathBuf,
+    sheet: Option<String>,
+) -> PyResult<Vec<PyLine>> {
+    let options = crate::file::ReadOptions {
+        sheet,
+        ..Default::default()
+    };
+    let lines = py
+        .detach(|| crate::file::read_with(&path, &options))
+        .map_err(to_py_err)?;
+    Ok(lines.into_iter().map(PyLine).collect())
+}
+
+#[pymodule]
+fn wave_propagation(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyLine>()?;
+    module.add_class::<PyLFMFResult>()?;
+    module.add_function(wrap_pyfunction!(py_calc_LFMF, module)?)?;
+    module.add_function(wrap_pyfunction!(millington_field_strength, module)?)?;
+    module.add_function(wrap_pyfunction!(py_find_max_distance_for_line, module)?)?;
+    module.add_function(wrap_pyfunction!(find_max_distances, module)?)?;
+    module.add_function(wrap_pyfunction!(sample_profile, module)?)?;
+    module.add_function(wrap_pyfunction!(read_lines, module)?)?;
+    Ok(())
+}
@@ -0,0 +1,220 @@
+//! Ground constants along radials from a gridded conductivity atlas, like a digitised ITU-R P.832 one,
+//! instead of the single medium of our project for all land.
+
+use super::grid::Grid;
+use super::line_from_stretches;
+use crate::terrain::defaults::Terrain;
+use crate::terrain::{Line, LineSegment, Medium};
+use anyhow::Result;
+use geographiclib_rs::{DirectGeodesic, Geodesic};
+use std::path::Path;
+
+/// How many samples are taken per cell of the atlas along a radial.
+const SAMPLES_PER_CELL: f64 = 4.0;
+
+/// The length of a degree of latitude in km, close enough for the spacing of samples.
+const KM_PER_DEGREE: f64 = 111.32;
+
+/// The shortest step between samples as a fraction of the height of a cell, since the cells narrow to nothing
+/// towards the poles.
+const MIN_STEP_PER_CELL_HEIGHT: f64 = 0.01;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Atlas {
+    /// Conductivity in mS/m, as in the maps of P.832.
+    conductivity: Grid,
+    /// Relative permittivity.
+    permittivity: Option<Grid>,
+}
+
+impl Atlas {
+    pub fn new(conductivity: Grid, permittivity: Option<Grid>) -> Self {
+        Self {
+            conductivity,
+            permittivity,
+        }
+    }
+
+    /// Reads a grid of the conductivity in mS/m and optionally one of the relative permittivity.
+    pub fn read(conductivity: &Path, permittivity: Option<&Path>) -> Result<Self> {
+        Ok(Self::new(
+            Grid::read(conductivity)?,
+            permittivity.map(Grid::read).transpose()?,
+        ))
+    }
+
+    /// The medium of the ground at a point, `None` where the atlas has no conductivity.
+    /// Where it has no permittivity, it's the one of our project's land.
+    pub fn medium_at(&self, longitude: f64, latitude: f64) -> Option<Medium> {
+        let conductivity = self.conductivity.value_at(longitude, latitude)?;
+        let epsilon = self
+            .permittivity
+            .as_ref()
+            .and_then(|permittivity| permittivity.value_at(longitude, latitude))
+            .unwrap_or(Terrain::Ground.medium().epsilon);
+        Some(Medium {
+            epsilon,
+            sigma: conductivity / 1000.0,
+        })
+    }
+
+    /// Gives the land of lines cast from the transmitter at `latitude`, `longitude` the media of the atlas,
+    /// splitting its segments where the cells change and merging adjacent cells with equal values.
+    /// The sea, and land where the atlas has no data, keep their media.
+    pub fn apply(&self, lines: Vec<Line>, latitude: f64, longitude: f64) -> Vec<Line> {
+        let geodesic = Geodesic::wgs84();
+        let (cell_width, cell_height) = self.conductivity.cell_degrees;
+        let step_km = ((cell_width * latitude.to_radians().cos()).min(cell_height)
+            / SAMPLES_PER_CELL)
+            .max(cell_height * MIN_STEP_PER_CELL_HEIGHT)
+            * KM_PER_DEGREE;
+        let medium_at = |angle: f64, distance_km: f64| {
+            let (point_latitude, point_longitude): (f64, f64) =
+                geodesic.direct(latitude, longitude, angle, distance_km * 1000.0);
+            self.medium_at(point_longitude, point_latitude)
+                .unwrap_or(Terrain::Ground.medium())
+        };
+
+        lines
+            .into_iter()
+            .map(|line| {
+                let angle = line.angle();
+                let mut stretches = Vec::new();
+                let mut start_km = 0.0;
+                for segment in line.segments() {
+                    let end_km = start_km + segment.length_km();
+                    if segment.terrain() != Some(Terrain::Ground) {
+                        stretches.push(*segment);
+                        start_km = end_km;
+                        continue;
+                    }
+                    let mut medium = medium_at(angle, start_km);
+                    let mut stretch_start_km = start_km;
+                    let mut distance_km = start_km + step_km;
+                    while distance_km < end_km {
+                        let next_medium = medium_at(angle, distance_km);
+                        if next_medium != medium {
+                            // The boundary is taken halfway between the samples on either side of it.
+                            let boundary_km = distance_km - step_km / 2.0;
+                            stretches.push(LineSegment::with_terrain(
+                                Terrain::Ground,
+                                medium,
+                                boundary_km - stretch_start_km,
+                            ));
+                            stretch_start_km = boundary_km;
+                            medium = next_medium;
+                        }
+                        distance_km += step_km;
+                    }
+                    stretches.push(LineSegment::with_terrain(
+                        Terrain::Ground,
+                        medium,
+                        end_km - stretch_start_km,
+                    ));
+                    start_km = end_km;
+                }
+                line_from_stretches(angle, stretches)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::radials::vector::{trace_polygons, RadialOptions};
+    use crate::terrain::notation;
+    use tiff::encoder::{colortype, TiffEncoder};
+    use tiff::tags::Tag;
+
+    /// Columns of half a degree from 0 to 2 degrees of longitude, with no data in the last one.
+    const CONDUCTIVITY: [&str; 4] = ["1", "1", "10", "-9999"];
+
+    fn write_ascii_grid(path: &Path) {
+        let row = CONDUCTIVITY.join(" ");
+        let rows = [row.as_str(); 4].join("\n");
+        std::fs::write(
+            path,
+            format!("ncols 4\nnrows 4\nxllcorner 0\nyllcorner 0\ncellsize 0.5\nNODATA_value -9999\n{rows}\n"),
+        )
+        .unwrap();
+    }
+
+    fn write_geotiff(path: &Path) {
+        let values = CONDUCTIVITY
+            .map(|value| value.parse::<f32>().unwrap())
+            .repeat(4);
+        let mut encoder = TiffEncoder::new(std::fs::File::create(path).unwrap()).unwrap();
+        let mut image = encoder.new_image::<colortype::Gray32Float>(4, 4).unwrap();
+        let directory = image.encoder();
+        directory
+            .write_tag(Tag::ModelPixelScaleTag, &[0.5, 0.5, 0.0][..])
+            .unwrap();
+        directory
+            .write_tag(Tag::ModelTiepointTag, &[0.0, 0.0, 0.0, 0.0, 2.0, 0.0][..])
+            .unwrap();
+        // A geographic model with its pixels as areas.
+        directory
+            .write_tag(
+                Tag::GeoKeyDirectoryTag,
+                &[1u16, 1, 0, 2, 1024, 0, 1, 2, 1025, 0, 1, 1][..],
+            )
+            .unwrap();
+        directory.write_tag(Tag::GdalNodata, "-9999").unwrap();
+        image.write_data(&values).unwrap();
+    }
+
+    #[test]
+    fn land_takes_the_media_of_the_atlas() {
+        let directory = tempfile::tempdir().unwrap();
+        let ascii_path = directory.path().join("conductivity.asc");
+        let geotiff_path = directory.path().join("conductivity.tif");
+        write_ascii_grid(&ascii_path);
+        write_geotiff(&geotiff_path);
+        let from_ascii = Atlas::read(&ascii_path, None).unwrap();
+        let from_geotiff = Atlas::read(&geotiff_path, None).unwrap();
+        std::fs::write(
+            &ascii_path,
+            "ncols 1\nnrows 1\nxllcorner 0\nyllcorner 0\ncellsize 0\n1\n",
+        )
+        .unwrap();
+        let without_cells = Atlas::read(&ascii_path, None);
+        assert_eq!(from_geotiff, from_ascii);
+        assert!(without_cells.is_err());
+
+        let land = [vec![(0.0, 0.0), (1.8, 0.0), (1.8, 2.0), (0.0, 2.0)]];
+        let options = RadialOptions {
+            latitude: 1.0,
+            longitude: 0.1,
+            step_degrees: 90.0,
+            length_km: Some(250.0),
+        };
+        let lines = trace_polygons(&land, None, &options).unwrap();
+        let lines = from_ascii.apply(lines, options.latitude, options.longitude);
+        let east = lines[1].segments().collect::<Vec<_>>();
+        let sigmas = east
+            .iter()
+            .map(|segment| segment.medium().sigma)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            sigmas,
+            [
+                0.001,
+                0.01,
+                Terrain::Ground.medium().sigma,
+                Terrain::Sea.medium().sigma
+            ]
+        );
+        assert_eq!(east[0].terrain(), Some(Terrain::Ground));
+        // The boundaries are within half a step of samples of the edges of the cells.
+        let half_step_km = 0.5 * KM_PER_DEGREE / SAMPLES_PER_CELL / 2.0;
+        assert!(
+            (east[0].length_km() - 0.9 * KM_PER_DEGREE).abs() <= half_step_km,
+            "{east:?}"
+        );
+        assert!((lines[1].max_distance() - 250.0).abs() < 1e-9);
+
+        let at_the_pole = from_ascii.apply(vec![notation::parse("G100").unwrap()], 90.0, 0.0);
+        assert!((at_the_pole[0].max_distance() - 100.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,115 @@
+//! Elevation profiles along radials from local digital elevation model tiles, like SRTM `.hgt` or GeoTIFF ones.
+
+use super::grid::Grid;
+use anyhow::{ensure, Context, Result};
+use geographiclib_rs::{DirectGeodesic, Geodesic};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dem {
+    tiles: Vec<Grid>,
+}
+
+impl Dem {
+    pub fn new(tiles: Vec<Grid>) -> Self {
+        Self { tiles }
+    }
+
+    /// Reads every tile of `paths`, where a directory stands for all the `.hgt`, `.tif` and `.tiff` files in it.
+    pub fn read(paths: &[PathBuf]) -> Result<Self> {
+        let mut tiles = Vec::new();
+        for path in paths {
+            if path.is_dir() {
+                for tile in tiles_in(path)? {
+                    tiles.push(Grid::read(&tile)?);
+                }
+            } else {
+                tiles.push(Grid::read(path)?);
+            }
+        }
+        ensure!(!tiles.is_empty(), "No elevation tiles were found.");
+        Ok(Self::new(tiles))
+    }
+
+    /// The height in metres at a point from the first tile that has it, `None` if none does.
+    pub fn height_at(&self, longitude: f64, latitude: f64) -> Option<f64> {
+        self.tiles
+            .iter()
+            .find_map(|tile| tile.interpolate_at(longitude, latitude))
+    }
+
+    /// The heights in metres every `step_km` along the geodesic at bearing `angle` from `latitude`, `longitude`,
+    /// from the start up to `length_km`. Points without data are taken to be at sea level.
+    pub fn profile(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        angle: f64,
+        length_km: f64,
+        step_km: f64,
+    ) -> Vec<f64> {
+        let geodesic = Geodesic::wgs84();
+        let samples = (length_km / step_km).round() as usize;
+        (0..=samples)
+            .map(|sample| {
+                let (point_latitude, point_longitude): (f64, f64) =
+                    geodesic.direct(latitude, longitude, angle, sample as f64 * step_km * 1000.0);
+                self.height_at(point_longitude, point_latitude)
+                    .unwrap_or(0.0)
+            })
+            .collect()
+    }
+}
+
+fn tiles_in(directory: &Path) -> Result<Vec<PathBuf>> {
+    let mut tiles = Vec::new();
+    for entry in std::fs::read_dir(directory)
+        .with_context(|| format!("Could not read the directory `{}`", directory.display()))?
+    {
+        let path = entry?.path();
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_lowercase);
+        if matches!(extension.as_deref(), Some("hgt" | "tif" | "tiff")) {
+            tiles.push(path);
+        }
+    }
+    tiles.sort();
+    Ok(tiles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heights_are_interpolated_between_the_points_of_hgt_tiles() {
+        let directory = tempfile::tempdir().unwrap();
+        // Points every half a degree from the north-west corner at 1° N 0° E, with a void in the south-east.
+        let heights: [i16; 9] = [0, 100, 200, 0, 100, 200, 0, 100, -32768];
+        let bytes = heights
+            .iter()
+            .flat_map(|height| height.to_be_bytes())
+            .collect::<Vec<_>>();
+        std::fs::write(directory.path().join("N00E000.hgt"), bytes).unwrap();
+        let dem = Dem::read(&[directory.path().to_path_buf()]).unwrap();
+
+        let height_at = |longitude, latitude| dem.height_at(longitude, latitude).unwrap();
+        assert_eq!(height_at(0.0, 1.0), 0.0);
+        assert!((height_at(0.25, 0.75) - 50.0).abs() < 1e-9);
+        assert!((height_at(0.75, 0.6) - 150.0).abs() < 1e-9);
+        // Next to the void, it's the height of the nearest point.
+        assert_eq!(height_at(0.6, 0.1), 100.0);
+        assert_eq!(dem.height_at(0.9, 0.1), None);
+        assert_eq!(dem.height_at(1.5, 0.5), None);
+
+        let profile = dem.profile(0.5, 0.0, 90.0, 100.0, 25.0);
+        assert_eq!(profile.len(), 5);
+        assert_eq!(profile[0], 0.0);
+        assert!(
+            profile.windows(2).all(|pair| pair[0] < pair[1]),
+            "{profile:?}"
+        );
+    }
+}
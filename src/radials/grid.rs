@@ -0,0 +1,283 @@
+//! Georeferenced rasters of values in longitude and latitude (WGS 84), read from a GeoTIFF, an ESRI ASCII grid
+//! or an SRTM `.hgt` tile.
+
+use anyhow::{bail, ensure, Context, Result};
+use std::fs::File;
+use std::path::Path;
+use tiff::decoder::{Decoder, DecodingResult};
+use tiff::tags::Tag;
+
+/// A raster whose cells are `cell_degrees` wide and high, in rows from the north-west corner.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grid {
+    /// Longitude of the west edge in degrees.
+    pub west: f64,
+    /// Latitude of the north edge in degrees.
+    pub north: f64,
+    /// Width and height of a cell in degrees.
+    pub cell_degrees: (f64, f64),
+    pub width: usize,
+    pub height: usize,
+    /// The values row by row, `None` where there's no data.
+    pub values: Vec<Option<f64>>,
+}
+
+impl Grid {
+    /// Reads a `.tif` or `.tiff` GeoTIFF, an `.asc` ESRI ASCII grid or an SRTM `.hgt` tile.
+    pub fn read(path: &Path) -> Result<Self> {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_lowercase);
+        match extension.as_deref() {
+            Some("tif" | "tiff") => read_geotiff(path),
+            Some("asc") => read_ascii_grid(path),
+            Some("hgt") => read_hgt(path),
+            _ => bail!(
+                "Can't tell the format of `{}` from its extension, expected `.tif`, `.tiff`, `.asc` or `.hgt`.",
+                path.display()
+            ),
+        }
+        .with_context(|| format!("Could not read the grid `{}`", path.display()))
+    }
+
+    /// The value of the cell at a point, `None` if there's no data or it's outside of the grid.
+    pub fn value_at(&self, longitude: f64, latitude: f64) -> Option<f64> {
+        let column = (longitude - self.west) / self.cell_degrees.0;
+        let row = (self.north - latitude) / self.cell_degrees.1;
+        if column < 0.0 || row < 0.0 || column >= self.width as f64 || row >= self.height as f64 {
+            return None;
+        }
+        self.values[row as usize * self.width + column as usize]
+    }
+
+    /// The value at a point interpolated bilinearly between the centres of the cells around it,
+    /// or the value of its cell if any of them has no data.
+    pub fn interpolate_at(&self, longitude: f64, latitude: f64) -> Option<f64> {
+        let value = self.value_at(longitude, latitude)?;
+        let column = (longitude - self.west) / self.cell_degrees.0 - 0.5;
+        let row = (self.north - latitude) / self.cell_degrees.1 - 0.5;
+        let (left, top) = (column.floor(), row.floor());
+        if left < 0.0
+            || top < 0.0
+            || left + 1.0 >= self.width as f64
+            || top + 1.0 >= self.height as f64
+        {
+            return Some(value);
+        }
+        let at = |row: f64, column: f64| self.values[row as usize * self.width + column as usize];
+        let (Some(top_left), Some(top_right), Some(bottom_left), Some(bottom_right)) = (
+            at(top, left),
+            at(top, left + 1.0),
+            at(top + 1.0, left),
+            at(top + 1.0, left + 1.0),
+        ) else {
+            return Some(value);
+        };
+        let (x, y) = (column - left, row - top);
+        let top = top_left + (top_right - top_left) * x;
+        let bottom = bottom_left + (bottom_right - bottom_left) * x;
+        Some(top + (bottom - top) * y)
+    }
+}
+
+fn read_geotiff(path: &Path) -> Result<Grid> {
+    let mut decoder = Decoder::new(File::open(path)?)?;
+    let (width, height) = decoder.dimensions()?;
+
+    // The keys of the GeoKeyDirectory come in fours after a header of four: id, location, count and value.
+    const MODEL_TYPE: u16 = 1024;
+    const RASTER_TYPE: u16 = 1025;
+    const GEOGRAPHIC: u16 = 2;
+    const PIXEL_IS_POINT: u16 = 2;
+    let keys = decoder
+        .find_tag_unsigned_vec::<u16>(Tag::GeoKeyDirectoryTag)?
+        .unwrap_or_default();
+    let key = |id| {
+        keys.chunks_exact(4)
+            .skip(1)
+            .find(|entry| entry[0] == id && entry[1] == 0)
+            .map(|entry| entry[3])
+    };
+    if let Some(model_type) = key(MODEL_TYPE) {
+        ensure!(
+            model_type == GEOGRAPHIC,
+            "Only GeoTIFFs in longitude and latitude are supported, but its model type is {model_type}."
+        );
+    }
+    let scale = decoder
+        .get_tag_f64_vec(Tag::ModelPixelScaleTag)
+        .context("It has no pixel scale, so it isn't a GeoTIFF.")?;
+    let tiepoint = decoder
+        .get_tag_f64_vec(Tag::ModelTiepointTag)
+        .context("It has no tiepoint, so it isn't a GeoTIFF.")?;
+    ensure!(
+        scale.len() >= 2 && tiepoint.len() >= 6,
+        "Its pixel scale or tiepoint is too short."
+    );
+    let cell_degrees = (scale[0], scale[1]);
+    ensure!(
+        valid_cell_size(cell_degrees.0) && valid_cell_size(cell_degrees.1),
+        "Its pixel scale of {} by {} degrees isn't positive.",
+        cell_degrees.0,
+        cell_degrees.1
+    );
+    // The tiepoint ties the raster point (i, j) to the model point (x, y).
+    let (mut west, mut north) = (
+        tiepoint[3] - tiepoint[0] * cell_degrees.0,
+        tiepoint[4] + tiepoint[1] * cell_degrees.1,
+    );
+    if key(RASTER_TYPE) == Some(PIXEL_IS_POINT) {
+        west -= cell_degrees.0 / 2.0;
+        north += cell_degrees.1 / 2.0;
+    }
+    let nodata = match decoder.find_tag(Tag::GdalNodata)? {
+        Some(value) => Some(
+            value
+                .into_string()?
+                .trim_end_matches('\0')
+                .trim()
+                .parse::<f64>()?,
+        ),
+        None => None,
+    };
+
+    let values: Vec<f64> = match decoder.read_image()? {
+        DecodingResult::U8(values) => values.into_iter().map(f64::from).collect(),
+        DecodingResult::U16(values) => values.into_iter().map(f64::from).collect(),
+        DecodingResult::U32(values) => values.into_iter().map(f64::from).collect(),
+        DecodingResult::I8(values) => values.into_iter().map(f64::from).collect(),
+        DecodingResult::I16(values) => values.into_iter().map(f64::from).collect(),
+        DecodingResult::I32(values) => values.into_iter().map(f64::from).collect(),
+        DecodingResult::F32(values) => values.into_iter().map(f64::from).collect(),
+        DecodingResult::F64(values) => values,
+        _ => bail!("Its sample format isn't supported."),
+    };
+    let (width, height) = (width as usize, height as usize);
+    ensure!(
+        values.len() == width * height,
+        "Only GeoTIFFs with a single band are supported."
+    );
+    Ok(Grid {
+        west,
+        north,
+        cell_degrees,
+        width,
+        height,
+        values: with_nodata(values, nodata),
+    })
+}
+
+/// Whether a cell size in degrees can be walked, unlike 0 or NaN.
+fn valid_cell_size(cell_size: f64) -> bool {
+    cell_size.is_finite() && cell_size > 0.0
+}
+
+fn read_ascii_grid(path: &Path) -> Result<Grid> {
+    let text = std::fs::read_to_string(path)?;
+    let mut tokens = text.split_whitespace().peekable();
+    let mut header = Vec::new();
+    while let Some(key) =
+        tokens.next_if(|token| token.starts_with(|c: char| c.is_ascii_alphabetic()))
+    {
+        let value = tokens
+            .next()
+            .with_context(|| format!("`{key}` has no value."))?
+            .parse::<f64>()
+            .with_context(|| format!("The value of `{key}` isn't a number."))?;
+        header.push((key.to_lowercase(), value));
+    }
+    let field = |key: &str| {
+        header
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|&(_, value)| value)
+    };
+    let required = |key: &str| field(key).with_context(|| format!("The header has no `{key}`."));
+    let (width, height) = (required("ncols")? as usize, required("nrows")? as usize);
+    let cell_size = required("cellsize")?;
+    ensure!(
+        valid_cell_size(cell_size),
+        "Its `cellsize` of {cell_size} isn't positive."
+    );
+    let (west, south) = match (field("xllcorner"), field("yllcorner")) {
+        (Some(west), Some(south)) => (west, south),
+        _ => (
+            required("xllcenter")? - cell_size / 2.0,
+            required("yllcenter")? - cell_size / 2.0,
+        ),
+    };
+    let values = tokens
+        .map(|token| {
+            token
+                .parse::<f64>()
+                .with_context(|| format!("`{token}` isn't a number."))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    ensure!(
+        values.len() == width * height,
+        "It should have {width}x{height} values but it has {}.",
+        values.len()
+    );
+    Ok(Grid {
+        west,
+        north: south + height as f64 * cell_size,
+        cell_degrees: (cell_size, cell_size),
+        width,
+        height,
+        values: with_nodata(values, field("nodata_value")),
+    })
+}
+
+/// Reads an SRTM tile, named after its south-west corner like `N39E022.hgt`, of big-endian 16-bit heights in metres
+/// at the points of a square grid of 1 or 3 arc-seconds.
+fn read_hgt(path: &Path) -> Result<Grid> {
+    const VOID: f64 = -32768.0;
+    let name = path
+        .file_stem()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default()
+        .to_uppercase();
+    let corner = (|| {
+        let (latitude, longitude) = name.get(..7)?.split_at(3);
+        let sign = |hemisphere, negative| if hemisphere == negative { -1.0 } else { 1.0 };
+        let south = sign(latitude.chars().next()?, 'S') * latitude.get(1..)?.parse::<f64>().ok()?;
+        let west =
+            sign(longitude.chars().next()?, 'W') * longitude.get(1..)?.parse::<f64>().ok()?;
+        let valid = matches!(latitude.chars().next()?, 'N' | 'S')
+            && matches!(longitude.chars().next()?, 'E' | 'W');
+        valid.then_some((south, west))
+    })();
+    let Some((south, west)) = corner else {
+        bail!("Its name should be the south-west corner of the tile, like `N39E022.hgt`.");
+    };
+
+    let bytes = std::fs::read(path)?;
+    let size = ((bytes.len() / 2) as f64).sqrt() as usize;
+    ensure!(
+        size > 1 && size * size * 2 == bytes.len(),
+        "It should be a square of 16-bit heights but it has {} bytes.",
+        bytes.len()
+    );
+    let values = bytes
+        .chunks_exact(2)
+        .map(|height| f64::from(i16::from_be_bytes([height[0], height[1]])))
+        .collect();
+    // The first and last rows and columns are on the edges of the tile, so the cells reach half a cell beyond them.
+    let cell_degrees = 1.0 / (size - 1) as f64;
+    Ok(Grid {
+        west: west - cell_degrees / 2.0,
+        north: south + 1.0 + cell_degrees / 2.0,
+        cell_degrees: (cell_degrees, cell_degrees),
+        width: size,
+        height: size,
+        values: with_nodata(values, Some(VOID)),
+    })
+}
+
+fn with_nodata(values: Vec<f64>, nodata: Option<f64>) -> Vec<Option<f64>> {
+    values
+        .into_iter()
+        .map(|value| (Some(value) != nodata && !value.is_nan()).then_some(value))
+        .collect()
+}
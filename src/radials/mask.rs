@@ -0,0 +1,328 @@
+//! Radials traced over a raster mask of land, sea and the outside of our area, like a PNG exported from our map.
+
+use super::{bearings, line_from_stretches};
+use crate::terrain::defaults::Terrain;
+use crate::terrain::{Line, LineSegment};
+use anyhow::{bail, ensure, Context, Result};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+/// How many samples are taken per pixel along a radial.
+const SAMPLES_PER_PX: f64 = 4.0;
+
+/// The colours of the mask, as RGB.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Legend {
+    pub land: [u8; 3],
+    pub sea: [u8; 3],
+    /// Beyond the border or the area of interest, where the lines end. Transparent pixels are outside too.
+    pub outside: [u8; 3],
+}
+
+impl Default for Legend {
+    fn default() -> Self {
+        Self {
+            land: [0, 0, 0],
+            sea: [255, 255, 255],
+            outside: [255, 0, 0],
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MaskOptions {
+    pub legend: Legend,
+    /// The position of the transmitter in pixels from the top left corner, with `(0, 0)` the first pixel.
+    pub transmitter_px: (f64, f64),
+    /// The scale of the mask, or `None` to read it from its world file, like `map.pgw` for `map.png`.
+    pub km_per_px: Option<f64>,
+    /// The step of the bearings of the radials in degrees.
+    pub step_degrees: f64,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Class {
+    Land,
+    Sea,
+    Outside,
+}
+
+struct Mask {
+    width: usize,
+    height: usize,
+    classes: Vec<Class>,
+}
+
+impl Mask {
+    /// The class of the pixel at a position in pixels, outside if it's beyond the image.
+    fn class_at(&self, x: f64, y: f64) -> Class {
+        if x < 0.0 || y < 0.0 || x >= self.width as f64 || y >= self.height as f64 {
+            return Class::Outside;
+        }
+        self.classes[y as usize * self.width + x as usize]
+    }
+}
+
+/// Traces a line along every bearing of `options` from the transmitter until the mask is outside or ends.
+pub fn trace(path: &Path, options: &MaskOptions) -> Result<Vec<Line>> {
+    ensure!(
+        options.step_degrees > 0.0,
+        "The step of the bearings must be positive but it was {}.",
+        options.step_degrees
+    );
+    let mask = read_mask(path, &options.legend)?;
+    let km_per_px = match options.km_per_px {
+        Some(km_per_px) => {
+            ensure!(
+                km_per_px.is_finite() && km_per_px > 0.0,
+                "The scale must be a positive number of km per pixel but it was {km_per_px}."
+            );
+            (km_per_px, km_per_px)
+        }
+        None => read_world_file(path)?,
+    };
+    let (x, y) = options.transmitter_px;
+    // The transmitter is at the centre of its pixel.
+    let transmitter = (x + 0.5, y + 0.5);
+    ensure!(
+        mask.class_at(transmitter.0, transmitter.1) != Class::Outside,
+        "The transmitter at pixel ({x}, {y}) is outside of the mask of `{}`.",
+        path.display()
+    );
+    Ok(bearings(options.step_degrees)
+        .map(|angle| trace_radial(&mask, transmitter, km_per_px, angle))
+        .collect())
+}
+
+fn trace_radial(mask: &Mask, transmitter: (f64, f64), km_per_px: (f64, f64), angle: f64) -> Line {
+    let (sin, cos) = angle.to_radians().sin_cos();
+    // Pixels per km towards the bearing, with y pointing south.
+    let direction = (sin / km_per_px.0, -cos / km_per_px.1);
+    let step_km = km_per_px.0.min(km_per_px.1) / SAMPLES_PER_PX;
+
+    let mut stretches = Vec::new();
+    let mut class = mask.class_at(transmitter.0, transmitter.1);
+    let mut start_km = 0.0;
+    for sample in 1.. {
+        let distance_km = sample as f64 * step_km;
+        let next_class = mask.class_at(
+            transmitter.0 + distance_km * direction.0,
+            transmitter.1 + distance_km * direction.1,
+        );
+        if next_class == class {
+            continue;
+        }
+        // The boundary is taken halfway between the samples on either side of it.
+        let boundary_km = distance_km - step_km / 2.0;
+        let terrain = match class {
+            Class::Land => Terrain::Ground,
+            Class::Sea => Terrain::Sea,
+            Class::Outside => unreachable!("The radial ends when it's outside."),
+        };
+        stretches.push(LineSegment::new(terrain.medium(), boundary_km - start_km));
+        if next_class == Class::Outside {
+            break;
+        }
+        class = next_class;
+        start_km = boundary_km;
+    }
+    line_from_stretches(angle, stretches)
+}
+
+fn read_mask(path: &Path, legend: &Legend) -> Result<Mask> {
+    let file = File::open(path).with_context(|| format!("Could not open `{}`", path.display()))?;
+    let mut decoder = png::Decoder::new(file);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder
+        .read_info()
+        .with_context(|| format!("Could not read the PNG `{}`", path.display()))?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader
+        .next_frame(&mut buffer)
+        .with_context(|| format!("Could not decode the PNG `{}`", path.display()))?;
+
+    let channels = info.color_type.samples();
+    let classes = buffer[..info.buffer_size()]
+        .chunks_exact(channels)
+        .map(|pixel| {
+            let (rgb, alpha) = match *pixel {
+                [gray] => ([gray; 3], 255),
+                [gray, alpha] => ([gray; 3], alpha),
+                [r, g, b] => ([r, g, b], 255),
+                [r, g, b, alpha] => ([r, g, b], alpha),
+                _ => unreachable!("PNGs have 1 to 4 channels."),
+            };
+            if alpha == 0 {
+                Class::Outside
+            } else {
+                classify(rgb, legend)
+            }
+        })
+        .collect();
+    Ok(Mask {
+        width: info.width as usize,
+        height: info.height as usize,
+        classes,
+    })
+}
+
+/// The class of the colour of the legend nearest to `rgb`, so that antialiased edges still count.
+fn classify(rgb: [u8; 3], legend: &Legend) -> Class {
+    let distance = |colour: [u8; 3]| {
+        rgb.iter()
+            .zip(colour)
+            .map(|(&a, b)| (i32::from(a) - i32::from(b)).pow(2))
+            .sum::<i32>()
+    };
+    [
+        (Class::Land, legend.land),
+        (Class::Sea, legend.sea),
+        (Class::Outside, legend.outside),
+    ]
+    .into_iter()
+    .min_by_key(|&(_, colour)| distance(colour))
+    .map(|(class, _)| class)
+    .expect("There are three classes.")
+}
+
+/// The km per pixel along x and y from the world file of an image, whose map units must be metres.
+fn read_world_file(path: &Path) -> Result<(f64, f64)> {
+    let candidates = world_file_candidates(path);
+    let Some(world_file) = candidates.iter().find(|candidate| candidate.is_file()) else {
+        bail!(
+            "The scale of `{}` wasn't given and it has no world file, like `{}`.",
+            path.display(),
+            candidates[0].display()
+        );
+    };
+    let text = std::fs::read_to_string(world_file)
+        .with_context(|| format!("Could not read `{}`", world_file.display()))?;
+    let values = text
+        .split_whitespace()
+        .map(|value| value.parse::<f64>())
+        .collect::<Result<Vec<_>, _>>()
+        .with_context(|| format!("Could not parse the world file `{}`", world_file.display()))?;
+    let [x_size, y_rotation, x_rotation, y_size, _, _] = values[..] else {
+        bail!(
+            "The world file `{}` should have 6 numbers but it has {}.",
+            world_file.display(),
+            values.len()
+        );
+    };
+    ensure!(
+        x_rotation == 0.0 && y_rotation == 0.0,
+        "The world file `{}` is rotated, which isn't supported.",
+        world_file.display()
+    );
+    ensure!(
+        x_size.is_finite() && x_size != 0.0 && y_size.is_finite() && y_size != 0.0,
+        "The world file `{}` has a pixel size of {x_size} by {y_size} m, which isn't a scale.",
+        world_file.display()
+    );
+    Ok((x_size.abs() / 1000.0, y_size.abs() / 1000.0))
+}
+
+/// The usual names of the world file of an image, like `map.pgw`, `map.pngw` and `map.wld` for `map.png`.
+fn world_file_candidates(path: &Path) -> Vec<PathBuf> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default();
+    let mut short = String::new();
+    short.extend(extension.chars().next());
+    short.extend(extension.chars().last());
+    vec![
+        path.with_extension(short + "w"),
+        path.with_extension(format!("{extension}w")),
+        path.with_extension("wld"),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A mask of 100x100 pixels of land with sea from x = 70 and the outside from x = 90.
+    fn write_mask(path: &Path) {
+        let legend = Legend::default();
+        let pixels = (0..100)
+            .flat_map(|_| {
+                (0..100).flat_map(|x| match x {
+                    ..70 => legend.land,
+                    70..90 => legend.sea,
+                    90.. => legend.outside,
+                })
+            })
+            .collect::<Vec<_>>();
+        let mut encoder = png::Encoder::new(File::create(path).unwrap(), 100, 100);
+        encoder.set_color(png::ColorType::Rgb);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&pixels).unwrap();
+    }
+
+    #[test]
+    fn radials_end_outside_or_at_the_edge() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("mask.png");
+        write_mask(&path);
+        let options = MaskOptions {
+            legend: Legend::default(),
+            transmitter_px: (50.0, 50.0),
+            km_per_px: Some(2.0),
+            step_degrees: 90.0,
+        };
+        let lines = trace(&path, &options).unwrap();
+        assert_eq!(
+            lines.iter().map(Line::angle).collect::<Vec<_>>(),
+            [0.0, 90.0, 180.0, 270.0]
+        );
+
+        let east = lines[1].segments().collect::<Vec<_>>();
+        assert_eq!(east.len(), 2);
+        assert_eq!(east[0].terrain(), Some(Terrain::Ground));
+        assert!((east[0].length_km() - 39.0).abs() <= 0.5, "{east:?}");
+        assert_eq!(east[1].terrain(), Some(Terrain::Sea));
+        assert!((east[1].length_km() - 40.0).abs() <= 0.5, "{east:?}");
+        let west = lines[3].segments().collect::<Vec<_>>();
+        assert_eq!(west.len(), 1);
+        assert!((west[0].length_km() - 101.0).abs() <= 0.5, "{west:?}");
+
+        let world_file = path.with_extension("pgw");
+        std::fs::write(&world_file, "2000\n0\n0\n-2000\n400000\n4200000\n").unwrap();
+        let georeferenced = trace(
+            &path,
+            &MaskOptions {
+                km_per_px: None,
+                ..options.clone()
+            },
+        );
+        assert_eq!(georeferenced.unwrap(), lines);
+        std::fs::write(&world_file, "0\n0\n0\n-2000\n400000\n4200000\n").unwrap();
+        let without_size = trace(
+            &path,
+            &MaskOptions {
+                km_per_px: None,
+                ..options.clone()
+            },
+        );
+        std::fs::remove_file(world_file).unwrap();
+        assert!(without_size.is_err());
+        let without_scale = trace(
+            &path,
+            &MaskOptions {
+                km_per_px: Some(0.0),
+                ..options.clone()
+            },
+        );
+        assert!(without_scale.is_err());
+
+        let outside = trace(
+            &path,
+            &MaskOptions {
+                transmitter_px: (95.0, 50.0),
+                ..options
+            },
+        );
+        assert!(outside.is_err());
+    }
+}
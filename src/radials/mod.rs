@@ -0,0 +1,41 @@
+//! Lines traced automatically along radials from the transmitter, instead of measured by hand on a map.
+//!
+//! The angles are bearings, clockwise from north, like those of our measurements.
+
+pub mod atlas;
+pub mod dem;
+pub mod grid;
+pub mod mask;
+pub mod osm;
+mod pbf;
+pub mod vector;
+
+use crate::terrain::{Line, LineSegment};
+
+/// The bearings in degrees from 0 up to but excluding 360, every `step_degrees`.
+pub fn bearings(step_degrees: f64) -> impl Iterator<Item = f64> {
+    let count = (360.0 / step_degrees).ceil() as usize;
+    (0..count)
+        .map(move |i| i as f64 * step_degrees)
+        .filter(|&angle| angle < 360.0)
+}
+
+/// Builds a line out of the stretches of ground along a radial, in order from the transmitter,
+/// merging adjacent stretches of the same terrain and medium into single segments.
+fn line_from_stretches(angle: f64, stretches: impl IntoIterator<Item = LineSegment>) -> Line {
+    let mut segments: Vec<LineSegment> = Vec::new();
+    for stretch in stretches {
+        if stretch.length_km() <= 0.0 {
+            continue;
+        }
+        match segments.last_mut() {
+            Some(last)
+                if last.medium() == stretch.medium() && last.terrain() == stretch.terrain() =>
+            {
+                last.set_length_km(last.length_km() + stretch.length_km());
+            }
+            _ => segments.push(stretch),
+        }
+    }
+    Line::with_segments(angle, segments)
+}
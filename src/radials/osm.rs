@@ -0,0 +1,368 @@
+//! Radials over the land of a local OpenStreetMap extract in `.osm.pbf`, assembled from its `natural=coastline` ways
+//! and optionally its water bodies, then cast like those over [`vector`](super::vector) polygons.
+
+use super::pbf::{self, Bounds, Element, Kind};
+use super::vector::{read_polygons, trace_polygons, RadialOptions, Ring};
+use crate::terrain::Line;
+use anyhow::{ensure, Context, Result};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// Traces a line along every bearing of `options` over the land of the extract,
+/// ending at the first crossing of the border polygons of the file `border` if there is one.
+/// With `water`, lakes and other `natural=water` areas are taken as sea.
+pub fn trace(
+    path: &Path,
+    border: Option<&Path>,
+    water: bool,
+    options: &RadialOptions,
+) -> Result<Vec<Line>> {
+    let land = read_land(path, water)?;
+    let border = border.map(read_polygons).transpose()?;
+    trace_polygons(&land, border.as_deref(), options)
+}
+
+/// The rings of the land of the extract, with the water bodies as holes if `water`.
+///
+/// Coastlines have land on their left, so those cut off at the edge of the extract are closed along it counter-clockwise.
+pub fn read_land(path: &Path, water: bool) -> Result<Vec<Ring>> {
+    let mut water_members = HashSet::new();
+    if water {
+        pbf::read(path, Kind::Relation, |element| {
+            if let Element::Relation { tags, ways } = element {
+                if has_tag(&tags, "type", "multipolygon") && has_tag(&tags, "natural", "water") {
+                    water_members.extend(ways);
+                }
+            }
+        })?;
+    }
+
+    let mut coastlines = Vec::new();
+    let mut water_ways = Vec::new();
+    let header_bounds = pbf::read(path, Kind::Way, |element| {
+        if let Element::Way { id, tags, nodes } = element {
+            if has_tag(&tags, "natural", "coastline") {
+                coastlines.push(nodes);
+            } else if water && (has_tag(&tags, "natural", "water") || water_members.contains(&id)) {
+                water_ways.push(nodes);
+            }
+        }
+    })?;
+    ensure!(
+        !coastlines.is_empty(),
+        "There are no coastlines in `{}`.",
+        path.display()
+    );
+
+    let needed = coastlines
+        .iter()
+        .chain(&water_ways)
+        .flatten()
+        .copied()
+        .collect::<HashSet<_>>();
+    let mut positions = HashMap::with_capacity(needed.len());
+    let mut node_bounds: Option<Bounds> = None;
+    pbf::read(path, Kind::Node, |element| {
+        if let Element::Node {
+            id,
+            longitude,
+            latitude,
+        } = element
+        {
+            let (west, south, east, north) =
+                node_bounds.get_or_insert((longitude, latitude, longitude, latitude));
+            *west = west.min(longitude);
+            *south = south.min(latitude);
+            *east = east.max(longitude);
+            *north = north.max(latitude);
+            if needed.contains(&id) {
+                positions.insert(id, (longitude, latitude));
+            }
+        }
+    })?;
+    let bounds = header_bounds.or(node_bounds).with_context(|| {
+        format!(
+            "`{}` has coastlines but neither a bounding box nor nodes to bound them.",
+            path.display()
+        )
+    })?;
+    // The ways of an extract may refer to nodes that were cut off.
+    let ring = |nodes: Vec<i64>| -> Ring {
+        nodes
+            .iter()
+            .filter_map(|id| positions.get(id).copied())
+            .collect()
+    };
+
+    let (closed, open): (Vec<_>, Vec<_>) = join(coastlines, true)
+        .into_iter()
+        .partition(|chain| chain.first() == chain.last());
+    let mut rings = closed.into_iter().map(ring).collect::<Vec<_>>();
+    let open = open.into_iter().map(ring).filter(|chain| chain.len() >= 2);
+    rings.extend(close_along_bounds(open.collect(), bounds));
+    rings.extend(
+        join(water_ways, false)
+            .into_iter()
+            .filter(|chain| chain.first() == chain.last())
+            .map(ring),
+    );
+    Ok(rings)
+}
+
+fn has_tag(tags: &[(&str, &str)], key: &str, value: &str) -> bool {
+    tags.contains(&(key, value))
+}
+
+/// Joins ways that share end nodes into chains, which are closed when they end where they start.
+/// `oriented` ways are only joined head to tail, as coastlines must keep land on their left.
+fn join(ways: Vec<Vec<i64>>, oriented: bool) -> Vec<Vec<i64>> {
+    let ways = ways
+        .into_iter()
+        .filter(|way| way.len() >= 2)
+        .collect::<Vec<_>>();
+    let mut by_end = HashMap::<i64, Vec<usize>>::new();
+    for (i, way) in ways.iter().enumerate() {
+        by_end.entry(way[0]).or_default().push(i);
+        by_end.entry(way[way.len() - 1]).or_default().push(i);
+    }
+    let mut used = vec![false; ways.len()];
+    let mut chains = Vec::new();
+    for first in 0..ways.len() {
+        if used[first] {
+            continue;
+        }
+        used[first] = true;
+        let mut chain = ways[first].clone();
+        let mut joiner = Joiner {
+            ways: &ways,
+            by_end: &by_end,
+            used: &mut used,
+            oriented,
+        };
+        joiner.extend(&mut chain, false);
+        // Extends the start of the chain by extending the end of the reversed chain.
+        chain.reverse();
+        joiner.extend(&mut chain, true);
+        chain.reverse();
+        chains.push(chain);
+    }
+    chains
+}
+
+struct Joiner<'a> {
+    ways: &'a [Vec<i64>],
+    by_end: &'a HashMap<i64, Vec<usize>>,
+    used: &'a mut [bool],
+    oriented: bool,
+}
+
+impl Joiner<'_> {
+    /// Appends unused ways to the end of `chain` until it's closed or none fits.
+    /// If the chain is `reversed`, oriented ways fit only by their end, and are appended reversed.
+    fn extend(&mut self, chain: &mut Vec<i64>, reversed: bool) {
+        while chain.first() != chain.last() {
+            let end = chain[chain.len() - 1];
+            let next = self.by_end[&end].iter().copied().find(|&i| {
+                let (starts, ends) = (self.ways[i][0] == end, self.ways[i].last() == Some(&end));
+                !self.used[i]
+                    && match (self.oriented, reversed) {
+                        (false, _) => starts || ends,
+                        (true, false) => starts,
+                        (true, true) => ends,
+                    }
+            });
+            let Some(next) = next else {
+                break;
+            };
+            self.used[next] = true;
+            let way = &self.ways[next];
+            if way[0] == end && !(self.oriented && reversed) {
+                chain.extend(&way[1..]);
+            } else {
+                chain.extend(way.iter().rev().skip(1));
+            }
+        }
+    }
+}
+
+/// Closes coastlines cut off at the edge of the extract into rings, following the edge counter-clockwise
+/// from the end of each to the start of the next, so that land stays on the left.
+fn close_along_bounds(chains: Vec<Ring>, (west, south, east, north): Bounds) -> Vec<Ring> {
+    let (width, height) = (east - west, north - south);
+    let perimeter = 2.0 * (width + height);
+    // How far a point is along the edge counter-clockwise from the south-west corner.
+    let position = |(x, y): (f64, f64)| {
+        let distances = [y - south, east - x, north - y, x - west].map(f64::abs);
+        let edge = (0..4)
+            .min_by(|&a, &b| distances[a].total_cmp(&distances[b]))
+            .expect("There are four edges.");
+        match edge {
+            0 => (x - west).clamp(0.0, width),
+            1 => width + (y - south).clamp(0.0, height),
+            2 => width + height + (east - x).clamp(0.0, width),
+            _ => 2.0 * width + height + (north - y).clamp(0.0, height),
+        }
+    };
+    let corners = [
+        (width, (east, south)),
+        (width + height, (east, north)),
+        (2.0 * width + height, (west, north)),
+        (perimeter, (west, south)),
+    ];
+    let counter_clockwise = |from: f64, to: f64| (to - from).rem_euclid(perimeter);
+
+    let starts = chains
+        .iter()
+        .map(|chain| position(chain[0]))
+        .collect::<Vec<_>>();
+    let mut used = vec![false; chains.len()];
+    let mut rings = Vec::new();
+    for first in 0..chains.len() {
+        if used[first] {
+            continue;
+        }
+        used[first] = true;
+        let mut ring = chains[first].clone();
+        let mut current = first;
+        loop {
+            let end = position(chains[current][chains[current].len() - 1]);
+            let next = (0..chains.len())
+                .filter(|&i| !used[i] || i == first)
+                .min_by(|&a, &b| {
+                    counter_clockwise(end, starts[a]).total_cmp(&counter_clockwise(end, starts[b]))
+                })
+                .expect("The first chain is always a candidate.");
+            let gap = counter_clockwise(end, starts[next]);
+            let mut passed = corners
+                .iter()
+                .filter(|(corner, _)| counter_clockwise(end, *corner) < gap)
+                .collect::<Vec<_>>();
+            passed.sort_by(|a, b| {
+                counter_clockwise(end, a.0).total_cmp(&counter_clockwise(end, b.0))
+            });
+            ring.extend(passed.into_iter().map(|&(_, point)| point));
+            if next == first {
+                break;
+            }
+            used[next] = true;
+            ring.extend(&chains[next]);
+            current = next;
+        }
+        ring.push(ring[0]);
+        rings.push(ring);
+    }
+    rings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::radials::pbf::write::*;
+    use crate::terrain::defaults::Terrain;
+
+    const NODES: [(i64, (f64, f64)); 12] = [
+        // A coastline from south to north, with land to the west of it.
+        (1, (1.0, 0.0)),
+        (2, (1.0, 1.0)),
+        (3, (1.0, 2.0)),
+        // An island.
+        (4, (1.5, 0.9)),
+        (5, (1.6, 0.9)),
+        (6, (1.6, 1.1)),
+        (7, (1.5, 1.1)),
+        // A lake.
+        (8, (0.2, 0.9)),
+        (9, (0.3, 0.9)),
+        (10, (0.3, 1.1)),
+        (11, (0.2, 1.1)),
+        // Some other node.
+        (12, (0.7, 1.9)),
+    ];
+
+    fn way(id: u64, tag: u64, nodes: &[i64]) -> Vec<u8> {
+        let mut way = Vec::new();
+        field_varint(&mut way, 1, id);
+        field_bytes(&mut way, 2, &packed([1]));
+        field_bytes(&mut way, 3, &packed([tag]));
+        field_bytes(&mut way, 8, &deltas(nodes));
+        way
+    }
+
+    /// An extract from 0 to 2 degrees of longitude and latitude.
+    fn write_extract(path: &Path) {
+        let mut file = Vec::new();
+        let mut bbox = Vec::new();
+        for (number, degrees) in [(1, 0), (2, 2), (3, 2), (4, 0)] {
+            field_varint(&mut bbox, number, zigzag(degrees * 1_000_000_000));
+        }
+        let mut header = Vec::new();
+        field_bytes(&mut header, 1, &bbox);
+        field_bytes(&mut header, 4, b"OsmSchema-V0.6");
+        field_bytes(&mut header, 4, b"DenseNodes");
+        blob(&mut file, "OSMHeader", &header);
+
+        let mut strings = Vec::new();
+        for string in ["", "natural", "coastline", "water"] {
+            field_bytes(&mut strings, 1, string.as_bytes());
+        }
+        let mut dense = Vec::new();
+        let ids = NODES.map(|(id, _)| id);
+        field_bytes(&mut dense, 1, &deltas(&ids));
+        // In units of the default granularity of 100 nanodegrees.
+        let units = |degrees: f64| (degrees * 1e7).round() as i64;
+        field_bytes(
+            &mut dense,
+            8,
+            &deltas(&NODES.map(|(_, (_, lat))| units(lat))),
+        );
+        field_bytes(
+            &mut dense,
+            9,
+            &deltas(&NODES.map(|(_, (lon, _))| units(lon))),
+        );
+        let mut nodes = Vec::new();
+        field_bytes(&mut nodes, 2, &dense);
+        let mut ways = Vec::new();
+        // The coastline is split into two ways, in the wrong order.
+        field_bytes(&mut ways, 3, &way(11, 2, &[2, 3]));
+        field_bytes(&mut ways, 3, &way(10, 2, &[1, 2]));
+        field_bytes(&mut ways, 3, &way(12, 2, &[4, 5, 6, 7, 4]));
+        field_bytes(&mut ways, 3, &way(13, 3, &[8, 9, 10, 11, 8]));
+        let mut block = Vec::new();
+        field_bytes(&mut block, 1, &strings);
+        field_bytes(&mut block, 2, &nodes);
+        field_bytes(&mut block, 2, &ways);
+        blob(&mut file, "OSMData", &block);
+        std::fs::write(path, file).unwrap();
+    }
+
+    fn terrains(line: &Line) -> Vec<Terrain> {
+        line.segments()
+            .map(|segment| segment.terrain().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn coastlines_are_closed_along_the_extract() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("extract.osm.pbf");
+        write_extract(&path);
+        let options = RadialOptions {
+            latitude: 1.0,
+            longitude: 0.5,
+            step_degrees: 90.0,
+            length_km: Some(150.0),
+        };
+        let with_water = trace(&path, None, true, &options).unwrap();
+        let without_water = trace(&path, None, false, &options).unwrap();
+
+        use Terrain::{Ground, Sea};
+        assert_eq!(terrains(&without_water[1]), [Ground, Sea, Ground, Sea]);
+        assert_eq!(with_water[1], without_water[1]);
+        assert!((without_water[1].segments().next().unwrap().length_km() - 55.6).abs() < 0.1);
+        // The land ends at the edge of the extract.
+        assert_eq!(terrains(&without_water[3]), [Ground, Sea]);
+        assert_eq!(terrains(&with_water[3]), [Ground, Sea, Ground, Sea]);
+        assert_eq!(terrains(&with_water[0]), [Ground, Sea]);
+    }
+}
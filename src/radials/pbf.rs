@@ -0,0 +1,404 @@
+//! The little of the OpenStreetMap PBF format that's needed to find coastlines and water bodies:
+//! the nodes, ways and relations of its blocks, decoded from the protobuf wire format directly.
+
+use anyhow::{bail, ensure, Context, Result};
+use flate2::read::ZlibDecoder;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(super) enum Kind {
+    Node,
+    Way,
+    Relation,
+}
+
+pub(super) enum Element<'a> {
+    Node {
+        id: i64,
+        longitude: f64,
+        latitude: f64,
+    },
+    Way {
+        id: i64,
+        tags: Vec<(&'a str, &'a str)>,
+        nodes: Vec<i64>,
+    },
+    Relation {
+        tags: Vec<(&'a str, &'a str)>,
+        /// The ids of the ways among its members.
+        ways: Vec<i64>,
+    },
+}
+
+/// The bounding box of an extract as (west, south, east, north) in degrees.
+pub(super) type Bounds = (f64, f64, f64, f64);
+
+/// Visits the elements of `kind` in the file, returning the bounding box of its header if it has one.
+pub(super) fn read(
+    path: &Path,
+    kind: Kind,
+    mut visit: impl FnMut(Element),
+) -> Result<Option<Bounds>> {
+    let file = File::open(path).with_context(|| format!("Could not open `{}`", path.display()))?;
+    let mut reader = BufReader::new(file);
+    let mut bounds = None;
+    loop {
+        let mut length = [0; 4];
+        match reader.read_exact(&mut length) {
+            Ok(()) => {}
+            Err(error) if error.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(error) => {
+                return Err(error).context(format!("Could not read `{}`", path.display()))
+            }
+        }
+        let header = read_bytes(&mut reader, u32::from_be_bytes(length) as usize)?;
+        let mut blob_type = "";
+        let mut blob_length = 0;
+        for field in Fields(&header) {
+            match field? {
+                (1, Value::Bytes(bytes)) => blob_type = std::str::from_utf8(bytes)?,
+                (3, Value::Varint(value)) => blob_length = value as usize,
+                _ => {}
+            }
+        }
+        let blob = read_bytes(&mut reader, blob_length)?;
+        let block = decompress(&blob)
+            .with_context(|| format!("Could not decompress a block of `{}`", path.display()))?;
+        match blob_type {
+            "OSMHeader" => bounds = read_header(&block)?,
+            "OSMData" => read_block(&block, kind, &mut visit)
+                .with_context(|| format!("Could not decode a block of `{}`", path.display()))?,
+            _ => {}
+        }
+    }
+    Ok(bounds)
+}
+
+fn read_bytes(reader: &mut impl Read, length: usize) -> Result<Vec<u8>> {
+    let mut bytes = vec![0; length];
+    reader
+        .read_exact(&mut bytes)
+        .context("The file ends in the middle of a block.")?;
+    Ok(bytes)
+}
+
+fn decompress(blob: &[u8]) -> Result<Vec<u8>> {
+    for field in Fields(blob) {
+        match field? {
+            (1, Value::Bytes(raw)) => return Ok(raw.to_vec()),
+            (3, Value::Bytes(zlib)) => {
+                let mut bytes = Vec::new();
+                ZlibDecoder::new(zlib).read_to_end(&mut bytes)?;
+                return Ok(bytes);
+            }
+            (4..=7, _) => bail!("Only uncompressed and zlib blocks are supported."),
+            _ => {}
+        }
+    }
+    bail!("The block has no data.")
+}
+
+fn read_header(block: &[u8]) -> Result<Option<Bounds>> {
+    for field in Fields(block) {
+        match field? {
+            (1, Value::Bytes(bbox)) => {
+                let mut edges = [0.0; 4];
+                for field in Fields(bbox) {
+                    if let (number @ 1..=4, Value::Varint(value)) = field? {
+                        edges[number as usize - 1] = zigzag(value) as f64 * 1e-9;
+                    }
+                }
+                let [left, right, top, bottom] = edges;
+                return Ok(Some((left, bottom, right, top)));
+            }
+            (4, Value::Bytes(feature)) => {
+                let feature = std::str::from_utf8(feature)?;
+                ensure!(
+                    matches!(feature, "OsmSchema-V0.6" | "DenseNodes"),
+                    "The file needs the unsupported feature `{feature}`."
+                );
+            }
+            _ => {}
+        }
+    }
+    Ok(None)
+}
+
+fn read_block(block: &[u8], kind: Kind, visit: &mut impl FnMut(Element)) -> Result<()> {
+    let mut strings = Vec::new();
+    let mut groups = Vec::new();
+    let mut granularity = 100;
+    let (mut latitude_offset, mut longitude_offset) = (0, 0);
+    for field in Fields(block) {
+        match field? {
+            (1, Value::Bytes(table)) => {
+                for field in Fields(table) {
+                    if let (1, Value::Bytes(string)) = field? {
+                        strings.push(std::str::from_utf8(string).unwrap_or_default());
+                    }
+                }
+            }
+            (2, Value::Bytes(group)) => groups.push(group),
+            (17, Value::Varint(value)) => granularity = value as i64,
+            (19, Value::Varint(value)) => latitude_offset = value as i64,
+            (20, Value::Varint(value)) => longitude_offset = value as i64,
+            _ => {}
+        }
+    }
+    let degrees = |offset: i64, value: i64| (offset + granularity * value) as f64 * 1e-9;
+    let string = |index: u64| strings.get(index as usize).copied().unwrap_or_default();
+    let tags = |keys: &[u64], values: &[u64]| -> Vec<(&str, &str)> {
+        keys.iter()
+            .zip(values)
+            .map(|(&key, &value)| (string(key), string(value)))
+            .collect()
+    };
+
+    for group in groups {
+        for field in Fields(group) {
+            match (field?, kind) {
+                ((1, Value::Bytes(node)), Kind::Node) => {
+                    let (mut id, mut latitude, mut longitude) = (0, 0, 0);
+                    for field in Fields(node) {
+                        match field? {
+                            (1, Value::Varint(value)) => id = zigzag(value),
+                            (8, Value::Varint(value)) => latitude = zigzag(value),
+                            (9, Value::Varint(value)) => longitude = zigzag(value),
+                            _ => {}
+                        }
+                    }
+                    visit(Element::Node {
+                        id,
+                        longitude: degrees(longitude_offset, longitude),
+                        latitude: degrees(latitude_offset, latitude),
+                    });
+                }
+                ((2, Value::Bytes(dense)), Kind::Node) => {
+                    let (mut ids, mut latitudes, mut longitudes) = (vec![], vec![], vec![]);
+                    for field in Fields(dense) {
+                        match field? {
+                            (1, value) => ids = value.deltas()?,
+                            (8, value) => latitudes = value.deltas()?,
+                            (9, value) => longitudes = value.deltas()?,
+                            _ => {}
+                        }
+                    }
+                    for ((id, latitude), longitude) in
+                        ids.into_iter().zip(latitudes).zip(longitudes)
+                    {
+                        visit(Element::Node {
+                            id,
+                            longitude: degrees(longitude_offset, longitude),
+                            latitude: degrees(latitude_offset, latitude),
+                        });
+                    }
+                }
+                ((3, Value::Bytes(way)), Kind::Way) => {
+                    let (mut id, mut keys, mut values, mut nodes) = (0, vec![], vec![], vec![]);
+                    for field in Fields(way) {
+                        match field? {
+                            (1, Value::Varint(value)) => id = value as i64,
+                            (2, value) => keys = value.varints()?,
+                            (3, value) => values = value.varints()?,
+                            (8, value) => nodes = value.deltas()?,
+                            _ => {}
+                        }
+                    }
+                    visit(Element::Way {
+                        id,
+                        tags: tags(&keys, &values),
+                        nodes,
+                    });
+                }
+                ((4, Value::Bytes(relation)), Kind::Relation) => {
+                    let (mut keys, mut values, mut members, mut types) =
+                        (vec![], vec![], vec![], vec![]);
+                    for field in Fields(relation) {
+                        match field? {
+                            (2, value) => keys = value.varints()?,
+                            (3, value) => values = value.varints()?,
+                            (9, value) => members = value.deltas()?,
+                            (10, value) => types = value.varints()?,
+                            _ => {}
+                        }
+                    }
+                    const WAY: u64 = 1;
+                    let ways = members
+                        .into_iter()
+                        .zip(types)
+                        .filter(|&(_, member_type)| member_type == WAY)
+                        .map(|(id, _)| id)
+                        .collect();
+                    visit(Element::Relation {
+                        tags: tags(&keys, &values),
+                        ways,
+                    });
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The value of a field of a protobuf message.
+#[derive(Debug, Copy, Clone)]
+enum Value<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+    Fixed,
+}
+
+impl<'a> Value<'a> {
+    /// The varints of a repeated field, whether packed or not.
+    fn varints(self) -> Result<Vec<u64>> {
+        match self {
+            Self::Varint(value) => Ok(vec![value]),
+            Self::Bytes(mut bytes) => {
+                let mut values = Vec::new();
+                while !bytes.is_empty() {
+                    values.push(varint(&mut bytes)?);
+                }
+                Ok(values)
+            }
+            Self::Fixed => bail!("Expected varints but found a fixed-size value."),
+        }
+    }
+
+    /// The values of a packed field of signed varints, each stored as the difference from the previous one.
+    fn deltas(self) -> Result<Vec<i64>> {
+        let mut current = 0;
+        Ok(self
+            .varints()?
+            .into_iter()
+            .map(|delta| {
+                current += zigzag(delta);
+                current
+            })
+            .collect())
+    }
+}
+
+/// The fields of a protobuf message as (number, value).
+struct Fields<'a>(&'a [u8]);
+
+impl<'a> Iterator for Fields<'a> {
+    type Item = Result<(u64, Value<'a>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0.is_empty() {
+            return None;
+        }
+        let mut field = || {
+            let key = varint(&mut self.0)?;
+            let value = match key & 0b111 {
+                0 => Value::Varint(varint(&mut self.0)?),
+                1 | 5 => {
+                    let size = if key & 0b111 == 1 { 8 } else { 4 };
+                    ensure!(self.0.len() >= size, "A fixed-size value is cut off.");
+                    self.0 = &self.0[size..];
+                    Value::Fixed
+                }
+                2 => {
+                    let length = varint(&mut self.0)? as usize;
+                    ensure!(
+                        self.0.len() >= length,
+                        "A length-delimited value is cut off."
+                    );
+                    let (bytes, rest) = self.0.split_at(length);
+                    self.0 = rest;
+                    Value::Bytes(bytes)
+                }
+                wire_type => bail!("Unsupported wire type {wire_type}."),
+            };
+            Ok((key >> 3, value))
+        };
+        let field = field();
+        if field.is_err() {
+            // Stops at the first error rather than misreading the rest.
+            self.0 = &[];
+        }
+        Some(field)
+    }
+}
+
+fn varint(bytes: &mut &[u8]) -> Result<u64> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = bytes.split_first().context("A varint is cut off.")?;
+        *bytes = rest;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    bail!("A varint is too long.")
+}
+
+fn zigzag(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}
+
+/// Writes the parts of a PBF file for tests.
+#[cfg(test)]
+pub(super) mod write {
+    use flate2::write::ZlibEncoder;
+    use std::io::Write;
+
+    pub fn varint(bytes: &mut Vec<u8>, mut value: u64) {
+        while value >= 0x80 {
+            bytes.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        bytes.push(value as u8);
+    }
+
+    pub fn zigzag(value: i64) -> u64 {
+        ((value << 1) ^ (value >> 63)) as u64
+    }
+
+    pub fn field_varint(bytes: &mut Vec<u8>, number: u64, value: u64) {
+        varint(bytes, number << 3);
+        varint(bytes, value);
+    }
+
+    pub fn field_bytes(bytes: &mut Vec<u8>, number: u64, value: &[u8]) {
+        varint(bytes, number << 3 | 2);
+        varint(bytes, value.len() as u64);
+        bytes.extend(value);
+    }
+
+    pub fn packed(values: impl IntoIterator<Item = u64>) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for value in values {
+            varint(&mut bytes, value);
+        }
+        bytes
+    }
+
+    pub fn deltas(values: &[i64]) -> Vec<u8> {
+        let mut previous = 0;
+        packed(values.iter().map(|&value| {
+            let delta = value - previous;
+            previous = value;
+            zigzag(delta)
+        }))
+    }
+
+    /// A block of the file with its header, zlib compressed.
+    pub fn blob(file: &mut Vec<u8>, blob_type: &str, block: &[u8]) {
+        let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(block).unwrap();
+        let mut blob = Vec::new();
+        field_varint(&mut blob, 2, block.len() as u64);
+        field_bytes(&mut blob, 3, &encoder.finish().unwrap());
+        let mut header = Vec::new();
+        field_bytes(&mut header, 1, blob_type.as_bytes());
+        field_varint(&mut header, 3, blob.len() as u64);
+        file.extend((header.len() as u32).to_be_bytes());
+        file.extend(header);
+        file.extend(blob);
+    }
+}
@@ -0,0 +1,380 @@
+//! Radials cast along geodesics from the transmitter and intersected with polygons of land,
+//! read from GeoJSON or a Shapefile in longitude and latitude (WGS 84).
+//!
+//! The polygons are projected once onto an azimuthal equidistant plane centred on the transmitter,
+//! where every geodesic from it is a straight line with its true length, so that each radial is a ray.
+
+use super::{bearings, line_from_stretches};
+use crate::terrain::defaults::Terrain;
+use crate::terrain::{Line, LineSegment};
+use anyhow::{bail, ensure, Context, Result};
+use geographiclib_rs::{Geodesic, InverseGeodesic};
+use geojson::{GeoJson, Geometry, Value};
+use std::path::Path;
+
+/// A closed ring of a polygon as (longitude, latitude) in degrees.
+/// Holes are rings too, as whether a point is inside is decided by the even-odd rule.
+pub(super) type Ring = Vec<(f64, f64)>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RadialOptions {
+    /// Latitude of the transmitter in degrees.
+    pub latitude: f64,
+    /// Longitude of the transmitter in degrees.
+    pub longitude: f64,
+    /// The step of the bearings of the radials in degrees.
+    pub step_degrees: f64,
+    /// The length of the lines in km, or `None` to end them only at the border.
+    pub length_km: Option<f64>,
+}
+
+/// Traces a line along every bearing of `options` over the land polygons of the file `land`,
+/// ending at the first crossing of the border polygons of the file `border` if there is one.
+pub fn trace(land: &Path, border: Option<&Path>, options: &RadialOptions) -> Result<Vec<Line>> {
+    let land = read_polygons(land)?;
+    let border = border.map(read_polygons).transpose()?;
+    trace_polygons(&land, border.as_deref(), options)
+}
+
+pub(super) fn trace_polygons(
+    land: &[Ring],
+    border: Option<&[Ring]>,
+    options: &RadialOptions,
+) -> Result<Vec<Line>> {
+    ensure!(
+        options.step_degrees > 0.0,
+        "The step of the bearings must be positive but it was {}.",
+        options.step_degrees
+    );
+    ensure!(
+        border.is_some() || options.length_km.is_some(),
+        "The lines need a border or a length to end at."
+    );
+    let projection = Projection::new(options.latitude, options.longitude);
+    let land = projection.project_rings(land);
+    let border = border.map(|border| projection.project_rings(border));
+
+    bearings(options.step_degrees)
+        .map(|angle| {
+            let mut end_km = options.length_km.unwrap_or(f64::INFINITY);
+            if let Some(border) = &border {
+                let (inside, crossings) = crossings(border, angle);
+                ensure!(
+                    inside,
+                    "The transmitter at {}, {} is outside of the border.",
+                    options.latitude,
+                    options.longitude
+                );
+                end_km = end_km.min(crossings[0]);
+            }
+            let (mut inside, crossings) = crossings(&land, angle);
+            let mut stretches = Vec::new();
+            let mut start_km = 0.0;
+            for boundary_km in crossings
+                .into_iter()
+                .take_while(|&km| km < end_km)
+                .chain([end_km])
+            {
+                let terrain = if inside {
+                    Terrain::Ground
+                } else {
+                    Terrain::Sea
+                };
+                stretches.push(LineSegment::new(terrain.medium(), boundary_km - start_km));
+                start_km = boundary_km;
+                inside = !inside;
+            }
+            Ok(line_from_stretches(angle, stretches))
+        })
+        .collect()
+}
+
+/// Whether the transmitter is inside the rings, and the distances in km where the ray towards `angle` crosses them, in order.
+fn crossings(rings: &[Vec<(f64, f64)>], angle: f64) -> (bool, Vec<f64>) {
+    let (sin, cos) = angle.to_radians().sin_cos();
+    // The distance of a point to the left of the ray, which crosses an edge where the sign changes.
+    let side = |(x, y): (f64, f64)| sin * y - cos * x;
+    let mut crossings = Vec::new();
+    for ring in rings {
+        for (&p, &q) in ring.iter().zip(ring.iter().cycle().skip(1)) {
+            let (side_p, side_q) = (side(p), side(q));
+            // Counting a point on the ray as on its right keeps shared vertices from counting twice.
+            if (side_p > 0.0) == (side_q > 0.0) {
+                continue;
+            }
+            let fraction = side_p / (side_p - side_q);
+            let x = p.0 + (q.0 - p.0) * fraction;
+            let y = p.1 + (q.1 - p.1) * fraction;
+            let distance_km = sin * x + cos * y;
+            if distance_km > 0.0 {
+                crossings.push(distance_km);
+            }
+        }
+    }
+    crossings.sort_by(f64::total_cmp);
+    // The ray leaves every polygon it starts in, so it crosses them an odd number of times.
+    (crossings.len() % 2 == 1, crossings)
+}
+
+/// The azimuthal equidistant projection centred on the transmitter, in km east and north of it.
+struct Projection {
+    geodesic: Geodesic,
+    latitude: f64,
+    longitude: f64,
+}
+
+impl Projection {
+    fn new(latitude: f64, longitude: f64) -> Self {
+        Self {
+            geodesic: Geodesic::wgs84(),
+            latitude,
+            longitude,
+        }
+    }
+
+    fn project(&self, (longitude, latitude): (f64, f64)) -> (f64, f64) {
+        let (distance_m, azimuth, _, _): (f64, f64, f64, f64) =
+            self.geodesic
+                .inverse(self.latitude, self.longitude, latitude, longitude);
+        let (sin, cos) = azimuth.to_radians().sin_cos();
+        (distance_m / 1000.0 * sin, distance_m / 1000.0 * cos)
+    }
+
+    fn project_rings(&self, rings: &[Ring]) -> Vec<Vec<(f64, f64)>> {
+        rings
+            .iter()
+            .map(|ring| ring.iter().map(|&point| self.project(point)).collect())
+            .collect()
+    }
+}
+
+/// Reads the rings of the polygons of a `.geojson`, `.json` or `.shp` file.
+pub(super) fn read_polygons(path: &Path) -> Result<Vec<Ring>> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_lowercase);
+    let rings = match extension.as_deref() {
+        Some("shp") => read_shapefile(path)?,
+        Some("geojson" | "json") => read_geojson(path)?,
+        _ => bail!(
+            "Can't tell the format of `{}` from its extension, expected `.geojson`, `.json` or `.shp`.",
+            path.display()
+        ),
+    };
+    ensure!(
+        !rings.is_empty(),
+        "There are no polygons in `{}`.",
+        path.display()
+    );
+    Ok(rings)
+}
+
+fn read_geojson(path: &Path) -> Result<Vec<Ring>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Could not read `{}`", path.display()))?;
+    let geojson = text
+        .parse::<GeoJson>()
+        .with_context(|| format!("Could not parse the GeoJSON `{}`", path.display()))?;
+    let geometries: Vec<&Geometry> = match &geojson {
+        GeoJson::Geometry(geometry) => vec![geometry],
+        GeoJson::Feature(feature) => feature.geometry.iter().collect(),
+        GeoJson::FeatureCollection(collection) => collection
+            .features
+            .iter()
+            .filter_map(|feature| feature.geometry.as_ref())
+            .collect(),
+    };
+    let mut rings = Vec::new();
+    for geometry in geometries {
+        add_rings(&geometry.value, &mut rings);
+    }
+    Ok(rings)
+}
+
+/// Adds the rings of the polygons in a geometry, ignoring points and lines.
+fn add_rings(value: &Value, rings: &mut Vec<Ring>) {
+    let ring = |positions: &Vec<Vec<f64>>| {
+        positions
+            .iter()
+            .map(|position| (position[0], position[1]))
+            .collect()
+    };
+    match value {
+        Value::Polygon(polygon) => rings.extend(polygon.iter().map(ring)),
+        Value::MultiPolygon(polygons) => rings.extend(polygons.iter().flatten().map(ring)),
+        Value::GeometryCollection(geometries) => {
+            for geometry in geometries {
+                add_rings(&geometry.value, rings);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Reads the rings of the polygons of an ESRI Shapefile, whose records are polygons or null.
+fn read_shapefile(path: &Path) -> Result<Vec<Ring>> {
+    let bytes =
+        std::fs::read(path).with_context(|| format!("Could not read `{}`", path.display()))?;
+    let invalid = || format!("`{}` isn't a valid Shapefile.", path.display());
+    let big_i32 = |at: usize| -> Result<i32> {
+        let bytes = bytes.get(at..at + 4).with_context(invalid)?;
+        Ok(i32::from_be_bytes(bytes.try_into()?))
+    };
+    let little_i32 = |at: usize| -> Result<i32> {
+        let bytes = bytes.get(at..at + 4).with_context(invalid)?;
+        Ok(i32::from_le_bytes(bytes.try_into()?))
+    };
+    let little_f64 = |at: usize| -> Result<f64> {
+        let bytes = bytes.get(at..at + 8).with_context(invalid)?;
+        Ok(f64::from_le_bytes(bytes.try_into()?))
+    };
+    ensure!(big_i32(0)? == 9994, invalid());
+
+    const POLYGON: i32 = 5;
+    const POLYGON_Z: i32 = 15;
+    const POLYGON_M: i32 = 25;
+    let mut rings = Vec::new();
+    let mut record = 100;
+    while record < bytes.len() {
+        // The length of a record is in 16-bit words after its header of 8 bytes.
+        let length = usize::try_from(big_i32(record + 4)?)? * 2;
+        let content = record + 8;
+        match little_i32(content)? {
+            0 => {}
+            POLYGON | POLYGON_Z | POLYGON_M => {
+                let part_count = usize::try_from(little_i32(content + 36)?)?;
+                let point_count = usize::try_from(little_i32(content + 40)?)?;
+                let parts = (0..part_count)
+                    .map(|i| Ok(usize::try_from(little_i32(content + 44 + 4 * i)?)?))
+                    .chain([Ok(point_count)])
+                    .collect::<Result<Vec<_>>>()?;
+                let points = content + 44 + 4 * part_count;
+                for part in parts.windows(2) {
+                    let ring = (part[0]..part[1])
+                        .map(|i| {
+                            Ok((
+                                little_f64(points + 16 * i)?,
+                                little_f64(points + 16 * i + 8)?,
+                            ))
+                        })
+                        .collect::<Result<Ring>>()?;
+                    rings.push(ring);
+                }
+            }
+            shape_type => bail!(
+                "`{}` has shapes of type {shape_type} but only polygons are supported.",
+                path.display()
+            ),
+        }
+        record = content + length;
+    }
+    Ok(rings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(west: f64, south: f64, east: f64, north: f64) -> Ring {
+        vec![
+            (west, south),
+            (east, south),
+            (east, north),
+            (west, north),
+            (west, south),
+        ]
+    }
+
+    /// A Shapefile with a single polygon of `rings`.
+    fn write_shapefile(path: &Path, rings: &[Ring]) {
+        let point_count = rings.iter().map(Vec::len).sum::<usize>();
+        let mut content = Vec::new();
+        content.extend(5i32.to_le_bytes());
+        content.extend([0; 32]);
+        content.extend((rings.len() as i32).to_le_bytes());
+        content.extend((point_count as i32).to_le_bytes());
+        let mut start = 0;
+        for ring in rings {
+            content.extend((start as i32).to_le_bytes());
+            start += ring.len();
+        }
+        for &(x, y) in rings.iter().flatten() {
+            content.extend(x.to_le_bytes());
+            content.extend(y.to_le_bytes());
+        }
+        let mut bytes = Vec::new();
+        bytes.extend(9994i32.to_be_bytes());
+        bytes.extend([0; 20]);
+        bytes.extend((((100 + 8 + content.len()) / 2) as i32).to_be_bytes());
+        bytes.extend(1000i32.to_le_bytes());
+        bytes.extend(5i32.to_le_bytes());
+        bytes.extend([0; 64]);
+        bytes.extend(1i32.to_be_bytes());
+        bytes.extend(((content.len() / 2) as i32).to_be_bytes());
+        bytes.extend(content);
+        std::fs::write(path, bytes).unwrap();
+    }
+
+    #[test]
+    fn radials_cross_land_and_end_at_the_border() {
+        let directory = tempfile::tempdir().unwrap();
+        let land_path = directory.path().join("land.geojson");
+        let border_path = directory.path().join("border.geojson");
+        std::fs::write(
+            &land_path,
+            r#"{"type": "FeatureCollection", "features": [
+                {"type": "Feature", "properties": {}, "geometry": {"type": "Polygon",
+                    "coordinates": [[[0, 0], [1, 0], [1, 1], [0, 1], [0, 0]]]}},
+                {"type": "Feature", "properties": {}, "geometry": {"type": "MultiPolygon",
+                    "coordinates": [[[[1.5, 0], [1.6, 0], [1.6, 1], [1.5, 1], [1.5, 0]]]]}}
+            ]}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            &border_path,
+            r#"{"type": "Polygon", "coordinates": [[[-2, -2], [2, -2], [2, 2], [-2, 2], [-2, -2]]]}"#,
+        )
+        .unwrap();
+        let options = RadialOptions {
+            latitude: 0.5,
+            longitude: 0.5,
+            step_degrees: 90.0,
+            length_km: None,
+        };
+        let lines = trace(&land_path, Some(&border_path), &options).unwrap();
+
+        let geodesic = Geodesic::wgs84();
+        let distance_km = |longitude: f64| {
+            let distance_m: f64 = geodesic.inverse(0.5, 0.5, 0.5, longitude);
+            distance_m / 1000.0
+        };
+        let east = lines[1].segments().collect::<Vec<_>>();
+        let terrains = east
+            .iter()
+            .map(|segment| segment.terrain())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            terrains,
+            [Terrain::Ground, Terrain::Sea, Terrain::Ground, Terrain::Sea].map(Some)
+        );
+        assert!((east[0].length_km() - distance_km(1.0)).abs() < 0.01);
+        assert!((lines[1].max_distance() - distance_km(2.0)).abs() < 0.1);
+        let north = lines[0].segments().collect::<Vec<_>>();
+        assert_eq!(north.len(), 2);
+
+        let shapefile = directory.path().join("land.shp");
+        let land = [square(0.0, 0.0, 1.0, 1.0)];
+        write_shapefile(&shapefile, &land);
+        let options = RadialOptions {
+            length_km: Some(100.0),
+            ..options
+        };
+        let from_shapefile = trace(&shapefile, None, &options).unwrap();
+        assert_eq!(
+            from_shapefile,
+            trace_polygons(&land, None, &options).unwrap()
+        );
+        assert!((from_shapefile[2].max_distance() - 100.0).abs() < 1e-9);
+    }
+}
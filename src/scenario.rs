@@ -0,0 +1,236 @@
+use crate::terrain::defaults::Terrain;
+use crate::terrain::{Line, LineSegment};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// A value of a line's stations, atmosphere or media that can be changed.
+/// The electrical parameters of the ground are changed separately for each terrain type.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "&'static str", try_from = "String")
+)]
+pub enum Parameter {
+    TxHeight,
+    RxHeight,
+    Frequency,
+    TxPower,
+    SurfaceRefractivity,
+    Permittivity(Terrain),
+    Conductivity(Terrain),
+}
+
+impl Parameter {
+    pub const ALL: [Self; 9] = [
+        Self::TxHeight,
+        Self::RxHeight,
+        Self::Frequency,
+        Self::TxPower,
+        Self::SurfaceRefractivity,
+        Self::Permittivity(Terrain::Ground),
+        Self::Conductivity(Terrain::Ground),
+        Self::Permittivity(Terrain::Sea),
+        Self::Conductivity(Terrain::Sea),
+    ];
+
+    pub const fn unit(&self) -> &'static str {
+        match self {
+            Self::TxHeight | Self::RxHeight => "m",
+            Self::Frequency => "MHz",
+            Self::TxPower => "W",
+            Self::SurfaceRefractivity => "N-units",
+            Self::Permittivity(_) => "",
+            Self::Conductivity(_) => "S/m",
+        }
+    }
+
+    /// The name used for the parameter on the command line.
+    pub const fn key(&self) -> &'static str {
+        match self {
+            Self::TxHeight => "tx-height",
+            Self::RxHeight => "rx-height",
+            Self::Frequency => "frequency",
+            Self::TxPower => "tx-power",
+            Self::SurfaceRefractivity => "surface-refractivity",
+            Self::Permittivity(Terrain::Ground) => "land-epsilon",
+            Self::Conductivity(Terrain::Ground) => "land-sigma",
+            Self::Permittivity(Terrain::Sea) => "sea-epsilon",
+            Self::Conductivity(Terrain::Sea) => "sea-sigma",
+        }
+    }
+
+    /// The value in `line`, taken from its first segment of the terrain for the media.
+    /// Returns `None` if the line has no segment of that terrain.
+    pub fn value(self, line: &Line) -> Option<f64> {
+        let first_medium = || {
+            line.segments()
+                .find(|segment| self.applies_to(segment))
+                .map(LineSegment::medium)
+        };
+        match self {
+            Self::TxHeight => Some(line.transmitter().height_m),
+            Self::RxHeight => Some(line.receiver().height_m),
+            Self::Frequency => Some(line.transmitter().frequency_mhz),
+            Self::TxPower => Some(line.transmitter().power_w),
+            Self::SurfaceRefractivity => Some(line.surface_refractivity()),
+            Self::Permittivity(_) => first_medium().map(|medium| medium.epsilon),
+            Self::Conductivity(_) => first_medium().map(|medium| medium.sigma),
+        }
+    }
+
+    /// Replaces the value in `line` by `update` of it, in every segment of the terrain for the media.
+    pub(crate) fn update(self, line: &mut Line, update: impl Fn(f64) -> f64) {
+        let value = match self {
+            Self::TxHeight => &mut line.transmitter_mut().height_m,
+            Self::RxHeight => &mut line.receiver_mut().height_m,
+            Self::Frequency => &mut line.transmitter_mut().frequency_mhz,
+            Self::TxPower => &mut line.transmitter_mut().power_w,
+            Self::SurfaceRefractivity => line.surface_refractivity_mut(),
+            Self::Permittivity(_) | Self::Conductivity(_) => {
+                for segment in line.segments_mut() {
+                    if self.applies_to(segment) {
+                        let medium = segment.medium_mut();
+                        let value = match self {
+                            Self::Permittivity(_) => &mut medium.epsilon,
+                            _ => &mut medium.sigma,
+                        };
+                        *value = update(*value);
+                    }
+                }
+                return;
+            }
+        };
+        *value = update(*value);
+    }
+
+    pub fn applies_to(self, segment: &LineSegment) -> bool {
+        match self {
+            Self::Permittivity(terrain) | Self::Conductivity(terrain) => {
+                segment.terrain() == Some(terrain)
+            }
+            _ => true,
+        }
+    }
+}
+
+impl Display for Parameter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TxHeight => write!(f, "TX height"),
+            Self::RxHeight => write!(f, "RX height"),
+            Self::Frequency => write!(f, "Frequency"),
+            Self::TxPower => write!(f, "TX power"),
+            Self::SurfaceRefractivity => write!(f, "Surface refractivity (N_s)"),
+            Self::Permittivity(terrain) => write!(f, "{} permittivity (ε)", terrain.name()),
+            Self::Conductivity(terrain) => write!(f, "{} conductivity (σ)", terrain.name()),
+        }
+    }
+}
+
+impl FromStr for Parameter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|parameter| parameter.key() == s)
+            .ok_or_else(|| {
+                let keys = Self::ALL.map(|parameter| parameter.key());
+                format!(
+                    "`{s}` isn't a parameter. Expected one of: {}.",
+                    keys.join(", ")
+                )
+            })
+    }
+}
+
+impl From<Parameter> for &'static str {
+    fn from(parameter: Parameter) -> Self {
+        parameter.key()
+    }
+}
+
+impl TryFrom<String> for Parameter {
+    type Error = String;
+
+    fn try_from(key: String) -> Result<Self, Self::Error> {
+        key.parse()
+    }
+}
+
+/// Values that replace those of the lines' stations, atmosphere and media.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Scenario {
+    overrides: Vec<(Parameter, f64)>,
+}
+
+impl Scenario {
+    /// Sets the value of a parameter, replacing any previous value of it.
+    pub fn set(&mut self, parameter: Parameter, value: f64) {
+        self.overrides
+            .retain(|(existing, _)| *existing != parameter);
+        self.overrides.push((parameter, value));
+    }
+
+    pub fn overrides(&self) -> impl Iterator<Item = (Parameter, f64)> + '_ {
+        self.overrides.iter().copied()
+    }
+
+    pub fn with(mut self, parameter: Parameter, value: f64) -> Self {
+        self.set(parameter, value);
+        self
+    }
+
+    /// Makes a copy of `line` with the scenario's values wherever they apply.
+    pub fn apply(&self, line: &Line) -> Line {
+        let mut applied = line.clone();
+        for (parameter, value) in &self.overrides {
+            parameter.update(&mut applied, |_| *value);
+        }
+        applied
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn apply_changes_only_matching_terrain() {
+        let segments = [
+            LineSegment::new(Terrain::Ground.medium(), 100.0),
+            LineSegment::new(Terrain::Sea.medium(), 50.0),
+        ];
+        let line = Line::with_segments(10.0, segments);
+        let scenario = Scenario::default()
+            .with(Parameter::Conductivity(Terrain::Ground), 0.01)
+            .with(Parameter::Permittivity(Terrain::Ground), 10.0)
+            .with(Parameter::TxPower, 1000.0);
+        let applied = scenario.apply(&line);
+        let segments = applied.segments().collect::<Vec<_>>();
+
+        assert_eq!(applied.angle(), 10.0);
+        // Both overrides of the land apply, even though the first one changes its medium from the project's.
+        assert_eq!(segments[0].medium().sigma, 0.01);
+        assert_eq!(segments[0].medium().epsilon, 10.0);
+        assert_eq!(segments[0].terrain(), Some(Terrain::Ground));
+        assert_eq!(segments[0].length_km(), 100.0);
+        assert_eq!(segments[1].medium(), Terrain::Sea.medium());
+        assert_eq!(applied.transmitter().power_w, 1000.0);
+        assert_eq!(
+            Parameter::Conductivity(Terrain::Ground).value(&applied),
+            Some(0.01)
+        );
+        assert_eq!(line.transmitter().power_w, 10000.0);
+    }
+
+    #[test]
+    fn parse_parameter_keys() {
+        for parameter in Parameter::ALL {
+            assert_eq!(parameter.key().parse(), Ok(parameter));
+        }
+        assert!("power".parse::<Parameter>().is_err());
+    }
+}
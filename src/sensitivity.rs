@@ -0,0 +1,347 @@
+use crate::find_max_distance::find_max_distance_for_line;
+use crate::propagation_model::Lfmf;
+use crate::scenario::Parameter;
+use crate::terrain::Line;
+use anyhow::{ensure, Context, Result};
+use rayon::prelude::*;
+use std::fmt::{Display, Formatter};
+
+/// An input of a line that the maximum distance depends on.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Input {
+    Parameter(Parameter),
+    /// The boundary at the end of the segment with the given index, located at the given distance in km.
+    Boundary {
+        index: usize,
+        distance_km: f64,
+    },
+}
+
+impl Display for Input {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Parameter(parameter) => write!(f, "{parameter}"),
+            Self::Boundary { index, distance_km } => {
+                write!(f, "Boundary #{} ({distance_km:.2} km)", index + 1)
+            }
+        }
+    }
+}
+
+/// The steps by which each input gets perturbed, both upwards and downwards.
+#[derive(Debug, Copy, Clone)]
+pub struct Steps {
+    /// Relative step for the `LFMF_Parameters` fields, e.g. 0.1 for ±10%.
+    pub relative: f64,
+    /// Absolute step in km for the segment boundaries.
+    pub boundary_km: f64,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct Sensitivity {
+    pub input: Input,
+    /// The absolute step that the input was perturbed by, in the input's unit.
+    pub step: f64,
+    /// Half the difference between the maximum distances for the input increased and decreased by `step`.
+    /// `None` if the maximum distance couldn't be found for either of the perturbed lines.
+    pub distance_change_km: Option<f64>,
+}
+
+impl Sensitivity {
+    /// The central finite difference of the maximum distance with respect to the input.
+    pub fn derivative(&self) -> Option<f64> {
+        self.distance_change_km.map(|change| change / self.step)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LineSensitivity {
+    pub angle: f64,
+    pub max_distance_km: f64,
+    /// Sorted from the most to the least important input.
+    pub sensitivities: Vec<Sensitivity>,
+}
+
+/// Computes how much the maximum distance of `line` changes when each of its inputs is perturbed.
+pub fn analyse_line(
+    min_usable_field_strength: f64,
+    line: &Line,
+    steps: Steps,
+) -> Result<LineSensitivity> {
+    ensure!(
+        0.0 < steps.relative && steps.relative < 1.0,
+        "The relative step must be in the range (0, 1) but it was {}.",
+        steps.relative
+    );
+    ensure!(
+        0.0 < steps.boundary_km,
+        "The boundary step must be positive but it was {} km.",
+        steps.boundary_km
+    );
+    let max_distance_km = find_max_distance_for_line(&Lfmf, min_usable_field_strength, line)
+        .context("Could not find the unperturbed maximum distance.")?;
+
+    let parameters = Parameter::ALL
+        .into_iter()
+        .filter(|parameter| line.segments().any(|segment| parameter.applies_to(segment)))
+        .map(Input::Parameter);
+    let mut boundary_distance_km = 0.0;
+    let boundaries = line
+        .segments()
+        .enumerate()
+        .map(|(index, segment)| {
+            boundary_distance_km += segment.length_km();
+            Input::Boundary {
+                index,
+                distance_km: boundary_distance_km,
+            }
+        })
+        .collect::<Vec<_>>();
+    // The end of the last segment isn't a boundary between terrains.
+    let boundaries = &boundaries[..boundaries.len().saturating_sub(1)];
+    let inputs = parameters
+        .chain(boundaries.iter().copied())
+        .collect::<Vec<_>>();
+
+    let mut sensitivities: Vec<_> = inputs
+        .into_par_iter()
+        .map(|input| {
+            let step = match input {
+                Input::Parameter(parameter) => {
+                    let value = parameter
+                        .value(line)
+                        .expect("Only parameters that apply to some segment are analysed.");
+                    value * steps.relative
+                }
+                Input::Boundary { .. } => steps.boundary_km,
+            };
+            let distance_change_km = perturb(line, input, step)
+                .zip(perturb(line, input, -step))
+                .and_then(|(increased, decreased)| {
+                    let increased =
+                        find_max_distance_for_line(&Lfmf, min_usable_field_strength, &increased)
+                            .ok()?;
+                    let decreased =
+                        find_max_distance_for_line(&Lfmf, min_usable_field_strength, &decreased)
+                            .ok()?;
+                    Some((increased - decreased) * 0.5)
+                });
+            Sensitivity {
+                input,
+                step,
+                distance_change_km,
+            }
+        })
+        .collect();
+    sensitivities.sort_by(|a, b| {
+        let a = a.distance_change_km.map_or(-1.0, f64::abs);
+        let b = b.distance_change_km.map_or(-1.0, f64::abs);
+        b.total_cmp(&a)
+    });
+
+    Ok(LineSensitivity {
+        angle: line.angle(),
+        max_distance_km,
+        sensitivities,
+    })
+}
+
+/// Makes a copy of `line` with `input` changed by `step`.
+/// Returns `None` if a boundary can't be moved by `step` without a segment vanishing.
+fn perturb(line: &Line, input: Input, step: f64) -> Option<Line> {
+    let mut perturbed = line.clone();
+    match input {
+        Input::Parameter(parameter) => parameter.update(&mut perturbed, |value| value + step),
+        Input::Boundary { index, .. } => {
+            let segments = perturbed.segments_mut();
+            let before = segments[index].length_km() + step;
+            let after = segments[index + 1].length_km() - step;
+            if before <= 0.0 || after <= 0.0 {
+                return None;
+            }
+            segments[index].set_length_km(before);
+            segments[index + 1].set_length_km(after);
+        }
+    }
+    Some(perturbed)
+}
+
+/// How much an input matters across all the analysed lines.
+/// The boundaries of a line are summarised together by their most important one.
+#[derive(Debug, Clone)]
+pub struct SummaryRow {
+    pub name: String,
+    pub mean_abs_change_km: f64,
+    pub max_abs_change_km: f64,
+    pub angle_of_max: f64,
+    pub lines: usize,
+}
+
+/// Ranks the inputs from the most to the least important over all the analysed lines.
+pub fn summarise(analyses: &[LineSensitivity]) -> Vec<SummaryRow> {
+    let mut rows: Vec<SummaryRow> = Vec::new();
+    for analysis in analyses {
+        let mut line_changes: Vec<(String, f64)> = Vec::new();
+        for sensitivity in &analysis.sensitivities {
+            let Some(change) = sensitivity.distance_change_km.map(f64::abs) else {
+                continue;
+            };
+            let name = match sensitivity.input {
+                Input::Parameter(parameter) => parameter.to_string(),
+                Input::Boundary { .. } => "Segment boundaries".to_string(),
+            };
+            match line_changes.iter_mut().find(|(n, _)| *n == name) {
+                Some((_, existing)) => *existing = existing.max(change),
+                None => line_changes.push((name, change)),
+            }
+        }
+        for (name, change) in line_changes {
+            let row = match rows.iter_mut().position(|row| row.name == name) {
+                Some(position) => &mut rows[position],
+                None => {
+                    rows.push(SummaryRow {
+                        name,
+                        mean_abs_change_km: 0.0,
+                        max_abs_change_km: f64::NEG_INFINITY,
+                        angle_of_max: analysis.angle,
+                        lines: 0,
+                    });
+                    rows.last_mut().expect("Just pushed a row.")
+                }
+            };
+            // Keep a running sum for now, it's divided into a mean at the end.
+            row.mean_abs_change_km += change;
+            row.lines += 1;
+            if change > row.max_abs_change_km {
+                row.max_abs_change_km = change;
+                row.angle_of_max = analysis.angle;
+            }
+        }
+    }
+    for row in &mut rows {
+        row.mean_abs_change_km /= row.lines as f64;
+    }
+    rows.sort_by(|a, b| b.mean_abs_change_km.total_cmp(&a.mean_abs_change_km));
+    rows
+}
+
+pub fn print_line(analysis: &LineSensitivity) {
+    println!("Angle: {}", analysis.angle);
+    println!("Maximum distance: {} km", analysis.max_distance_km);
+    println!(
+        "  {:<4} {:<32} {:>14} {:>12} {:>16}",
+        "Rank", "Input", "Step", "Δd (km)", "∂d/∂x"
+    );
+    for (rank, sensitivity) in analysis.sensitivities.iter().enumerate() {
+        let unit = match sensitivity.input {
+            Input::Parameter(parameter) => parameter.unit(),
+            Input::Boundary { .. } => "km",
+        };
+        let step = format!("±{:.4} {unit}", sensitivity.step)
+            .trim_end()
+            .to_string();
+        let (change, derivative) = match sensitivity.distance_change_km {
+            Some(change) => (
+                format!("{change:+.3}"),
+                format!(
+                    "{:+.4e}",
+                    sensitivity.derivative().expect("There is a change.")
+                ),
+            ),
+            None => ("n/a".to_string(), "n/a".to_string()),
+        };
+        println!(
+            "  {:<4} {:<32} {:>14} {:>12} {:>16}",
+            rank + 1,
+            sensitivity.input.to_string(),
+            step,
+            change,
+            derivative
+        );
+    }
+    println!();
+}
+
+pub fn print_summary(rows: &[SummaryRow]) {
+    println!("Summary (inputs ranked by the mean absolute change of the maximum distance):");
+    println!(
+        "  {:<4} {:<32} {:>14} {:>14} {:>10} {:>6}",
+        "Rank", "Input", "Mean |Δd| km", "Max |Δd| km", "At angle", "Lines"
+    );
+    for (rank, row) in rows.iter().enumerate() {
+        println!(
+            "  {:<4} {:<32} {:>14.3} {:>14.3} {:>10} {:>6}",
+            rank + 1,
+            row.name,
+            row.mean_abs_change_km,
+            row.max_abs_change_km,
+            row.angle_of_max,
+            row.lines
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::terrain::defaults::Terrain;
+    use crate::terrain::LineSegment;
+
+    const STEPS: Steps = Steps {
+        relative: 0.1,
+        boundary_km: 1.0,
+    };
+
+    #[test]
+    fn analyse_line_with_one_ground_segment() {
+        let segment = LineSegment::new(Terrain::Ground.medium(), 500.0);
+        let line = Line::with_segments(0.0, [segment]);
+        let analysis = analyse_line(43.2, &line, STEPS).unwrap();
+        let inputs = analysis
+            .sensitivities
+            .iter()
+            .map(|sensitivity| sensitivity.input)
+            .collect::<Vec<_>>();
+        assert!(inputs.contains(&Input::Parameter(Parameter::Conductivity(Terrain::Ground))));
+        assert!(!inputs.contains(&Input::Parameter(Parameter::Conductivity(Terrain::Sea))));
+        assert!(!inputs
+            .iter()
+            .any(|input| matches!(input, Input::Boundary { .. })));
+
+        // More power must always reach further.
+        let power = analysis
+            .sensitivities
+            .iter()
+            .find(|sensitivity| sensitivity.input == Input::Parameter(Parameter::TxPower))
+            .unwrap();
+        assert!(power.distance_change_km.unwrap() > 0.0);
+    }
+
+    #[test]
+    fn analyse_line_with_ground_sea_boundary() {
+        let segments = [
+            LineSegment::new(Terrain::Ground.medium(), 100.0),
+            LineSegment::new(Terrain::Sea.medium(), 1000.0),
+        ];
+        let line = Line::with_segments(0.0, segments);
+        let analysis = analyse_line(43.2, &line, STEPS).unwrap();
+        let boundary = analysis
+            .sensitivities
+            .iter()
+            .find(|sensitivity| matches!(sensitivity.input, Input::Boundary { index: 0, .. }))
+            .unwrap();
+        // Moving the coast further away means more of the lossy land, so less coverage.
+        assert!(boundary.distance_change_km.unwrap() < 0.0);
+    }
+
+    #[test]
+    fn analyse_line_with_invalid_steps() {
+        let segment = LineSegment::new(Terrain::Ground.medium(), 500.0);
+        let line = Line::with_segments(0.0, [segment]);
+        let steps = Steps {
+            relative: 1.5,
+            boundary_km: 1.0,
+        };
+        assert!(analyse_line(43.2, &line, steps).is_err());
+    }
+}
@@ -0,0 +1,450 @@
+//! A local HTTP server answering JSON queries about a single path.
+//!
+//! Every endpoint takes a `POST` with a JSON object that has the `path` in the path notation,
+//! an optional `angle` that is echoed back and an optional `scenario` of parameter keys like `tx-power` to values:
+//! - `/field-strength` also takes `distance_km` and returns the Millington field strength there.
+//! - `/profile` also takes an optional `step_km` and returns the field strength every step along the path.
+//! - `/coverage` also takes `min_field_strengths` and returns the maximum distance for each of them.
+//!
+//! `GET /health` answers with `{"status": "ok"}`, even while the server is busy. Errors are answered with `{"error": "..."}`.
+
+use crate::coverage::LineCoverage;
+use crate::find_max_distance::calc_millington_field_strength_for_line_at_km;
+use crate::lfmf;
+use crate::propagation_model::Lfmf;
+use crate::scenario::{Parameter, Scenario};
+use crate::terrain::{notation, Line};
+use anyhow::{anyhow, Context, Result};
+use rayon::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tiny_http::{Header, Method, Request, Response};
+
+/// Bounds on the work the server accepts.
+#[derive(Debug, Copy, Clone)]
+pub struct Limits {
+    /// Requests being calculated at the same time, beyond which requests other than `/health` are answered with 503.
+    pub max_concurrent_requests: usize,
+    pub max_body_bytes: usize,
+    /// Samples of a single profile.
+    pub max_samples: usize,
+    /// Minimum field strengths of a single coverage request.
+    pub max_field_strengths: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_concurrent_requests: 4,
+            max_body_bytes: 64 * 1024,
+            max_samples: 100_000,
+            max_field_strengths: 100,
+        }
+    }
+}
+
+pub struct Server {
+    http: tiny_http::Server,
+    pool: rayon::ThreadPool,
+    limits: Limits,
+}
+
+impl Server {
+    /// Listens on `address` and calculates on a pool of `threads` threads, or one per CPU if it's 0.
+    pub fn bind(address: impl ToSocketAddrs, threads: usize, limits: Limits) -> Result<Self> {
+        let http = tiny_http::Server::http(address)
+            .map_err(|error| anyhow!("Could not start the server: {error}"))?;
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .context("Could not start the thread pool.")?;
+        Ok(Self { http, pool, limits })
+    }
+
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.http.server_addr().to_ip()
+    }
+
+    /// Answers requests until the process ends.
+    pub fn run(self) {
+        serve(self.http, State::new(self.pool, self.limits));
+    }
+}
+
+fn serve(http: tiny_http::Server, state: State) {
+    let state = Arc::new(state);
+    for request in http.incoming_requests() {
+        // The health check calculates nothing, so it's answered even when the server is busy.
+        let limited = request.url().split('?').next() != Some("/health");
+        if limited
+            && state.in_flight.fetch_add(1, Ordering::SeqCst)
+                >= state.limits.max_concurrent_requests
+        {
+            state.in_flight.fetch_sub(1, Ordering::SeqCst);
+            let error = ApiError::new(503, "Too many requests are being calculated, retry later.");
+            respond(request, Err(error));
+            continue;
+        }
+        let state = Arc::clone(&state);
+        std::thread::spawn(move || {
+            let mut request = request;
+            let result = state.handle(&mut request);
+            respond(request, result);
+            if limited {
+                state.in_flight.fetch_sub(1, Ordering::SeqCst);
+            }
+        });
+    }
+}
+
+struct State {
+    pool: rayon::ThreadPool,
+    limits: Limits,
+    in_flight: AtomicUsize,
+    /// Keeps `GET /hold` requests busy until the test waits on it twice, once to see them arrive and once to let them go.
+    #[cfg(test)]
+    hold: Option<Arc<std::sync::Barrier>>,
+}
+
+impl State {
+    fn new(pool: rayon::ThreadPool, limits: Limits) -> Self {
+        Self {
+            pool,
+            limits,
+            in_flight: AtomicUsize::new(0),
+            #[cfg(test)]
+            hold: None,
+        }
+    }
+
+    fn handle(&self, request: &mut Request) -> Result<String, ApiError> {
+        let url = request.url().split('?').next().unwrap_or_default();
+        let method = request.method().clone();
+        match (url, method) {
+            ("/health", Method::Get) => to_json(&serde_json::json!({ "status": "ok" })),
+            #[cfg(test)]
+            ("/hold", Method::Get) => {
+                let hold = self
+                    .hold
+                    .as_ref()
+                    .expect("Only held servers are asked to hold.");
+                hold.wait();
+                hold.wait();
+                to_json(&serde_json::json!({ "status": "released" }))
+            }
+            ("/field-strength", Method::Post) => {
+                let query = self.read_json(request)?;
+                self.pool.install(|| self.field_strength(query))
+            }
+            ("/profile", Method::Post) => {
+                let query = self.read_json(request)?;
+                self.pool.install(|| self.profile(query))
+            }
+            ("/coverage", Method::Post) => {
+                let query = self.read_json(request)?;
+                self.pool.install(|| self.coverage(query))
+            }
+            ("/health" | "/field-strength" | "/profile" | "/coverage", method) => Err(
+                ApiError::new(405, format!("{method} isn't allowed for `{url}`.")),
+            ),
+            _ => Err(ApiError::new(404, format!("There is no endpoint `{url}`."))),
+        }
+    }
+
+    fn read_json<T: DeserializeOwned>(&self, request: &mut Request) -> Result<T, ApiError> {
+        let max = self.limits.max_body_bytes;
+        let too_large = || ApiError::new(413, format!("The body is larger than {max} bytes."));
+        if request.body_length().is_some_and(|length| length > max) {
+            return Err(too_large());
+        }
+        let mut body = Vec::new();
+        request
+            .as_reader()
+            .take(max as u64 + 1)
+            .read_to_end(&mut body)
+            .map_err(|error| ApiError::new(400, format!("Could not read the body: {error}")))?;
+        if body.len() > max {
+            return Err(too_large());
+        }
+        serde_json::from_slice(&body)
+            .map_err(|error| ApiError::new(400, format!("Invalid request: {error}")))
+    }
+
+    fn field_strength(&self, query: FieldStrengthQuery) -> Result<String, ApiError> {
+        let line = parse_line(&query.path, query.angle, &query.scenario)?;
+        let distance = query.distance_km;
+        if !(0.0 < distance && distance <= line.max_distance()) {
+            return Err(ApiError::new(
+                400,
+                format!(
+                    "`distance_km` must be within the path of {} km but it was {distance}.",
+                    line.max_distance()
+                ),
+            ));
+        }
+        let millington = calc_millington_field_strength_for_line_at_km(&Lfmf, &line, distance)
+            .map_err(ApiError::internal)?;
+        let transmitter = line.transmitter();
+        to_json(&FieldStrengthResponse {
+            angle: line.angle(),
+            distance_km: distance,
+            field_strength: millington.field_strength,
+            forward: millington.forward,
+            reverse: millington.reverse,
+            received_power_dbm: lfmf::received_power_dbm(
+                millington.field_strength,
+                transmitter.frequency_mhz,
+            ),
+            basic_transmission_loss_db: lfmf::basic_transmission_loss_db(
+                millington.field_strength,
+                transmitter.frequency_mhz,
+                transmitter.power_w,
+            ),
+        })
+    }
+
+    fn profile(&self, query: ProfileQuery) -> Result<String, ApiError> {
+        let line = parse_line(&query.path, query.angle, &query.scenario)?;
+        let step = query.step_km;
+        if !(step.is_finite() && 0.0 < step) {
+            return Err(ApiError::new(
+                400,
+                format!("`step_km` must be positive but it was {step}."),
+            ));
+        }
+        let samples = (line.max_distance() / step).floor() as usize;
+        if samples > self.limits.max_samples {
+            return Err(ApiError::new(
+                400,
+                format!(
+                    "The profile would have {samples} samples, more than the limit of {}. Use a larger `step_km`.",
+                    self.limits.max_samples
+                ),
+            ));
+        }
+        let samples = (1..=samples)
+            .into_par_iter()
+            .map(|sample| {
+                let distance_km = step * sample as f64;
+                calc_millington_field_strength_for_line_at_km(&Lfmf, &line, distance_km).map(
+                    |millington| Sample {
+                        distance_km,
+                        field_strength: millington.field_strength,
+                        forward: millington.forward,
+                        reverse: millington.reverse,
+                    },
+                )
+            })
+            .collect::<Result<Vec<_>>>()
+            .map_err(ApiError::internal)?;
+        to_json(&ProfileResponse {
+            angle: line.angle(),
+            samples,
+        })
+    }
+
+    fn coverage(&self, query: CoverageQuery) -> Result<String, ApiError> {
+        let line = parse_line(&query.path, query.angle, &query.scenario)?;
+        let min_es = &query.min_field_strengths;
+        if min_es.is_empty() || min_es.len() > self.limits.max_field_strengths {
+            return Err(ApiError::new(
+                400,
+                format!(
+                    "`min_field_strengths` must have 1 to {} values but it had {}.",
+                    self.limits.max_field_strengths,
+                    min_es.len()
+                ),
+            ));
+        }
+        if let Some(min_e) = min_es.iter().find(|min_e| !min_e.is_finite()) {
+            return Err(ApiError::new(
+                400,
+                format!("`{min_e}` isn't a field strength."),
+            ));
+        }
+        // A minimum that isn't reached within the path gives `null` rather than failing the others.
+        let coverage = LineCoverage::calculate(line, min_es).map_err(ApiError::internal)?;
+        to_json(&CoverageResponse {
+            angle: coverage.line.angle(),
+            min_field_strengths: query.min_field_strengths,
+            max_distances_km: coverage.max_distances_km,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FieldStrengthQuery {
+    path: String,
+    #[serde(default)]
+    angle: f64,
+    #[serde(default)]
+    scenario: BTreeMap<String, f64>,
+    distance_km: f64,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProfileQuery {
+    path: String,
+    #[serde(default)]
+    angle: f64,
+    #[serde(default)]
+    scenario: BTreeMap<String, f64>,
+    #[serde(default = "default_step_km")]
+    step_km: f64,
+}
+
+fn default_step_km() -> f64 {
+    0.5
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CoverageQuery {
+    path: String,
+    #[serde(default)]
+    angle: f64,
+    #[serde(default)]
+    scenario: BTreeMap<String, f64>,
+    min_field_strengths: Vec<f64>,
+}
+
+#[derive(Serialize)]
+struct FieldStrengthResponse {
+    angle: f64,
+    distance_km: f64,
+    field_strength: f64,
+    forward: f64,
+    reverse: f64,
+    received_power_dbm: f64,
+    basic_transmission_loss_db: f64,
+}
+
+#[derive(Serialize)]
+struct Sample {
+    distance_km: f64,
+    field_strength: f64,
+    forward: f64,
+    reverse: f64,
+}
+
+#[derive(Serialize)]
+struct ProfileResponse {
+    angle: f64,
+    samples: Vec<Sample>,
+}
+
+#[derive(Serialize)]
+struct CoverageResponse {
+    angle: f64,
+    min_field_strengths: Vec<f64>,
+    max_distances_km: Vec<Option<f64>>,
+}
+
+/// Parses the path, applies the scenario to it and checks that LFMF accepts the result.
+fn parse_line(path: &str, angle: f64, scenario: &BTreeMap<String, f64>) -> Result<Line, ApiError> {
+    let bad_request = |error: anyhow::Error| ApiError::new(400, format!("{error:#}"));
+    let mut overrides = Scenario::default();
+    for (key, value) in scenario {
+        let parameter: Parameter = key.parse().map_err(|error| ApiError::new(400, error))?;
+        overrides.set(parameter, *value);
+    }
+    let mut line = notation::parse(path).map_err(bad_request)?;
+    line.set_angle(angle);
+    let line = overrides.apply(&line);
+    line.validate().map_err(bad_request)?;
+    Ok(line)
+}
+
+fn to_json(value: &impl Serialize) -> Result<String, ApiError> {
+    serde_json::to_string(value).map_err(|error| ApiError::internal(error.into()))
+}
+
+struct ApiError {
+    status: u16,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: u16, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+
+    fn internal(error: anyhow::Error) -> Self {
+        Self::new(500, format!("{error:#}"))
+    }
+}
+
+fn respond(request: Request, result: Result<String, ApiError>) {
+    let (status, body) = match result {
+        Ok(body) => (200, body),
+        Err(error) => (
+            error.status,
+            serde_json::json!({ "error": error.message }).to_string(),
+        ),
+    };
+    let content_type =
+        Header::from_bytes("Content-Type", "application/json").expect("The header is valid.");
+    let response = Response::from_string(body)
+        .with_status_code(status)
+        .with_header(content_type);
+    // The client may have gone away, there's no one left to tell.
+    let _ = request.respond(response);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::net::TcpStream;
+    use std::sync::Barrier;
+
+    /// Sends a request without a body and returns the status code of the response.
+    fn status(address: SocketAddr, method: &str, url: &str) -> u16 {
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(
+            stream,
+            "{method} {url} HTTP/1.0\r\nHost: {address}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response[9..12].parse().unwrap()
+    }
+
+    #[test]
+    fn requests_beyond_the_limit_are_turned_away() {
+        let limits = Limits {
+            max_concurrent_requests: 1,
+            ..Limits::default()
+        };
+        let server = Server::bind("127.0.0.1:0", 1, limits).unwrap();
+        let address = server.local_addr().unwrap();
+        let hold = Arc::new(Barrier::new(2));
+        let state = State {
+            hold: Some(Arc::clone(&hold)),
+            ..State::new(server.pool, server.limits)
+        };
+        let http = server.http;
+        std::thread::spawn(move || serve(http, state));
+
+        let held = std::thread::spawn(move || status(address, "GET", "/hold"));
+        // The held request has its slot once it arrives.
+        hold.wait();
+        assert_eq!(status(address, "POST", "/coverage"), 503);
+        assert_eq!(status(address, "GET", "/health"), 200);
+        hold.wait();
+        assert_eq!(held.join().unwrap(), 200);
+        // The slot is given back after the response, so retry until it is.
+        while status(address, "POST", "/coverage") == 503 {}
+    }
+}
@@ -0,0 +1,361 @@
+//! The night-time skywave of ITU-R P.1147, which limits MF coverage at night by interfering with the groundwave
+//! of the same transmitter, where LFMF only gives the groundwave.
+//!
+//! The transmitter is taken to be the short monopole of LFMF without any directivity, and the sea gain
+//! and the solar activity correction of Region 2 are left out.
+
+use crate::find_max_distance::find_max_distances_for_line_with;
+use crate::terrain::{Line, Transmitter};
+use anyhow::{ensure, Context, Result};
+use geographiclib_rs::{DirectGeodesic, Geodesic, InverseGeodesic};
+
+/// The northern geomagnetic pole of P.1147 in degrees.
+const POLE_LATITUDE: f64 = 78.5;
+const POLE_LONGITUDE: f64 = -69.0;
+/// Twice the height of the reflecting layer in km, which the slant distance adds to the length of the path.
+const SLANT_HEIGHT_KM: f64 = 200.0;
+/// The furthest from the geomagnetic equator in degrees that the loss factor is calculated at.
+const MAX_GEOMAGNETIC_LATITUDE: f64 = 60.0;
+
+/// The hourly loss in dB by hours after sunset, read off Figure 1 of P.1147. It's nil six hours after sunset.
+const LOSS_AFTER_SUNSET: [(f64, f64); 10] = [
+    (-1.0, 20.0),
+    (-0.5, 15.0),
+    (0.0, 10.5),
+    (0.5, 7.0),
+    (1.0, 4.5),
+    (2.0, 2.0),
+    (3.0, 1.0),
+    (4.0, 0.5),
+    (5.0, 0.2),
+    (6.0, 0.0),
+];
+/// The hourly loss in dB by hours before sunrise, read off Figure 1 of P.1147.
+const LOSS_BEFORE_SUNRISE: [(f64, f64); 8] = [
+    (-1.0, 19.0),
+    (-0.5, 14.0),
+    (0.0, 9.5),
+    (0.5, 6.5),
+    (1.0, 4.0),
+    (2.0, 1.5),
+    (3.0, 0.5),
+    (4.0, 0.0),
+];
+
+/// The regions of the ITU Radio Regulations, which have different constants in P.1147.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum Region {
+    /// Europe and Africa.
+    #[default]
+    One,
+    /// The Americas.
+    Two,
+    /// Asia and Oceania.
+    Three,
+}
+
+/// The time of night the skywave is calculated at, in hours.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum NightTime {
+    /// Negative before sunset.
+    AfterSunset(f64),
+    /// Negative after sunrise.
+    BeforeSunrise(f64),
+}
+
+impl NightTime {
+    /// The hourly loss relative to six hours after sunset in dB.
+    fn loss_db(self) -> Result<f64> {
+        let (hours, table) = match self {
+            Self::AfterSunset(hours) => (hours, &LOSS_AFTER_SUNSET[..]),
+            Self::BeforeSunrise(hours) => (hours, &LOSS_BEFORE_SUNRISE[..]),
+        };
+        ensure!(
+            hours >= table[0].0,
+            "{self:?} is in the day, more than an hour from the night."
+        );
+        let next = table.partition_point(|&(table_hours, _)| table_hours < hours);
+        let Some(&(hours_1, loss_1)) = table.get(next) else {
+            return Ok(0.0);
+        };
+        let (hours_0, loss_0) = table[next.max(1) - 1];
+        if hours_1 == hours_0 {
+            return Ok(loss_1);
+        }
+        Ok(loss_0 + (loss_1 - loss_0) * (hours - hours_0) / (hours_1 - hours_0))
+    }
+}
+
+/// The skywave of P.1147 at a time of night, exceeded for a percentage of the nights of the year.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Skywave {
+    pub region: Region,
+    pub time: NightTime,
+    /// The percentage of the nights the field strength is exceeded for, 50 for the annual median.
+    pub time_percentage: f64,
+}
+
+impl Default for Skywave {
+    /// The annual median six hours after sunset in Region 1.
+    fn default() -> Self {
+        Self {
+            region: Region::One,
+            time: NightTime::AfterSunset(6.0),
+            time_percentage: 50.0,
+        }
+    }
+}
+
+impl Skywave {
+    /// The field strength in dB(uV)/m of the skywave of `transmitter` at `transmitter_location`
+    /// at `receiver_location`, both in degrees of latitude and longitude.
+    /// P.1147 is meant for paths of 50 to 12000 km.
+    pub fn field_strength(
+        &self,
+        transmitter: &Transmitter,
+        transmitter_location: (f64, f64),
+        receiver_location: (f64, f64),
+    ) -> Result<f64> {
+        ensure!(
+            (1.0..=99.0).contains(&self.time_percentage),
+            "The time percentage must be from 1 to 99 but it was {}.",
+            self.time_percentage
+        );
+        let geodesic = Geodesic::wgs84();
+        let ((latitude_1, longitude_1), (latitude_2, longitude_2)) =
+            (transmitter_location, receiver_location);
+        let (distance_m, azimuth_1, azimuth_2, _): (f64, f64, f64, f64) =
+            geodesic.inverse(latitude_1, longitude_1, latitude_2, longitude_2);
+        let (midpoint_latitude, midpoint_longitude): (f64, f64) =
+            geodesic.direct(latitude_1, longitude_1, azimuth_1, distance_m / 2.0);
+        let midpoint_geomagnetic_latitude =
+            geomagnetic_latitude(midpoint_latitude, midpoint_longitude);
+
+        let cymomotive_force_db = 10.0 * (transmitter.power_w / 1000.0).log10();
+        let a = match self.region {
+            Region::Two => 110.0,
+            Region::One | Region::Three => 107.0,
+        };
+        let slant_distance_km = ((distance_m / 1000.0).powi(2) + SLANT_HEIGHT_KM.powi(2)).sqrt();
+        let loss_factor = loss_factor(
+            transmitter.frequency_mhz * 1000.0,
+            midpoint_geomagnetic_latitude,
+        );
+        let coupling_loss_db = polarization_coupling_loss_db(latitude_1, longitude_1, azimuth_1)
+            + polarization_coupling_loss_db(latitude_2, longitude_2, azimuth_2);
+
+        let median = cymomotive_force_db + a
+            - coupling_loss_db
+            - 20.0 * slant_distance_km.log10()
+            - loss_factor * slant_distance_km / 1000.0
+            - self.time.loss_db()?;
+        Ok(median + self.deviation_db(midpoint_geomagnetic_latitude))
+    }
+
+    /// The field strength in dB(uV)/m of the skywave at `distance` km along `line`,
+    /// cast from the transmitter at `latitude`, `longitude` at its angle.
+    pub fn field_strength_along(
+        &self,
+        line: &Line,
+        latitude: f64,
+        longitude: f64,
+        distance: f64,
+    ) -> Result<f64> {
+        let receiver_location: (f64, f64) =
+            Geodesic::wgs84().direct(latitude, longitude, line.angle(), distance * 1000.0);
+        self.field_strength(
+            &line.transmitter(),
+            (latitude, longitude),
+            receiver_location,
+        )
+    }
+
+    /// The furthest distance in km along `line` where its groundwave from `ground_wave_at_km`
+    /// is still `ratio_db` over the skywave of its transmitter at `latitude`, `longitude`,
+    /// `None` if it stays over it up to the end of the line.
+    pub fn find_night_distance_for_line_with(
+        &self,
+        ratio_db: f64,
+        line: &Line,
+        latitude: f64,
+        longitude: f64,
+        mut ground_wave_at_km: impl FnMut(f64) -> Result<f64>,
+    ) -> Result<Option<f64>> {
+        let ratio_at_km = |distance| -> Result<f64> {
+            Ok(ground_wave_at_km(distance)?
+                - self.field_strength_along(line, latitude, longitude, distance)?)
+        };
+        let distances = find_max_distances_for_line_with(&[ratio_db], line, ratio_at_km)
+            .with_context(|| {
+                format!(
+                    "Could not find where the groundwave falls to {ratio_db} dB over the skywave."
+                )
+            })?;
+        Ok(distances[0])
+    }
+
+    /// How much the field strength exceeded for the time percentage is over the median in dB.
+    /// P.1147 gives it for 10 % by the geomagnetic latitude of the midpoint, and it's taken to be normally distributed.
+    fn deviation_db(&self, midpoint_geomagnetic_latitude: f64) -> f64 {
+        let latitude = midpoint_geomagnetic_latitude.abs();
+        let deviation_at_10_percent = if latitude <= 40.0 {
+            6.0
+        } else if latitude <= 50.0 {
+            0.2 * latitude - 2.0
+        } else {
+            8.0
+        };
+        deviation_at_10_percent * normal_quantile(self.time_percentage / 100.0)
+            / normal_quantile(0.1)
+    }
+}
+
+/// The geomagnetic latitude in degrees of a point, as in P.1147.
+pub fn geomagnetic_latitude(latitude: f64, longitude: f64) -> f64 {
+    let (latitude, pole_latitude) = (latitude.to_radians(), POLE_LATITUDE.to_radians());
+    let longitude = (longitude - POLE_LONGITUDE).to_radians();
+    (latitude.sin() * pole_latitude.sin() + latitude.cos() * pole_latitude.cos() * longitude.cos())
+        .asin()
+        .to_degrees()
+}
+
+/// The basic loss factor k of P.1147 in dB per 1000 km at `frequency_khz` for a path
+/// with its midpoint at `midpoint_geomagnetic_latitude` in degrees, the same in every region.
+fn loss_factor(frequency_khz: f64, midpoint_geomagnetic_latitude: f64) -> f64 {
+    let latitude = midpoint_geomagnetic_latitude
+        .abs()
+        .min(MAX_GEOMAGNETIC_LATITUDE);
+    3.2 + 0.19 * frequency_khz.powf(0.4) * (latitude + 3.0).to_radians().tan().powi(2)
+}
+
+/// The polarization coupling loss in dB at an end of a path leaving at `azimuth`, which is only there
+/// where the magnetic dip is at most 45°. The dip and the magnetic north are those of the geomagnetic dipole.
+fn polarization_coupling_loss_db(latitude: f64, longitude: f64, azimuth: f64) -> f64 {
+    let dip = (2.0 * geomagnetic_latitude(latitude, longitude).to_radians().tan())
+        .atan()
+        .to_degrees();
+    if dip.abs() > 45.0 {
+        return 0.0;
+    }
+    let (_, north, _, _): (f64, f64, f64, f64) =
+        Geodesic::wgs84().inverse(latitude, longitude, POLE_LATITUDE, POLE_LONGITUDE);
+    // The angle between the path and the magnetic east-west.
+    let theta = (90.0 - (azimuth - north).rem_euclid(180.0)).abs();
+    180.0 / (36.0 + theta * theta + dip * dip).sqrt() - 2.0
+}
+
+/// The standard normal deviate exceeded with `probability`, by the approximation 26.2.23 of Abramowitz and Stegun.
+fn normal_quantile(probability: f64) -> f64 {
+    const C: [f64; 3] = [2.515517, 0.802853, 0.010328];
+    const D: [f64; 3] = [1.432788, 0.189269, 0.001308];
+    let tail = probability.min(1.0 - probability);
+    let t = (-2.0 * tail.ln()).sqrt();
+    let deviate =
+        t - (C[0] + C[1] * t + C[2] * t * t) / (1.0 + D[0] * t + D[1] * t * t + D[2] * t * t * t);
+    if probability <= 0.5 {
+        deviate
+    } else {
+        -deviate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::find_max_distance::calc_field_strength_for_line_at_km;
+    use crate::propagation_model::Lfmf;
+    use crate::terrain::defaults::{Terrain, TRANSMITTER};
+
+    const ATHENS: (f64, f64) = (37.98, 23.73);
+    const TRIPOLI: (f64, f64) = (32.9, 13.2);
+
+    #[test]
+    fn the_skywave_follows_p1147() {
+        assert!((geomagnetic_latitude(ATHENS.0, ATHENS.1) - 36.55).abs() < 0.01);
+        assert!((geomagnetic_latitude(POLE_LATITUDE, POLE_LONGITUDE) - 90.0).abs() < 1e-9);
+
+        let median = Skywave::default();
+        let field_strength = median
+            .field_strength(&TRANSMITTER, ATHENS, TRIPOLI)
+            .unwrap();
+        // Worked by hand for 10 kW over the 1108.78 km from Athens to Tripoli: V = 10 dB and A = 107 dB,
+        // p = 1126.68 km and k = 3.2 + 0.19 * 1000^0.4 * tan²(35.27° + 3°) = 5.074 at the midpoint,
+        // with no coupling loss since the dip is over 45° at both ends.
+        assert!((field_strength - 50.2468).abs() < 1e-3, "{field_strength}");
+        let closer = median.field_strength(&TRANSMITTER, ATHENS, (39.0, 22.0));
+        assert!(closer.unwrap() > field_strength);
+
+        let field_strength_at = |skywave: Skywave| {
+            skywave
+                .field_strength(&TRANSMITTER, ATHENS, TRIPOLI)
+                .unwrap()
+                - field_strength
+        };
+        let ten_percent = Skywave {
+            time_percentage: 10.0,
+            ..median
+        };
+        assert!((field_strength_at(ten_percent) - 6.0).abs() < 1e-6);
+        let sunset = Skywave {
+            time: NightTime::AfterSunset(0.0),
+            ..median
+        };
+        assert!((field_strength_at(sunset) + 10.5).abs() < 1e-9);
+        let before_sunrise = Skywave {
+            time: NightTime::BeforeSunrise(5.0),
+            ..median
+        };
+        assert_eq!(field_strength_at(before_sunrise), 0.0);
+        let day = Skywave {
+            time: NightTime::AfterSunset(-3.0),
+            ..median
+        };
+        assert!(day.field_strength(&TRANSMITTER, ATHENS, TRIPOLI).is_err());
+    }
+
+    #[test]
+    fn the_loss_factor_is_the_same_in_every_region() {
+        assert!((loss_factor(1000.0, 0.0) - 3.2083).abs() < 1e-4);
+        assert!((loss_factor(1000.0, 40.0) - 5.8186).abs() < 1e-4);
+        assert!((loss_factor(540.0, -25.0) - 3.8654).abs() < 1e-4);
+        // Beyond 60° it's taken at 60°.
+        assert_eq!(loss_factor(1000.0, 70.0), loss_factor(1000.0, 60.0));
+
+        let americas = Skywave {
+            region: Region::Two,
+            ..Skywave::default()
+        };
+        let difference = americas
+            .field_strength(&TRANSMITTER, ATHENS, TRIPOLI)
+            .unwrap()
+            - Skywave::default()
+                .field_strength(&TRANSMITTER, ATHENS, TRIPOLI)
+                .unwrap();
+        // Only A differs.
+        assert!((difference - 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn the_night_distance_keeps_the_ratio() {
+        let line = Line::builder(270.0)
+            .segment(Terrain::Ground, 300.0)
+            .build()
+            .unwrap();
+        let skywave = Skywave::default();
+        let (latitude, longitude) = ATHENS;
+        let night_distance = skywave
+            .find_night_distance_for_line_with(8.0, &line, latitude, longitude, |distance| {
+                calc_field_strength_for_line_at_km(&Lfmf, &line, distance)
+            })
+            .unwrap()
+            .unwrap();
+        let ratio = calc_field_strength_for_line_at_km(&Lfmf, &line, night_distance).unwrap()
+            - skywave
+                .field_strength_along(&line, latitude, longitude, night_distance)
+                .unwrap();
+        assert!(
+            (ratio - 8.0).abs() < 0.01,
+            "{ratio} dB at {night_distance} km"
+        );
+    }
+}
@@ -1,40 +1,89 @@
 use crate::lfmf::LFMF_Parameters;
+use crate::propagation_model::HomogeneousPath;
+use crate::terrain::{Medium, Polarization, Receiver, Transmitter};
 
-const GROUND_PARAMETERS: LFMF_Parameters = LFMF_Parameters {
-    h_tx__meter: 10.0,
-    h_rx__meter: 10.0,
-    f__mhz: 1.0,
-    P_tx__watt: 10000.0,
-    N_s: 300.0,
-    d__km: 300.0,
+/// The transmitter of our project.
+pub const TRANSMITTER: Transmitter = Transmitter {
+    height_m: 10.0,
+    power_w: 10000.0,
+    frequency_mhz: 1.0,
+    polarization: Polarization::Vertical,
+};
+
+/// The receiver of our project.
+pub const RECEIVER: Receiver = Receiver { height_m: 10.0 };
+
+/// Surface refractivity in N-units.
+pub const SURFACE_REFRACTIVITY: f64 = 300.0;
+
+const GROUND_MEDIUM: Medium = Medium {
     epsilon: 22.0,
     sigma: 0.003,
-    pol: 1,
 };
 
-const SEA_PARAMETERS: LFMF_Parameters = LFMF_Parameters {
-    h_tx__meter: 10.0,
-    h_rx__meter: 10.0,
-    f__mhz: 1.0,
-    P_tx__watt: 10000.0,
-    N_s: 300.0,
-    d__km: 300.0,
+const SEA_MEDIUM: Medium = Medium {
     epsilon: 70.0,
     sigma: 5.0,
-    pol: 1,
 };
 
-#[derive(Debug, Copy, Clone)]
+/// The path distance of [`Terrain::parameters`].
+const DISTANCE__KM: f64 = 300.0;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Terrain {
     Ground,
     Sea,
 }
 
 impl Terrain {
+    pub const fn medium(&self) -> Medium {
+        match self {
+            Self::Ground => GROUND_MEDIUM,
+            Self::Sea => SEA_MEDIUM,
+        }
+    }
+
+    /// The parameters of our project for a path of only this terrain.
     pub const fn parameters(&self) -> LFMF_Parameters {
+        LFMF_Parameters::assemble(
+            &TRANSMITTER,
+            &RECEIVER,
+            SURFACE_REFRACTIVITY,
+            &self.medium(),
+            DISTANCE__KM,
+        )
+    }
+
+    /// The path of our project over only this terrain.
+    pub const fn path(&self) -> HomogeneousPath {
+        HomogeneousPath {
+            transmitter: TRANSMITTER,
+            receiver: RECEIVER,
+            surface_refractivity: SURFACE_REFRACTIVITY,
+            medium: self.medium(),
+        }
+    }
+
+    /// Finds the terrain whose medium matches the one given, if any.
+    pub fn from_medium(medium: &Medium) -> Option<Self> {
+        [Self::Ground, Self::Sea]
+            .into_iter()
+            .find(|terrain| terrain.medium() == *medium)
+    }
+
+    /// The letter of the terrain in the short form of the path notation.
+    pub const fn symbol(&self) -> &'static str {
+        match self {
+            Self::Ground => "G",
+            Self::Sea => "S",
+        }
+    }
+
+    pub const fn name(&self) -> &'static str {
         match self {
-            Self::Ground => GROUND_PARAMETERS,
-            Self::Sea => SEA_PARAMETERS,
+            Self::Ground => "Land",
+            Self::Sea => "Sea",
         }
     }
 }
@@ -1,40 +1,130 @@
+use crate::lfmf::LFMF_Parameters;
+use crate::propagation_model::HomogeneousPath;
+use crate::terrain::defaults::{self, Terrain};
 use crate::terrain::line_segment::LineSegment;
+use crate::terrain::{Medium, Receiver, Transmitter};
+use anyhow::{ensure, Context, Result};
 
-#[derive(Debug, Clone)]
+/// A path from the transmitter at an angle, made of segments of homogeneous ground in order from the transmitter.
+/// The stations and the atmosphere are the same along the whole line.
+/// Outside of this crate it is made with [`Line::builder`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Line {
     angle: f64,
+    transmitter: Transmitter,
+    receiver: Receiver,
+    /// Surface refractivity in N-units.
+    surface_refractivity: f64,
     segments: Vec<LineSegment>,
 }
 
 impl Line {
-    pub fn new(angle: f64) -> Self {
-        Self {
-            angle,
-            segments: Vec::new(),
-        }
+    /// Makes an empty line with the stations of our project.
+    pub(crate) fn new(angle: f64) -> Self {
+        Self::with_segments(angle, [])
     }
-    pub fn with_segments<I: IntoIterator<Item = LineSegment>>(
+    pub(crate) fn with_segments<I: IntoIterator<Item = LineSegment>>(
         angle: f64,
         segments_iter: I,
     ) -> Self {
         Self {
             angle,
+            transmitter: defaults::TRANSMITTER,
+            receiver: defaults::RECEIVER,
+            surface_refractivity: defaults::SURFACE_REFRACTIVITY,
             segments: Vec::from_iter(segments_iter),
         }
     }
 
+    /// Starts an empty line at `angle` degrees.
+    pub fn builder(angle: f64) -> LineBuilder {
+        LineBuilder {
+            line: Self::new(angle),
+        }
+    }
+
     pub fn angle(&self) -> f64 {
         self.angle
     }
+    pub(crate) fn set_angle(&mut self, angle: f64) {
+        self.angle = angle;
+    }
+
+    pub fn transmitter(&self) -> Transmitter {
+        self.transmitter
+    }
+    pub(crate) fn transmitter_mut(&mut self) -> &mut Transmitter {
+        &mut self.transmitter
+    }
+
+    pub fn receiver(&self) -> Receiver {
+        self.receiver
+    }
+    pub(crate) fn receiver_mut(&mut self) -> &mut Receiver {
+        &mut self.receiver
+    }
+
+    pub fn surface_refractivity(&self) -> f64 {
+        self.surface_refractivity
+    }
+    pub(crate) fn surface_refractivity_mut(&mut self) -> &mut f64 {
+        &mut self.surface_refractivity
+    }
 
     pub fn segments(&self) -> impl Iterator<Item = &LineSegment> {
         self.segments.iter()
     }
+    pub(crate) fn segments_mut(&mut self) -> &mut [LineSegment] {
+        &mut self.segments
+    }
+
+    /// The path over `medium` between the stations of the line.
+    pub fn homogeneous_path(&self, medium: Medium) -> HomogeneousPath {
+        HomogeneousPath {
+            transmitter: self.transmitter,
+            receiver: self.receiver,
+            surface_refractivity: self.surface_refractivity,
+            medium,
+        }
+    }
+
+    /// The parameters for LFMF of a path of `distance_km` over `medium` between the stations of the line.
+    pub fn lfmf_parameters(&self, medium: Medium, distance_km: f64) -> LFMF_Parameters {
+        self.homogeneous_path(medium).lfmf_parameters(distance_km)
+    }
 
-    pub fn add_segment(&mut self, segment: LineSegment) {
+    pub(crate) fn add_segment(&mut self, segment: LineSegment) {
         self.segments.push(segment);
     }
 
+    /// Checks that the line has segments of positive length and that LFMF accepts the parameters of every segment
+    /// for every distance up to the end of the line.
+    pub fn validate(&self) -> Result<()> {
+        ensure!(!self.is_empty(), "The line has no segments.");
+        for (i, segment) in self.segments().enumerate() {
+            let length_km = segment.length_km();
+            ensure!(
+                length_km.is_finite() && length_km > 0.0,
+                "Segment #{} must have a positive length but it was {length_km} km.",
+                i + 1
+            );
+            self.lfmf_parameters(segment.medium(), self.max_distance())
+                .validate()
+                .with_context(|| format!("Segment #{} has invalid parameters", i + 1))?;
+        }
+        Ok(())
+    }
+
+    /// Makes a copy of the line whose segment at `index` is replaced by one of `terrain` with the same length,
+    /// or `None` if there's no such segment.
+    pub fn with_segment_terrain(&self, index: usize, terrain: Terrain) -> Option<Line> {
+        let mut line = self.clone();
+        let segment = line.segments.get_mut(index)?;
+        *segment = LineSegment::new(terrain.medium(), segment.length_km());
+        Some(line)
+    }
+
     pub fn max_distance(&self) -> f64 {
         self.segments.iter().map(LineSegment::length_km).sum()
     }
@@ -65,3 +155,82 @@ impl Line {
         Some(segments)
     }
 }
+
+/// Builds a [`Line`] segment by segment, checking it once it is complete.
+#[derive(Debug, Clone)]
+pub struct LineBuilder {
+    line: Line,
+}
+
+impl LineBuilder {
+    /// Replaces the transmitter of our project.
+    pub fn transmitter(mut self, transmitter: Transmitter) -> Self {
+        self.line.transmitter = transmitter;
+        self
+    }
+
+    /// Replaces the receiver of our project.
+    pub fn receiver(mut self, receiver: Receiver) -> Self {
+        self.line.receiver = receiver;
+        self
+    }
+
+    /// Replaces the surface refractivity of our project, in N-units.
+    pub fn surface_refractivity(mut self, surface_refractivity: f64) -> Self {
+        self.line.surface_refractivity = surface_refractivity;
+        self
+    }
+
+    /// Adds a segment with the medium of our project for `terrain`.
+    pub fn segment(self, terrain: Terrain, length_km: f64) -> Self {
+        self.segment_with_medium(terrain.medium(), length_km)
+    }
+
+    /// Adds a segment with its own medium.
+    pub fn segment_with_medium(mut self, medium: Medium, length_km: f64) -> Self {
+        self.line.add_segment(LineSegment::new(medium, length_km));
+        self
+    }
+
+    /// Checks the line with [`Line::validate`].
+    pub fn build(self) -> Result<Line> {
+        self.line.validate()?;
+        Ok(self.line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builder_checks_segments() {
+        let line = Line::builder(10.0)
+            .segment(Terrain::Ground, 38.0)
+            .segment(Terrain::Sea, 61.0)
+            .build()
+            .unwrap();
+        assert_eq!(line.angle(), 10.0);
+        assert_eq!(line.max_distance(), 99.0);
+
+        assert!(Line::builder(0.0).build().is_err());
+        assert!(Line::builder(0.0)
+            .segment(Terrain::Ground, 0.0)
+            .build()
+            .is_err());
+        assert!(Line::builder(0.0)
+            .segment(Terrain::Ground, 6000.0)
+            .segment(Terrain::Sea, 6000.0)
+            .build()
+            .is_err());
+        let too_high = Transmitter {
+            height_m: 100.0,
+            ..Transmitter::default()
+        };
+        assert!(Line::builder(0.0)
+            .transmitter(too_high)
+            .segment(Terrain::Ground, 10.0)
+            .build()
+            .is_err());
+    }
+}
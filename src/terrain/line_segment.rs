@@ -1,27 +1,51 @@
-use crate::lfmf::LFMF_Parameters;
+use crate::terrain::defaults::Terrain;
+use crate::terrain::Medium;
 
-#[derive(Debug, Clone, Copy)]
+/// A stretch of homogeneous ground along a [`Line`](crate::terrain::Line).
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LineSegment {
-    /// In contained is the distance in km that the segment spans.
-    lfmf_parameters: LFMF_Parameters,
+    medium: Medium,
+    length_km: f64,
+    /// The terrain whose medium the segment was made with, `None` for a medium of neither.
+    /// The overrides of a scenario, the distributions of the Monte Carlo mode and the path notation select
+    /// and name segments by it, so it's kept when the medium is changed by them or by a conductivity atlas,
+    /// after which the medium alone no longer tells land from sea.
+    terrain: Option<Terrain>,
 }
 
 impl LineSegment {
-    pub fn new(lfmf_parameters: LFMF_Parameters) -> Self {
-        Self { lfmf_parameters }
+    pub(crate) fn new(medium: Medium, length_km: f64) -> Self {
+        Self {
+            medium,
+            length_km,
+            terrain: Terrain::from_medium(&medium),
+        }
     }
-    pub fn with_length(mut lfmf_parameters: LFMF_Parameters, length_km: f64) -> Self {
-        lfmf_parameters.d__km = length_km;
-        Self { lfmf_parameters }
+
+    /// A segment of `terrain` whose medium isn't the default one of our project.
+    pub(crate) fn with_terrain(terrain: Terrain, medium: Medium, length_km: f64) -> Self {
+        Self {
+            medium,
+            length_km,
+            terrain: Some(terrain),
+        }
     }
 
-    pub fn lfmf_parameters(&self) -> LFMF_Parameters {
-        self.lfmf_parameters
+    pub fn medium(&self) -> Medium {
+        self.medium
+    }
+    /// Changes the medium without forgetting the segment's terrain.
+    pub(crate) fn medium_mut(&mut self) -> &mut Medium {
+        &mut self.medium
+    }
+    pub fn terrain(&self) -> Option<Terrain> {
+        self.terrain
     }
     pub fn length_km(&self) -> f64 {
-        self.lfmf_parameters.d__km
+        self.length_km
     }
-    pub fn set_length_km(&mut self, distance_km: f64) {
-        self.lfmf_parameters.d__km = distance_km;
+    pub(crate) fn set_length_km(&mut self, distance_km: f64) {
+        self.length_km = distance_km;
     }
 }
@@ -0,0 +1,9 @@
+/// The electrical constants of the ground of a segment.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Medium {
+    /// Relative permittivity.
+    pub epsilon: f64,
+    /// Conductivity in S/m.
+    pub sigma: f64,
+}
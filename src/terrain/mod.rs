@@ -1,6 +1,11 @@
 pub mod defaults;
+pub mod notation;
 
 mod line;
-pub use line::Line;
+pub use line::{Line, LineBuilder};
 mod line_segment;
 pub use line_segment::LineSegment;
+mod medium;
+pub use medium::Medium;
+mod station;
+pub use station::{Polarization, Receiver, Transmitter};
@@ -0,0 +1,135 @@
+//! A compact text notation for describing a line on the command line.
+//!
+//! A line is written as its segments in order from the transmitter, separated by spaces or commas.
+//! Each segment is a terrain followed by its length in km, either in long form like `land:100 sea:50 land:200`
+//! or in short form like `G100,S50,G200`.
+//! The terrains are `land`/`ground` (`L`/`G`) and `sea` (`S`), in any case.
+
+use crate::terrain::defaults::Terrain;
+use crate::terrain::{Line, LineSegment};
+use anyhow::{bail, ensure, Context, Result};
+
+/// Parses a line with an angle of 0 from its notation.
+pub fn parse(notation: &str) -> Result<Line> {
+    let mut line = Line::new(0.0);
+    let tokens = notation
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|token| !token.is_empty());
+    for (i, token) in tokens.enumerate() {
+        let segment = parse_segment(token)
+            .with_context(|| format!("Could not parse segment #{} `{token}`", i + 1))?;
+        line.add_segment(segment);
+    }
+    ensure!(
+        !line.is_empty(),
+        "`{notation}` has no segments. Expected something like `land:100 sea:50` or `G100,S50`."
+    );
+    Ok(line)
+}
+
+fn parse_segment(token: &str) -> Result<LineSegment> {
+    let (terrain, length) = match token.split_once(':') {
+        Some((terrain, length)) => (terrain, length),
+        None => {
+            let length_start = token
+                .find(|c: char| !c.is_alphabetic())
+                .unwrap_or(token.len());
+            token.split_at(length_start)
+        }
+    };
+    let terrain = match terrain.to_lowercase().as_str() {
+        "land" | "ground" | "l" | "g" => Terrain::Ground,
+        "sea" | "s" => Terrain::Sea,
+        "" => bail!("It has no terrain. Expected e.g. `land:100` or `G100`."),
+        _ => {
+            bail!("`{terrain}` isn't a terrain. Expected `land`, `ground`, `sea`, `L`, `G` or `S`.")
+        }
+    };
+    ensure!(
+        !length.is_empty(),
+        "It has no length. Expected e.g. `{}:100` or `{}100`.",
+        terrain.name().to_lowercase(),
+        terrain.symbol()
+    );
+    let length_km: f64 = length
+        .parse()
+        .with_context(|| format!("Could not parse `{length}` to a float as a length in km."))?;
+    ensure!(
+        length_km.is_finite() && length_km > 0.0,
+        "Length must be positive but it was {length_km} km."
+    );
+    Ok(LineSegment::new(terrain.medium(), length_km))
+}
+
+/// Writes a line in the short form of the notation, e.g. `G100,S50,G200`.
+/// Segments without a terrain are written as `?`.
+pub fn format(line: &Line) -> String {
+    line.segments()
+        .map(|segment| {
+            let symbol = segment.terrain().map_or("?", |terrain| terrain.symbol());
+            format!("{symbol}{}", segment.length_km())
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terrains_and_lengths(line: &Line) -> Vec<(Option<Terrain>, f64)> {
+        line.segments()
+            .map(|segment| (Terrain::from_medium(&segment.medium()), segment.length_km()))
+            .collect()
+    }
+
+    #[test]
+    fn parse_long_form() {
+        let line = parse("land:100 sea:50 land:200").unwrap();
+        assert_eq!(
+            terrains_and_lengths(&line),
+            [
+                (Some(Terrain::Ground), 100.0),
+                (Some(Terrain::Sea), 50.0),
+                (Some(Terrain::Ground), 200.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_short_form() {
+        let line = parse("G100,S50.5,g200").unwrap();
+        assert_eq!(
+            terrains_and_lengths(&line),
+            [
+                (Some(Terrain::Ground), 100.0),
+                (Some(Terrain::Sea), 50.5),
+                (Some(Terrain::Ground), 200.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_malformed() {
+        for notation in [
+            "",
+            " , ",
+            "water:10",
+            "land:",
+            "S",
+            "G-5",
+            "G0",
+            "Gabc",
+            "100",
+            "land:1e400",
+        ] {
+            assert!(parse(notation).is_err(), "`{notation}` should not parse.");
+        }
+    }
+
+    #[test]
+    fn format_round_trips() {
+        let notation = "G100,S50.5,G200";
+        assert_eq!(format(&parse(notation).unwrap()), notation);
+    }
+}
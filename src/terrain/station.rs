@@ -0,0 +1,59 @@
+use crate::terrain::defaults;
+
+/// The transmitting station at the start of a line.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Transmitter {
+    /// Height of the antenna in m.
+    pub height_m: f64,
+    /// Power in W.
+    pub power_w: f64,
+    /// Frequency in MHz.
+    pub frequency_mhz: f64,
+    pub polarization: Polarization,
+}
+
+impl Default for Transmitter {
+    fn default() -> Self {
+        defaults::TRANSMITTER
+    }
+}
+
+/// The receiving station at the distance being evaluated along a line.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Receiver {
+    /// Height of the antenna in m.
+    pub height_m: f64,
+}
+
+impl Default for Receiver {
+    fn default() -> Self {
+        defaults::RECEIVER
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Polarization {
+    Horizontal,
+    Vertical,
+}
+
+impl Polarization {
+    /// The value LFMF uses for the polarization.
+    pub const fn lfmf_code(self) -> i32 {
+        match self {
+            Self::Horizontal => 0,
+            Self::Vertical => 1,
+        }
+    }
+
+    pub const fn from_lfmf_code(code: i32) -> Option<Self> {
+        match code {
+            0 => Some(Self::Horizontal),
+            1 => Some(Self::Vertical),
+            _ => None,
+        }
+    }
+}
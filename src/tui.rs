@@ -0,0 +1,542 @@
+//! An interactive terminal view of the field strength along the loaded lines.
+
+use anyhow::Result;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::symbols::Marker;
+use ratatui::text::{Line as TextLine, Span};
+use ratatui::widgets::{
+    Axis, Block, Chart, Dataset, GraphType, List, ListItem, ListState, Paragraph,
+};
+use ratatui::{DefaultTerminal, Frame};
+use rayon::prelude::*;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::time::Duration;
+use wave_propagation::find_max_distance::{
+    calc_millington_field_strength_for_line_at_km,
+    explain_millington_field_strength_for_line_at_km, find_max_distance_for_line,
+    MillingtonExplanation, MillingtonTerm,
+};
+use wave_propagation::propagation_model::Lfmf;
+use wave_propagation::scenario::Scenario;
+use wave_propagation::terrain::defaults::Terrain;
+use wave_propagation::terrain::Line;
+
+/// Points of the chart of a line.
+const CURVE_SAMPLES: usize = 200;
+/// Fraction of the line's length that the cursor moves with every key press.
+const CURSOR_STEPS: f64 = 100.0;
+
+const KEYS: &str =
+    "↑↓ line  ←→ cursor (shift ×10)  +/- threshold  t toggle terrain  r reset  q quit";
+
+/// A result of a calculation that ran in the background, for the `version` of the line at `index`.
+enum Update {
+    Curve {
+        index: usize,
+        version: u64,
+        points: Result<Vec<(f64, f64)>>,
+    },
+    Coverage {
+        index: usize,
+        version: u64,
+        threshold: f64,
+        distance: Option<f64>,
+    },
+}
+
+/// What's known about a line, which is forgotten whenever the line changes.
+#[derive(Default)]
+struct LineState {
+    version: u64,
+    curve: Option<Result<Vec<(f64, f64)>, String>>,
+    curve_pending: bool,
+    /// The threshold and the maximum distance for it.
+    coverage: Option<(f64, Option<f64>)>,
+    coverage_pending: bool,
+}
+
+/// The selected line, its version and the cursor's distance.
+type CursorKey = (usize, u64, f64);
+
+struct App {
+    original_lines: Vec<Line>,
+    lines: Vec<Line>,
+    states: Vec<LineState>,
+    scenario: Scenario,
+    list: ListState,
+    cursor_field: Option<(CursorKey, Result<MillingtonExplanation, String>)>,
+    threshold: f64,
+    cursor_km: f64,
+    sender: Sender<Update>,
+    receiver: Receiver<Update>,
+}
+
+pub fn run(lines: Vec<Line>, scenario: Scenario, threshold: f64) -> Result<()> {
+    let mut terminal = ratatui::init();
+    let result = App::new(lines, scenario, threshold).run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+impl App {
+    fn new(lines: Vec<Line>, scenario: Scenario, threshold: f64) -> Self {
+        let (sender, receiver) = channel();
+        let cursor_km = lines.first().map_or(0.0, |line| line.max_distance() / 2.0);
+        Self {
+            original_lines: lines.clone(),
+            states: lines.iter().map(|_| LineState::default()).collect(),
+            lines,
+            scenario,
+            list: ListState::default().with_selected(Some(0)),
+            cursor_field: None,
+            threshold,
+            cursor_km,
+            sender,
+            receiver,
+        }
+    }
+
+    fn selected(&self) -> usize {
+        self.list.selected().unwrap_or(0)
+    }
+
+    fn run(mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        loop {
+            self.request_calculations();
+            self.update_cursor_field();
+            terminal.draw(|frame| self.draw(frame))?;
+            if event::poll(Duration::from_millis(100))? {
+                if let Event::Key(key) = event::read()? {
+                    if key.kind == KeyEventKind::Press && !self.handle_key(key.code) {
+                        return Ok(());
+                    }
+                }
+            }
+            while let Ok(update) = self.receiver.try_recv() {
+                self.apply(update);
+            }
+        }
+    }
+
+    /// Returns whether to keep running.
+    fn handle_key(&mut self, key: KeyCode) -> bool {
+        let line_km = self.lines[self.selected()].max_distance();
+        let step_km = line_km / CURSOR_STEPS;
+        match key {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Up | KeyCode::Char('k') => self.select(self.selected().saturating_sub(1)),
+            KeyCode::Down | KeyCode::Char('j') => self.select(self.selected() + 1),
+            KeyCode::Left | KeyCode::Char('h') => self.move_cursor(-step_km),
+            KeyCode::Right | KeyCode::Char('l') => self.move_cursor(step_km),
+            KeyCode::Char('H') => self.move_cursor(-10.0 * step_km),
+            KeyCode::Char('L') => self.move_cursor(10.0 * step_km),
+            KeyCode::Char('+') | KeyCode::Char('=') => self.threshold += 1.0,
+            KeyCode::Char('-') => self.threshold -= 1.0,
+            KeyCode::Char('t') => self.toggle_terrain(),
+            KeyCode::Char('r') => {
+                let index = self.selected();
+                self.replace_line(index, self.original_lines[index].clone());
+            }
+            _ => {}
+        }
+        true
+    }
+
+    fn select(&mut self, index: usize) {
+        let index = index.min(self.lines.len() - 1);
+        self.list.select(Some(index));
+        self.cursor_km = self.cursor_km.min(self.lines[index].max_distance());
+    }
+
+    fn move_cursor(&mut self, km: f64) {
+        let line_km = self.lines[self.selected()].max_distance();
+        self.cursor_km = (self.cursor_km + km).clamp(line_km / CURSOR_STEPS, line_km);
+    }
+
+    /// Swaps the segment under the cursor between land and sea.
+    fn toggle_terrain(&mut self) {
+        let index = self.selected();
+        let line = &self.lines[index];
+        let Some((segment_index, _)) = segment_at(line, self.cursor_km) else {
+            return;
+        };
+        let terrain = match line.segments().nth(segment_index).and_then(|s| s.terrain()) {
+            Some(Terrain::Ground) => Terrain::Sea,
+            _ => Terrain::Ground,
+        };
+        if let Some(toggled) = line.with_segment_terrain(segment_index, terrain) {
+            let toggled = self.scenario.apply(&toggled);
+            self.replace_line(index, toggled);
+        }
+    }
+
+    fn replace_line(&mut self, index: usize, line: Line) {
+        self.lines[index] = line;
+        let state = &mut self.states[index];
+        *state = LineState {
+            version: state.version + 1,
+            ..LineState::default()
+        };
+    }
+
+    /// Starts the calculations that the selected line is missing, one of each kind at a time.
+    fn request_calculations(&mut self) {
+        let index = self.selected();
+        let threshold = self.threshold;
+        let line = self.lines[index].clone();
+        let state = &mut self.states[index];
+        let version = state.version;
+        if state.curve.is_none() && !state.curve_pending {
+            state.curve_pending = true;
+            let sender = self.sender.clone();
+            let line = line.clone();
+            rayon::spawn(move || {
+                let points = calc_curve(&line);
+                let _ = sender.send(Update::Curve {
+                    index,
+                    version,
+                    points,
+                });
+            });
+        }
+        let coverage_is_current = state
+            .coverage
+            .is_some_and(|(coverage_threshold, _)| coverage_threshold == threshold);
+        if !coverage_is_current && !state.coverage_pending {
+            state.coverage_pending = true;
+            let sender = self.sender.clone();
+            rayon::spawn(move || {
+                let distance = find_max_distance_for_line(&Lfmf, threshold, &line).ok();
+                let _ = sender.send(Update::Coverage {
+                    index,
+                    version,
+                    threshold,
+                    distance,
+                });
+            });
+        }
+    }
+
+    fn update_cursor_field(&mut self) {
+        let index = self.selected();
+        let key = (index, self.states[index].version, self.cursor_km);
+        if self
+            .cursor_field
+            .as_ref()
+            .is_some_and(|(cached, _)| *cached == key)
+        {
+            return;
+        }
+        let field = explain_millington_field_strength_for_line_at_km(
+            &Lfmf,
+            &self.lines[index],
+            self.cursor_km,
+        )
+        .map_err(|error| format!("{error:#}"));
+        self.cursor_field = Some((key, field));
+    }
+
+    fn apply(&mut self, update: Update) {
+        match update {
+            Update::Curve {
+                index,
+                version,
+                points,
+            } => {
+                let state = &mut self.states[index];
+                if state.version == version {
+                    state.curve_pending = false;
+                    state.curve = Some(points.map_err(|error| format!("{error:#}")));
+                }
+            }
+            Update::Coverage {
+                index,
+                version,
+                threshold,
+                distance,
+            } => {
+                let state = &mut self.states[index];
+                if state.version == version {
+                    state.coverage_pending = false;
+                    state.coverage = Some((threshold, distance));
+                }
+            }
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, help] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(frame.area());
+        let [list, right] =
+            Layout::horizontal([Constraint::Length(24), Constraint::Fill(1)]).areas(main);
+        let readout_lines = self.readouts();
+        let [chart, segments, readouts] = Layout::vertical([
+            Constraint::Fill(1),
+            Constraint::Length(3),
+            Constraint::Length(readout_lines.len() as u16 + 2),
+        ])
+        .areas(right);
+
+        self.draw_list(frame, list);
+        self.draw_chart(frame, chart);
+        self.draw_segments(frame, segments);
+        self.draw_readouts(frame, readouts, readout_lines);
+        frame.render_widget(
+            Paragraph::new(KEYS).style(Style::new().fg(Color::DarkGray)),
+            help,
+        );
+    }
+
+    fn draw_list(&mut self, frame: &mut Frame, area: Rect) {
+        let items = self
+            .lines
+            .iter()
+            .zip(&self.states)
+            .map(|(line, state)| {
+                let coverage = match state.coverage {
+                    Some((threshold, Some(distance))) if threshold == self.threshold => {
+                        format!("{distance:7.1} km")
+                    }
+                    Some((threshold, None)) if threshold == self.threshold => "      - ".into(),
+                    _ => "        ".into(),
+                };
+                ListItem::new(format!("{:6.1}° {coverage}", line.angle()))
+            })
+            .collect::<Vec<_>>();
+        let list = List::new(items)
+            .block(Block::bordered().title("Lines"))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, area, &mut self.list);
+    }
+
+    fn draw_chart(&self, frame: &mut Frame, area: Rect) {
+        let index = self.selected();
+        let line = &self.lines[index];
+        let title = format!("Field strength at {}°", line.angle());
+        let block = Block::bordered().title(title);
+        let points = match &self.states[index].curve {
+            Some(Ok(points)) => points,
+            Some(Err(error)) => {
+                frame.render_widget(Paragraph::new(error.as_str()).block(block), area);
+                return;
+            }
+            None => {
+                frame.render_widget(Paragraph::new("Calculating…").block(block), area);
+                return;
+            }
+        };
+
+        let max_km = line.max_distance();
+        let (mut min_e, mut max_e) = points
+            .iter()
+            .fold((self.threshold, self.threshold), |(min, max), &(_, e)| {
+                (min.min(e), max.max(e))
+            });
+        min_e = (min_e - 5.0).floor();
+        max_e = (max_e + 5.0).ceil();
+        let threshold = [(0.0, self.threshold), (max_km, self.threshold)];
+        let cursor = [(self.cursor_km, min_e), (self.cursor_km, max_e)];
+        let coverage = match self.states[index].coverage {
+            Some((threshold, Some(distance))) if threshold == self.threshold => {
+                vec![(distance, min_e), (distance, max_e)]
+            }
+            _ => Vec::new(),
+        };
+
+        let datasets = vec![
+            dataset("E", points, Color::Cyan),
+            dataset("threshold", &threshold, Color::Red),
+            dataset("cursor", &cursor, Color::Yellow),
+            dataset("coverage", &coverage, Color::Green),
+        ];
+        let x_labels = [0.0, max_km / 2.0, max_km].map(|km| format!("{km:.0}"));
+        let y_labels = [min_e, (min_e + max_e) / 2.0, max_e].map(|e| format!("{e:.0}"));
+        let chart = Chart::new(datasets)
+            .block(block)
+            .x_axis(
+                Axis::default()
+                    .title("km")
+                    .bounds([0.0, max_km])
+                    .labels(x_labels),
+            )
+            .y_axis(
+                Axis::default()
+                    .title("dB(uV)/m")
+                    .bounds([min_e, max_e])
+                    .labels(y_labels),
+            );
+        frame.render_widget(chart, area);
+    }
+
+    /// Draws the segments as a bar in proportion to their lengths, with the one under the cursor highlighted.
+    fn draw_segments(&self, frame: &mut Frame, area: Rect) {
+        let line = &self.lines[self.selected()];
+        let block = Block::bordered().title("Segments");
+        let width = block.inner(area).width as f64;
+        let under_cursor = segment_at(line, self.cursor_km).map(|(index, _)| index);
+        let mut start_km = 0.0;
+        let mut start_column = 0;
+        let spans = line
+            .segments()
+            .enumerate()
+            .map(|(i, segment)| {
+                start_km += segment.length_km();
+                let end_column = (start_km / line.max_distance() * width).round() as usize;
+                let columns = end_column.saturating_sub(start_column);
+                start_column = end_column;
+                let (symbol, color) = match segment.terrain() {
+                    Some(Terrain::Ground) => ("G", Color::Green),
+                    Some(Terrain::Sea) => ("S", Color::Blue),
+                    None => ("?", Color::Gray),
+                };
+                let mut style = Style::new().bg(color).fg(Color::Black);
+                if Some(i) == under_cursor {
+                    style = style.add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+                }
+                let text = format!("{symbol:<columns$}")
+                    .chars()
+                    .take(columns)
+                    .collect::<String>();
+                Span::styled(text, style)
+            })
+            .collect::<Vec<_>>();
+        frame.render_widget(Paragraph::new(TextLine::from(spans)).block(block), area);
+    }
+
+    fn draw_readouts(&self, frame: &mut Frame, area: Rect, readouts: Vec<String>) {
+        let text = readouts.into_iter().map(TextLine::from).collect::<Vec<_>>();
+        frame.render_widget(
+            Paragraph::new(text).block(Block::bordered().title("Readouts")),
+            area,
+        );
+    }
+
+    /// The cursor, the segment under it and the field strength there with every term of Millington's method,
+    /// the forward ones next to the reverse ones.
+    fn readouts(&self) -> Vec<String> {
+        let line = &self.lines[self.selected()];
+        let distance = self.cursor_km;
+        let mut text = vec![format!(
+            "Cursor: {distance:.2} km    Threshold: {:.1} dB(uV)/m",
+            self.threshold
+        )];
+        if let Some((index, start_km)) = segment_at(line, distance) {
+            let segment = line.segments().nth(index).expect("The segment exists.");
+            let medium = segment.medium();
+            let terrain = segment.terrain().map_or("Custom", |terrain| terrain.name());
+            text.push(format!(
+                "Segment #{}: {terrain} from {start_km:.2} to {:.2} km, ε = {}, σ = {} S/m",
+                index + 1,
+                start_km + segment.length_km(),
+                medium.epsilon,
+                medium.sigma
+            ));
+        }
+        match self.cursor_field.as_ref().map(|(_, field)| field) {
+            Some(Ok(explanation)) => {
+                text.push(format!(
+                    "E: {:.2} dB(uV)/m    Forward sum: {:.2}    Reverse sum: {:.2}",
+                    explanation.field_strength, explanation.forward, explanation.reverse
+                ));
+                text.push(format!("{:<34} │ {}", "Forward terms", "Reverse terms"));
+                for (forward, reverse) in explanation
+                    .forward_terms
+                    .iter()
+                    .zip(&explanation.reverse_terms)
+                {
+                    text.push(format!("{:<34} │ {}", term(forward), term(reverse)));
+                }
+            }
+            Some(Err(error)) => text.push(format!("E: {error}")),
+            None => {}
+        }
+        text
+    }
+}
+
+/// A term of Millington's method as its ground, distance and signed field strength.
+fn term(term: &MillingtonTerm) -> String {
+    let ground = term.terrain.map_or("Custom", |terrain| terrain.name());
+    let sign = if term.subtracted { '-' } else { '+' };
+    format!(
+        "{ground:<8} {:>9.2} km {:>10}",
+        term.distance_km,
+        format!("{sign}{:.2}", term.field_strength)
+    )
+}
+
+fn dataset<'a>(name: &'a str, points: &'a [(f64, f64)], color: Color) -> Dataset<'a> {
+    Dataset::default()
+        .name(name)
+        .marker(Marker::Braille)
+        .graph_type(GraphType::Line)
+        .style(Style::new().fg(color))
+        .data(points)
+}
+
+fn calc_curve(line: &Line) -> Result<Vec<(f64, f64)>> {
+    let step_km = line.max_distance() / CURVE_SAMPLES as f64;
+    (1..=CURVE_SAMPLES)
+        .into_par_iter()
+        .map(|sample| {
+            let distance = step_km * sample as f64;
+            calc_millington_field_strength_for_line_at_km(&Lfmf, line, distance)
+                .map(|millington| (distance, millington.field_strength))
+        })
+        .collect()
+}
+
+/// The index and the start in km of the segment that contains `distance_km`.
+fn segment_at(line: &Line, distance_km: f64) -> Option<(usize, f64)> {
+    let mut start_km = 0.0;
+    for (i, segment) in line.segments().enumerate() {
+        let end_km = start_km + segment.length_km();
+        if distance_km <= end_km {
+            return Some((i, start_km));
+        }
+        start_km = end_km;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_readouts_follow_the_cursor_and_the_terrain() {
+        let line = Line::builder(0.0)
+            .segment(Terrain::Ground, 100.0)
+            .segment(Terrain::Sea, 100.0)
+            .build()
+            .unwrap();
+        let mut app = App::new(vec![line], Scenario::default(), 43.2);
+        app.move_cursor(50.0);
+        app.update_cursor_field();
+        assert_eq!(app.cursor_km, 150.0);
+        let readouts = app.readouts();
+        assert!(readouts[1].starts_with("Segment #2: Sea"), "{readouts:?}");
+        // Two segments have three terms each way.
+        assert_eq!(readouts.len(), 7, "{readouts:?}");
+        assert!(
+            readouts[5].starts_with("Sea         100.00 km     -"),
+            "{readouts:?}"
+        );
+        assert!(
+            readouts[6].starts_with("Sea         150.00 km     +"),
+            "{readouts:?}"
+        );
+
+        app.toggle_terrain();
+        assert_eq!(app.states[0].version, 1);
+        app.update_cursor_field();
+        let readouts = app.readouts();
+        assert!(readouts[1].starts_with("Segment #2: Land"), "{readouts:?}");
+        assert!(
+            readouts[4..].iter().all(|row| !row.contains("Sea")),
+            "{readouts:?}"
+        );
+    }
+}
@@ -0,0 +1,155 @@
+use serde_json::{json, Value};
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use wave_propagation::server::{Limits, Server};
+use wave_propagation::{
+    calc_millington_field_strength_for_line_at_km, find_max_distance_for_line, notation, Lfmf,
+};
+
+const PATH: &str = "G38,S61,G191";
+
+fn start(limits: Limits) -> SocketAddr {
+    let server = Server::bind("127.0.0.1:0", 2, limits).unwrap();
+    let address = server.local_addr().unwrap();
+    std::thread::spawn(move || server.run());
+    address
+}
+
+/// Sends a request and returns the status code and the JSON body of the response.
+/// It uses HTTP/1.0 so that the response isn't chunked.
+fn request(address: SocketAddr, method: &str, url: &str, body: &str) -> (u16, Value) {
+    let mut stream = TcpStream::connect(address).unwrap();
+    write!(
+        stream,
+        "{method} {url} HTTP/1.0\r\nHost: {address}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+    .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let status = response[9..12].parse().unwrap();
+    let (_, body) = response.split_once("\r\n\r\n").unwrap();
+    (status, serde_json::from_str(body).unwrap())
+}
+
+fn post(address: SocketAddr, url: &str, body: Value) -> (u16, Value) {
+    request(address, "POST", url, &body.to_string())
+}
+
+#[test]
+fn health() {
+    let address = start(Limits::default());
+    let (status, body) = request(address, "GET", "/health", "");
+    assert_eq!(status, 200);
+    assert_eq!(body, json!({ "status": "ok" }));
+}
+
+#[test]
+fn field_strength_matches_library() {
+    let address = start(Limits::default());
+    let (status, body) = post(
+        address,
+        "/field-strength",
+        json!({ "path": PATH, "angle": 50, "distance_km": 87 }),
+    );
+    assert_eq!(status, 200, "{body}");
+    let expected =
+        calc_millington_field_strength_for_line_at_km(&Lfmf, &notation::parse(PATH).unwrap(), 87.0)
+            .unwrap();
+    assert_eq!(body["angle"], 50.0);
+    assert_eq!(body["field_strength"], expected.field_strength);
+    assert_eq!(body["forward"], expected.forward);
+    assert_eq!(body["reverse"], expected.reverse);
+}
+
+#[test]
+fn profile_samples_every_step() {
+    let address = start(Limits::default());
+    let (status, body) = post(
+        address,
+        "/profile",
+        json!({ "path": "G10", "step_km": 2.5 }),
+    );
+    assert_eq!(status, 200, "{body}");
+    let distances = body["samples"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|sample| sample["distance_km"].as_f64().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(distances, [2.5, 5.0, 7.5, 10.0]);
+}
+
+#[test]
+fn coverage_applies_scenario() {
+    let address = start(Limits::default());
+    let (status, body) = post(
+        address,
+        "/coverage",
+        json!({ "path": PATH, "min_field_strengths": [43.2, 10.0], "scenario": { "tx-power": 1000 } }),
+    );
+    assert_eq!(status, 200, "{body}");
+
+    let line = notation::parse(PATH).unwrap();
+    let line = wave_propagation::scenario::Scenario::default()
+        .with(wave_propagation::scenario::Parameter::TxPower, 1000.0)
+        .apply(&line);
+    let expected = find_max_distance_for_line(&Lfmf, 43.2, &line).unwrap();
+    assert_eq!(body["max_distances_km"], json!([expected, null]));
+}
+
+#[test]
+fn invalid_requests_are_rejected() {
+    let address = start(Limits {
+        max_samples: 10,
+        ..Limits::default()
+    });
+    let bad_requests = [
+        (
+            "/field-strength",
+            json!({ "path": "water:10", "distance_km": 1 }),
+        ),
+        (
+            "/field-strength",
+            json!({ "path": PATH, "distance_km": 1000 }),
+        ),
+        ("/field-strength", json!({ "path": PATH })),
+        (
+            "/field-strength",
+            json!({ "path": PATH, "distance_km": 1, "unknown": 1 }),
+        ),
+        (
+            "/coverage",
+            json!({ "path": PATH, "min_field_strengths": [] }),
+        ),
+        (
+            "/coverage",
+            json!({ "path": PATH, "min_field_strengths": [43.2], "scenario": { "power": 1 } }),
+        ),
+        (
+            "/coverage",
+            json!({ "path": PATH, "min_field_strengths": [43.2], "scenario": { "frequency": 100 } }),
+        ),
+        ("/profile", json!({ "path": PATH, "step_km": 0 })),
+        ("/profile", json!({ "path": PATH, "step_km": 1 })),
+    ];
+    for (url, body) in bad_requests {
+        let (status, response) = post(address, url, body.clone());
+        assert_eq!(status, 400, "{body} -> {response}");
+        assert!(response["error"].is_string());
+    }
+
+    assert_eq!(request(address, "POST", "/coverage", "{").0, 400);
+    assert_eq!(request(address, "GET", "/coverage", "").0, 405);
+    assert_eq!(request(address, "GET", "/nothing", "").0, 404);
+}
+
+#[test]
+fn large_bodies_are_rejected() {
+    let address = start(Limits {
+        max_body_bytes: 100,
+        ..Limits::default()
+    });
+    let body = json!({ "path": "G1,".repeat(50), "min_field_strengths": [43.2] });
+    assert_eq!(post(address, "/coverage", body).0, 413);
+}